@@ -0,0 +1,753 @@
+//! Generates code from the Lexicon documents of the `lexicons` directory:
+//!
+//! - `bundled_lexicons.rs`: the JSON source of the documents bundled with the
+//!   server (see `src/lexicon/bundled.rs`).
+//!
+//! - `lexicon_types.rs`: Rust types for the definitions of the documents,
+//!   including the parameters, bodies and errors of XRPC methods (see
+//!   `src/api/xrpc/lex.rs`).
+//!
+//! - `xrpc_router.rs`: the function dispatching XRPC requests to the handler
+//!   of their method (see `src/api/xrpc/mod.rs`).
+//!
+//! The documents of the `com.atproto` namespace are always included, since
+//! the other namespaces build on them. Other namespaces require the matching
+//! `ns-*` feature, such as `ns-app-bsky` for `app.bsky.*`.
+
+use {
+    schema::*,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fmt::Write,
+        path::{Path, PathBuf},
+    },
+};
+
+#[allow(dead_code)]
+#[path = "src/lexicon/schema.rs"]
+mod schema;
+
+/// The namespace whose documents are always included.
+const CORE_NAMESPACE: &str = "com.atproto";
+
+/// The path of the generated types within the crate.
+const TYPES_PATH: &str = "crate::api::xrpc::lex";
+
+/// The path of the model types within the crate.
+const MODEL_PATH: &str = "crate::api::xrpc::model";
+
+/// The type used for dynamically-typed values.
+const VALUE_TYPE: &str = "crate::dag_cbor::Value";
+
+/// The type used for errors returned by XRPC methods.
+const XRPC_ERROR_TYPE: &str = "crate::api::xrpc::error::XrpcError";
+
+fn main() {
+    println!("cargo:rerun-if-changed=lexicons");
+    println!("cargo:rerun-if-changed=src/lexicon/schema.rs");
+    println!("cargo:rerun-if-changed=src/api/xrpc");
+
+    let manifest_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+
+    let mut files = Vec::new();
+    collect_json_files(&manifest_dir.join("lexicons"), &mut files);
+    files.sort();
+
+    let mut docs = BTreeMap::new();
+    for path in files {
+        let json = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("can't read `{}`: {err}", path.display()));
+        let doc: LexiconDoc = serde_json::from_str(&json)
+            .unwrap_or_else(|err| panic!("invalid Lexicon `{}`: {err}", path.display()));
+
+        let ns = namespace(&doc.id);
+        if ns == CORE_NAMESPACE || feature_enabled(ns) {
+            if let Some(prev) = docs.insert(doc.id.clone(), (path, doc)) {
+                panic!("Lexicon `{}` is defined twice", prev.1.id);
+            }
+        }
+    }
+
+    write_file(
+        &out_dir.join("bundled_lexicons.rs"),
+        &generate_bundled(&docs),
+    );
+    write_file(
+        &out_dir.join("lexicon_types.rs"),
+        &TypeGenerator::new(&docs).generate(),
+    );
+    write_file(
+        &out_dir.join("xrpc_router.rs"),
+        &generate_router(&manifest_dir, &docs),
+    );
+}
+
+/// The loaded Lexicon documents, indexed by NSID, along with the path of the
+/// file they were loaded from.
+type Docs = BTreeMap<String, (PathBuf, LexiconDoc)>;
+
+/// Recursively collects the `.json` files of the provided directory.
+fn collect_json_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = std::fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("can't read `{}`: {err}", dir.display()));
+
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_json_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+}
+
+/// Writes a generated file.
+fn write_file(path: &Path, contents: &str) {
+    std::fs::write(path, contents)
+        .unwrap_or_else(|err| panic!("can't write `{}`: {err}", path.display()));
+}
+
+/// Returns the namespace of an NSID, made of its first two segments.
+fn namespace(nsid: &str) -> &str {
+    match nsid.match_indices('.').nth(1) {
+        Some((i, _)) => &nsid[..i],
+        None => nsid,
+    }
+}
+
+/// Returns whether the `ns-*` feature of the provided namespace is enabled.
+fn feature_enabled(ns: &str) -> bool {
+    let var = format!("CARGO_FEATURE_NS_{}", ns.replace('.', "_").to_uppercase());
+    std::env::var_os(var).is_some()
+}
+
+/// Generates the list of the bundled documents.
+fn generate_bundled(docs: &Docs) -> String {
+    let mut out = String::from("&[\n");
+    for (path, _) in docs.values() {
+        writeln!(out, "    include_str!({:?}),", path.display().to_string()).unwrap();
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// Generates the XRPC router.
+///
+/// A method is routed to `src/api/xrpc/<namespace>/<rest>.rs`, such as
+/// `com_atproto/repo_getRecord.rs` for `com.atproto.repo.getRecord`. Methods
+/// without a handler file are left out, and reported as not implemented.
+fn generate_router(manifest_dir: &Path, docs: &Docs) -> String {
+    let mut arms = String::new();
+    for (id, (_, doc)) in docs {
+        let Some(LexType::Query(_) | LexType::Procedure(_)) = doc.defs.get("main") else {
+            continue;
+        };
+
+        let ns = namespace(id);
+        if !feature_enabled(ns) {
+            continue;
+        }
+
+        let module = ns.replace('.', "_");
+        let file = id[ns.len() + 1..].replace('.', "_");
+        let path = manifest_dir
+            .join("src/api/xrpc")
+            .join(&module)
+            .join(format!("{file}.rs"));
+        if !path.exists() {
+            continue;
+        }
+
+        writeln!(
+            arms,
+            "        b{id:?} => Some(self::{module}::{file}::handler.into_handler().handle(req).await),"
+        )
+        .unwrap();
+    }
+
+    // Without any method, the request is left unused.
+    let req = if arms.is_empty() { "_req" } else { "req" };
+
+    format!(
+        "\
+/// Dispatches an XRPC request to the handler of the method named `nsid`.
+///
+/// Returns `None` if the server does not implement the method.
+async fn dispatch(nsid: &[u8], {req}: &mut Request) -> Option<Response> {{
+    match nsid {{
+{arms}        _ => None,
+    }}
+}}
+"
+    )
+}
+
+/// Generates Rust types from Lexicon definitions.
+struct TypeGenerator<'a> {
+    docs: &'a Docs,
+    out: String,
+}
+
+/// A node of the module tree of the generated types.
+#[derive(Default)]
+struct Module<'a> {
+    /// The document defined in this module, if any.
+    doc: Option<&'a LexiconDoc>,
+    /// The child modules.
+    children: BTreeMap<&'a str, Module<'a>>,
+}
+
+impl<'a> TypeGenerator<'a> {
+    fn new(docs: &'a Docs) -> Self {
+        Self {
+            docs,
+            out: String::new(),
+        }
+    }
+
+    /// Generates the types of all the documents.
+    fn generate(mut self) -> String {
+        let mut root = Module::default();
+        for (id, (_, doc)) in self.docs {
+            let mut module = &mut root;
+            for segment in id.split('.') {
+                module = module.children.entry(segment).or_default();
+            }
+            module.doc = Some(doc);
+        }
+
+        self.module(&root);
+        self.out
+    }
+
+    /// Generates a module and its children.
+    fn module(&mut self, module: &Module) {
+        if let Some(doc) = module.doc {
+            self.doc(doc);
+        }
+
+        for (name, child) in &module.children {
+            let doc = match child.doc {
+                Some(doc) => doc_attr(&format!("`{}`", doc.id), doc.description.as_deref()),
+                None => String::new(),
+            };
+            writeln!(self.out, "{doc}pub mod {} {{", module_ident(name)).unwrap();
+            self.module(child);
+            self.out.push_str("}\n");
+        }
+    }
+
+    /// Generates the types of a document.
+    fn doc(&mut self, doc: &LexiconDoc) {
+        for (name, def) in &doc.defs {
+            self.def(&doc.id, name, def);
+        }
+    }
+
+    /// Generates the item(s) of a definition.
+    fn def(&mut self, ctx: &str, name: &str, def: &LexType) {
+        let reference = match name {
+            "main" => format!("`{ctx}`"),
+            _ => format!("`{ctx}#{name}`"),
+        };
+        let type_name = def_type_name(name, def);
+
+        match def {
+            LexType::Query(method) | LexType::Procedure(method) => self.method(ctx, method),
+            // Subscriptions are not served over plain HTTP, and their messages
+            // are framed differently.
+            LexType::Subscription(_) => (),
+            LexType::Record(record) => {
+                let doc = doc_attr(&reference, record.description.as_deref());
+                self.object(ctx, &type_name, &doc, &record.record);
+            }
+            LexType::Object(object) => {
+                let doc = doc_attr(&reference, object.description.as_deref());
+                self.object(ctx, &type_name, &doc, object);
+            }
+            LexType::Union(union) => {
+                let doc = doc_attr(&reference, union.description.as_deref());
+                self.union(ctx, &type_name, &doc, union);
+            }
+            LexType::Token(token) => {
+                let doc = doc_attr(&reference, token.description.as_deref());
+                let value = format!("{ctx}#{name}");
+                writeln!(self.out, "{doc}pub const {type_name}: &str = {value:?};").unwrap();
+            }
+            _ => {
+                let doc = doc_attr(&reference, description(def));
+                let ty = self.field_type(ctx, &type_name, def);
+                writeln!(self.out, "{doc}pub type {type_name} = {ty};").unwrap();
+            }
+        }
+    }
+
+    /// Generates the types of an XRPC method.
+    fn method(&mut self, ctx: &str, method: &LexXrpc) {
+        if let Some(params) = method.parameters.as_ref() {
+            if !params.properties.is_empty() {
+                let object = LexObject {
+                    required: params.required.clone(),
+                    properties: params.properties.clone(),
+                    ..Default::default()
+                };
+                let doc = doc_attr("The query parameters of the method.", None);
+                self.object(ctx, "Params", &doc, &object);
+            }
+        }
+
+        if let Some(input) = method.input.as_ref() {
+            self.body(ctx, "Input", "The input of the method.", input);
+        }
+        if let Some(output) = method.output.as_ref() {
+            self.body(ctx, "Output", "The output of the method.", output);
+        }
+
+        if !method.errors.is_empty() {
+            self.errors(&method.errors);
+        }
+    }
+
+    /// Generates the type of the body of an XRPC request or response.
+    ///
+    /// Only JSON bodies have a schema, and thus a type.
+    fn body(&mut self, ctx: &str, name: &str, summary: &str, body: &LexBody) {
+        let Some(schema) = body.schema.as_deref() else {
+            return;
+        };
+
+        let doc = doc_attr(summary, body.description.as_deref());
+        match schema {
+            LexType::Object(object) => self.object(ctx, name, &doc, object),
+            LexType::Union(union) => self.union(ctx, name, &doc, union),
+            _ => {
+                let ty = self.field_type(ctx, name, schema);
+                writeln!(self.out, "{doc}pub type {name} = {ty};").unwrap();
+            }
+        }
+    }
+
+    /// Generates the error enumeration of an XRPC method.
+    fn errors(&mut self, errors: &[LexXrpcError]) {
+        let mut variants = String::new();
+        let mut names = String::new();
+        let mut messages = String::new();
+
+        for error in errors {
+            let name = &error.name;
+            let doc = error
+                .description
+                .as_deref()
+                .map_or_else(String::new, |d| doc_attr(d, None));
+            let message = error.description.as_deref().unwrap_or(name);
+
+            writeln!(variants, "    {doc}    {name},").unwrap();
+            writeln!(names, "            Self::{name} => {name:?},").unwrap();
+            writeln!(messages, "            Error::{name} => {message:?},").unwrap();
+        }
+
+        write!(
+            self.out,
+            "\
+/// The errors that the method might return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {{
+{variants}}}
+
+impl Error {{
+    /// Returns the name of the error, as defined by the Lexicon.
+    pub const fn name(self) -> &'static str {{
+        match self {{
+{names}        }}
+    }}
+
+    /// Turns the error into an XRPC error with the provided message.
+    pub fn with_message(
+        self,
+        message: impl ::std::convert::Into<::std::borrow::Cow<'static, str>>,
+    ) -> {XRPC_ERROR_TYPE} {{
+        {XRPC_ERROR_TYPE}::custom(self.name(), message)
+    }}
+}}
+
+impl ::std::convert::From<Error> for {XRPC_ERROR_TYPE} {{
+    fn from(error: Error) -> Self {{
+        let message = match error {{
+{messages}        }};
+        error.with_message(message)
+    }}
+}}
+"
+        )
+        .unwrap();
+    }
+
+    /// Generates a struct for an object type.
+    fn object(&mut self, ctx: &str, name: &str, doc: &str, object: &LexObject) {
+        let mut fields = String::new();
+
+        for (prop, ty) in &object.properties {
+            let hint = format!("{name}{}", pascal_case(prop));
+            let mut rust_ty = self.field_type(ctx, &hint, ty);
+            let ident = field_ident(prop);
+
+            if let Some(desc) = description(ty) {
+                fields.push_str(&indent(&doc_attr(desc, None)));
+            }
+            if ident.trim_start_matches("r#") != prop {
+                writeln!(fields, "    #[serde(rename = {prop:?})]").unwrap();
+            }
+
+            if !object.required.contains(prop) && object.nullable.contains(prop) {
+                // A missing field is `None`, and `null` is `Some(None)`.
+                fields.push_str(
+                    "    #[serde(default, deserialize_with = \"crate::api::xrpc::lex::nullable\", \
+                     skip_serializing_if = \"::std::option::Option::is_none\")]\n",
+                );
+                rust_ty = format!("::std::option::Option<::std::option::Option<{rust_ty}>>");
+            } else if !object.required.contains(prop) {
+                fields.push_str(
+                    "    #[serde(default, skip_serializing_if = \"::std::option::Option::is_none\")]\n",
+                );
+                rust_ty = format!("::std::option::Option<{rust_ty}>");
+            } else if object.nullable.contains(prop) {
+                rust_ty = format!("::std::option::Option<{rust_ty}>");
+            }
+
+            writeln!(fields, "    pub {ident}: {rust_ty},").unwrap();
+        }
+
+        write!(
+            self.out,
+            "{doc}#[derive(Debug, Clone, ::serde::Serialize, ::serde::Deserialize)]\n\
+             pub struct {name} {{\n{fields}}}\n"
+        )
+        .unwrap();
+    }
+
+    /// Generates an enumeration for a union type.
+    ///
+    /// Values are told apart by their `$type` field. Open unions get an
+    /// additional `Unknown` variant holding values of any other type.
+    fn union(&mut self, ctx: &str, name: &str, doc: &str, union: &LexUnion) {
+        let mut seen = BTreeSet::new();
+        let mut variants = String::new();
+
+        for reference in &union.refs {
+            let (id, def_name, def) = self.resolve(ctx, reference);
+            if !matches!(def, LexType::Object(_) | LexType::Record(_)) {
+                panic!("union member `{reference}` of `{ctx}` is not an object");
+            }
+
+            let mut variant = variant_name(id, def_name);
+            if !seen.insert(variant.clone()) {
+                variant = pascal_case(&format!("{id}.{def_name}"));
+                seen.insert(variant.clone());
+            }
+
+            let ty = self.ref_type(ctx, reference);
+            let tag = qualify_ref(ctx, reference);
+            let alias = match def_name {
+                "main" => format!(", alias = \"{id}#main\""),
+                _ => String::new(),
+            };
+
+            writeln!(variants, "    #[serde(rename = {tag:?}{alias})]").unwrap();
+            writeln!(variants, "    {variant}(::std::boxed::Box<{ty}>),").unwrap();
+        }
+
+        if !union.closed {
+            writeln!(
+                variants,
+                "    /// An object whose `$type` is not part of the union."
+            )
+            .unwrap();
+            writeln!(variants, "    #[serde(untagged)]").unwrap();
+            writeln!(variants, "    Unknown({VALUE_TYPE}),").unwrap();
+        }
+
+        write!(
+            self.out,
+            "{doc}#[derive(Debug, Clone, ::serde::Serialize, ::serde::Deserialize)]\n\
+             #[serde(tag = \"$type\")]\n\
+             pub enum {name} {{\n{variants}}}\n"
+        )
+        .unwrap();
+    }
+
+    /// Returns the Rust type of a field.
+    ///
+    /// Inline objects and unions get their own type, named after `hint`.
+    fn field_type(&mut self, ctx: &str, hint: &str, ty: &LexType) -> String {
+        match ty {
+            LexType::String(string) => string_type(string),
+            LexType::Integer(_) => "i64".into(),
+            LexType::Boolean(_) => "bool".into(),
+            LexType::CidLink(_) => format!("{MODEL_PATH}::Cid"),
+            LexType::Bytes(_) | LexType::Blob(_) | LexType::Unknown(_) => VALUE_TYPE.into(),
+            LexType::Array(array) => {
+                let item = self.field_type(ctx, &format!("{hint}Item"), &array.items);
+                format!("::std::vec::Vec<{item}>")
+            }
+            LexType::Ref(r) => self.ref_type(ctx, &r.ref_),
+            LexType::Object(object) => {
+                let doc = doc_attr(
+                    &format!("An object of `{ctx}`."),
+                    object.description.as_deref(),
+                );
+                self.object(ctx, hint, &doc, object);
+                hint.into()
+            }
+            LexType::Union(union) => {
+                let doc = doc_attr(
+                    &format!("A union of `{ctx}`."),
+                    union.description.as_deref(),
+                );
+                self.union(ctx, hint, &doc, union);
+                hint.into()
+            }
+            LexType::Record(_)
+            | LexType::Query(_)
+            | LexType::Procedure(_)
+            | LexType::Subscription(_)
+            | LexType::Token(_) => panic!("`{ctx}` uses a primary type as a field"),
+        }
+    }
+
+    /// Returns the Rust type of the definition referred to by `reference`.
+    fn ref_type(&self, ctx: &str, reference: &str) -> String {
+        let (id, name, def) = self.resolve(ctx, reference);
+        match def {
+            LexType::Token(_) => "::std::string::String".into(),
+            _ => format!("{}::{}", module_path(id), def_type_name(name, def)),
+        }
+    }
+
+    /// Resolves a reference made from the document `ctx`.
+    fn resolve<'r>(&self, ctx: &'r str, reference: &'r str) -> (&'r str, &'r str, &'a LexType) {
+        let (id, name) = split_ref(ctx, reference);
+        let def = self
+            .docs
+            .get(id)
+            .and_then(|(_, doc)| doc.defs.get(name))
+            .unwrap_or_else(|| panic!("unresolved reference `{reference}` in `{ctx}`"));
+        (id, name, def)
+    }
+}
+
+/// Splits a reference into the NSID of its document and the name of the
+/// definition.
+fn split_ref<'a>(ctx: &'a str, reference: &'a str) -> (&'a str, &'a str) {
+    match reference.split_once('#') {
+        Some(("", name)) => (ctx, name),
+        Some((id, name)) => (id, name),
+        None => (reference, "main"),
+    }
+}
+
+/// Returns the fully-qualified form of a reference, as it appears in `$type`
+/// fields.
+fn qualify_ref(ctx: &str, reference: &str) -> String {
+    match split_ref(ctx, reference) {
+        (id, "main") => id.into(),
+        (id, name) => format!("{id}#{name}"),
+    }
+}
+
+/// Returns the Rust type of a string, depending on its format.
+fn string_type(string: &LexString) -> String {
+    let model = match string.format.as_deref() {
+        Some("at-identifier") => "AtIdentifier",
+        Some("at-uri") => "AtUri",
+        Some("cid") => "CidString",
+        Some("datetime") => "Datetime",
+        Some("did") => "Did",
+        Some("handle") => "Handle",
+        Some("language") => "Language",
+        Some("nsid") => "Nsid",
+        Some("record-key") => "RecordKey",
+        Some("tid") => "Tid",
+        Some("uri") => "Uri",
+        _ => return "::std::string::String".into(),
+    };
+    format!("{MODEL_PATH}::{model}")
+}
+
+/// Returns the description of a type.
+fn description(ty: &LexType) -> Option<&str> {
+    match ty {
+        LexType::Record(t) => t.description.as_deref(),
+        LexType::Query(t) | LexType::Procedure(t) => t.description.as_deref(),
+        LexType::Subscription(t) => t.description.as_deref(),
+        LexType::Object(t) => t.description.as_deref(),
+        LexType::Array(t) => t.description.as_deref(),
+        LexType::String(t) => t.description.as_deref(),
+        LexType::Integer(t) => t.description.as_deref(),
+        LexType::Boolean(t) => t.description.as_deref(),
+        LexType::Bytes(t) => t.description.as_deref(),
+        LexType::CidLink(t) => t.description.as_deref(),
+        LexType::Blob(t) => t.description.as_deref(),
+        LexType::Ref(t) => t.description.as_deref(),
+        LexType::Union(t) => t.description.as_deref(),
+        LexType::Unknown(t) => t.description.as_deref(),
+        LexType::Token(t) => t.description.as_deref(),
+    }
+}
+
+/// Returns the name of the item generated for a definition.
+fn def_type_name(name: &str, def: &LexType) -> String {
+    match (name, def) {
+        (_, LexType::Token(_)) => upper_snake_case(name),
+        ("main", LexType::Record(_)) => "Record".into(),
+        ("main", _) => "Main".into(),
+        _ => pascal_case(name),
+    }
+}
+
+/// Returns the name of the union variant for a definition.
+///
+/// This is the name of the definition, prefixed with the last segment of the
+/// NSID of its document unless that segment is `defs`.
+fn variant_name(id: &str, name: &str) -> String {
+    let last = id.rsplit('.').next().unwrap();
+    match (last, name) {
+        (_, "main") => pascal_case(last),
+        ("defs", _) => pascal_case(name),
+        _ => format!("{}{}", pascal_case(last), pascal_case(name)),
+    }
+}
+
+/// Returns the path of the module generated for a document.
+fn module_path(id: &str) -> String {
+    let mut path = String::from(TYPES_PATH);
+    for segment in id.split('.') {
+        path.push_str("::");
+        path.push_str(&module_ident(segment));
+    }
+    path
+}
+
+/// Returns the `#[doc]` attributes for an item.
+fn doc_attr(summary: &str, description: Option<&str>) -> String {
+    let mut out = format!("#[doc = {summary:?}]\n");
+    if let Some(description) = description {
+        out.push_str("#[doc = \"\"]\n");
+        writeln!(out, "#[doc = {description:?}]").unwrap();
+    }
+    out
+}
+
+/// Indents generated lines by one level.
+fn indent(s: &str) -> String {
+    s.lines().map(|line| format!("    {line}\n")).collect()
+}
+
+/// Converts a camelCase name to snake_case.
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else {
+            out.push('_');
+        }
+    }
+    out
+}
+
+/// Converts a camelCase or dotted name to PascalCase.
+fn pascal_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut upper = true;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Converts a camelCase name to UPPER_SNAKE_CASE.
+fn upper_snake_case(name: &str) -> String {
+    snake_case(name).to_ascii_uppercase()
+}
+
+/// Returns whether `s` is a reserved Rust keyword.
+fn is_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "as" | "async"
+            | "await"
+            | "break"
+            | "const"
+            | "continue"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "static"
+            | "struct"
+            | "trait"
+            | "true"
+            | "type"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+            | "abstract"
+            | "become"
+            | "box"
+            | "do"
+            | "final"
+            | "gen"
+            | "macro"
+            | "override"
+            | "priv"
+            | "try"
+            | "typeof"
+            | "unsized"
+            | "virtual"
+            | "yield"
+    )
+}
+
+/// Returns the identifier of a generated field.
+fn field_ident(name: &str) -> String {
+    let ident = snake_case(name);
+    match ident.as_str() {
+        "self" | "super" | "crate" => format!("{ident}_"),
+        _ if is_keyword(&ident) => format!("r#{ident}"),
+        _ => ident,
+    }
+}
+
+/// Returns the identifier of a generated module.
+fn module_ident(segment: &str) -> String {
+    field_ident(segment)
+}
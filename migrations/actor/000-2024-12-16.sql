@@ -0,0 +1,36 @@
+-- Each account has its own database, holding the data of its repository.
+
+-- The private key used to sign the commits of the repository.
+CREATE TABLE repo_signing_key (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    private_key BLOB NOT NULL
+) STRICT;
+
+-- The current commit of the repository.
+CREATE TABLE repo_root (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    cid TEXT NOT NULL,
+    rev TEXT NOT NULL
+) STRICT;
+
+-- The blocks (commits, MST nodes and records) of the repository.
+CREATE TABLE repo_blocks (
+    cid TEXT PRIMARY KEY,
+    repo_rev TEXT NOT NULL, -- the revision of the commit that created the block
+    content BLOB NOT NULL
+) STRICT;
+
+-- The records of the current commit of the repository.
+CREATE TABLE records (
+    collection TEXT NOT NULL,
+    rkey TEXT NOT NULL,
+    cid TEXT NOT NULL,
+    repo_rev TEXT NOT NULL, -- the revision of the commit that last wrote the record
+    PRIMARY KEY (collection, rkey)
+) STRICT;
+
+-- The private preferences of the account, such as those of `app.bsky`.
+CREATE TABLE account_preferences (
+    name TEXT PRIMARY KEY, -- the `$type` of the preference
+    value_json TEXT NOT NULL
+) STRICT;
@@ -0,0 +1,12 @@
+-- The blobs referenced by the records of the current commit of the
+-- repository. Blobs of the account that no record references anymore are
+-- removed by the blob garbage collector.
+CREATE TABLE record_blob (
+    blob_cid TEXT NOT NULL,
+    collection TEXT NOT NULL,
+    rkey TEXT NOT NULL,
+    PRIMARY KEY (blob_cid, collection, rkey),
+    FOREIGN KEY (collection, rkey) REFERENCES records (collection, rkey) ON DELETE CASCADE
+) STRICT;
+
+CREATE INDEX record_blob_record ON record_blob (collection, rkey);
@@ -1,5 +1,3 @@
-DROP TABLE IF EXISTS accounts;
-
 CREATE TABLE accounts (
     did TEXT PRIMARY KEY,
     email TEXT NOT NULL UNIQUE,
@@ -0,0 +1,4 @@
+-- The handle of each account, stored in lowercase.
+ALTER TABLE accounts ADD COLUMN handle TEXT;
+
+CREATE UNIQUE INDEX accounts_handle ON accounts (handle);
@@ -3,6 +3,7 @@
 use {
     crate::panic::trace_payload,
     futures::FutureExt,
+    http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full},
     hyper::{
         body::Bytes,
         header::{self, HeaderValue},
@@ -11,13 +12,36 @@ use {
     std::panic::AssertUnwindSafe,
 };
 
-mod xrpc;
+pub mod xrpc;
+
+/// The body of the requests handled by the [`handle_request`] function.
+///
+/// The body is boxed so that extractors can take ownership of it, leaving an
+/// empty body behind.
+pub type RequestBody = http_body_util::combinators::BoxBody<Bytes, hyper::Error>;
 
 /// The input request type used by the [`handle_request`] function.
-pub type Request = hyper::Request<hyper::body::Incoming>;
+pub type Request = hyper::Request<RequestBody>;
+
+/// An error that interrupts the body of a response while it is sent.
+pub type BodyError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The body of the responses returned by the [`handle_request`] function.
+///
+/// The body is boxed so that responses can be streamed rather than fully
+/// materialized in memory. Bodies whose length is known in advance are sent
+/// with a `Content-Length` header, others with chunked framing.
+pub type ResponseBody = UnsyncBoxBody<Bytes, BodyError>;
 
 /// The output response type used by the [`handle_request`] function.
-pub type Response = hyper::Response<http_body_util::Full<Bytes>>;
+pub type Response = hyper::Response<ResponseBody>;
+
+/// Creates a [`ResponseBody`] that holds `data` in a single chunk.
+pub fn full_body(data: impl Into<Bytes>) -> ResponseBody {
+    Full::new(data.into())
+        .map_err(|never| match never {})
+        .boxed_unsync()
+}
 
 /// Handles a request and returns an appropriate response.
 pub async fn handle_request(request: &mut Request) -> Response {
@@ -28,7 +52,7 @@ pub async fn handle_request(request: &mut Request) -> Response {
         Err(payload) => {
             trace_payload(&payload, None);
 
-            let mut response = Response::new("".into());
+            let mut response = Response::new(full_body(""));
             *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
             response
         }
@@ -58,14 +82,14 @@ async fn handle_request_inner(request: &mut Request) -> Response {
 /// Not additional information is provided, and the body of the response is left
 /// empty.
 fn not_found() -> Response {
-    let mut response = Response::new("".into());
+    let mut response = Response::new(full_body(""));
     *response.status_mut() = StatusCode::NOT_FOUND;
     response
 }
 
 // /// Creates a [`Response`] that indicates that the request was successful.
 // fn ok() -> Response {
-//     let mut response = Response::new("".into());
+//     let mut response = Response::new(full_body(""));
 //     *response.status_mut() = StatusCode::OK;
 //     response
 // }
@@ -77,7 +101,7 @@ const MIME_HTML: HeaderValue = HeaderValue::from_static("text/html");
 
 /// Creates a [`Response`] that contains `data` with the provided content type.
 fn file(data: &'static [u8], content_type: HeaderValue) -> Response {
-    let mut response = Response::new(data.into());
+    let mut response = Response::new(full_body(data));
     *response.status_mut() = StatusCode::OK;
     let headers = response.headers_mut();
     headers.insert(header::CONTENT_TYPE, content_type);
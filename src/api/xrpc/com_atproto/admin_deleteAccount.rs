@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::admin::delete_account::Input,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.admin.deleteAccount`
 #[instrument(name = "com.atproto.admin.deleteAccount", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
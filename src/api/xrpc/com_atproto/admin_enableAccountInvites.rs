@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::admin::enable_account_invites::Input,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.admin.enableAccountInvites`
 #[instrument(name = "com.atproto.admin.enableAccountInvites", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
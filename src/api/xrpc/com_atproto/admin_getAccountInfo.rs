@@ -1,7 +1,17 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::admin::get_account_info::{Output, Params},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.admin.getAccountInfo`
 #[instrument(name = "com.atproto.admin.getAccountInfo", skip_all)]
-pub async fn handler() {
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
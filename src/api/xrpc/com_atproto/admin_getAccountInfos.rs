@@ -1,7 +1,17 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::admin::get_account_infos::{Output, Params},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.admin.getAccountInfos`
 #[instrument(name = "com.atproto.admin.getAccountInfos", skip_all)]
-pub async fn handler() {
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
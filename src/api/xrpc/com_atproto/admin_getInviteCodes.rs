@@ -1,7 +1,17 @@
-use {crate::api::xrpc::error::XrpcError, tracing::instrument};
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::admin::get_invite_codes::{Output, Params},
+    },
+    tracing::instrument,
+};
 
-/// com.atproto.admin.getInviteCodes
+/// `com.atproto.admin.getInviteCodes`
 #[instrument(name = "com.atproto.admin.getInviteCodes", skip_all)]
-pub async fn handler() {
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
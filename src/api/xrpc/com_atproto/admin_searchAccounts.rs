@@ -1,7 +1,17 @@
-use {crate::api::xrpc::error::XrpcError, tracing::instrument};
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::admin::search_accounts::{Output, Params},
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.admin.getSubjectStatus`
-#[instrument(name = "com.atproto.admin.getSubjectStatus", skip_all)]
-pub async fn handler() {
+/// `com.atproto.admin.searchAccounts`
+#[instrument(name = "com.atproto.admin.searchAccounts", skip_all)]
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
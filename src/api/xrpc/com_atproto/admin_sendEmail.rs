@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::admin::send_email::{Input, Output},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.admin.sendEmail`
 #[instrument(name = "com.atproto.admin.sendEmail", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
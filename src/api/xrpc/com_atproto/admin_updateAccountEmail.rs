@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::admin::update_account_email::Input,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.admin.updateAccountEmail`
 #[instrument(name = "com.atproto.admin.updateAccountEmail", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
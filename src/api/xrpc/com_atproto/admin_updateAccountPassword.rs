@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::admin::update_account_password::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.admin.updateAccountHandle`
+/// `com.atproto.admin.updateAccountPassword`
 #[instrument(name = "com.atproto.admin.updateAccountPassword", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::admin::update_subject_status::{Input, Output},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.admin.updateSubjectStatus`
 #[instrument(name = "com.atproto.admin.updateSubjectStatus", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet},
+        lex::com::atproto::identity::get_recommended_did_credentials::Output,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.identity.getRecommendedDidCredentials`
 #[instrument(name = "com.atproto.identity.getRecommendedDidCredentials", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodGet) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,10 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{error::XrpcError, handler::MethodPost},
+    tracing::instrument,
+};
 
-/// `com.atproto.identity.getPrequestPlcOperationSignature`
+/// `com.atproto.identity.requestPlcOperationSignature`
 #[instrument(name = "com.atproto.identity.requestPlcOperationSignature", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,17 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::identity::resolve_handle::{Output, Params},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.identity.resolveHandle`
 #[instrument(name = "com.atproto.identity.resolveHandle", skip_all)]
-pub async fn handler() {
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
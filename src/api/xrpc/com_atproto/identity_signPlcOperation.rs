@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::identity::sign_plc_operation::{Input, Output},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.identity.signPlcOperation`
 #[instrument(name = "com.atproto.identity.signPlcOperation", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::identity::submit_plc_operation::Input,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.identity.submitPlcOperation`
 #[instrument(name = "com.atproto.identity.submitPlcOperation", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::identity::update_handle::Input,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.identity.updateHandle`
 #[instrument(name = "com.atproto.identity.updateHandle", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,17 @@
-use {crate::api::xrpc::error::XrpcError, tracing::instrument};
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::label::query_labels::{Output, Params},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.label.queryLabels`
 #[instrument(name = "com.atproto.label.queryLabels", skip_all)]
-pub async fn handler() {
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
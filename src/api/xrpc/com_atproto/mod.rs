@@ -67,3 +67,62 @@ pub mod sync_listBlobs;
 pub mod sync_listRepos;
 pub mod sync_notifyOfUpdate;
 pub mod sync_requestCrawl;
+
+use crate::{
+    api::xrpc::{
+        error::XrpcError,
+        model::{AtIdentifier, Did},
+    },
+    blob::BlobStore,
+    global::{
+        self,
+        database::{ActorStores, Queries},
+    },
+    repo::{RepoError, RepoStorage, RepoWriter},
+};
+
+/// Opens the repository designated by `repo`, which must be hosted by the
+/// server.
+///
+/// Handles are resolved using the accounts of the server, ignoring case.
+async fn open_repo(repo: &AtIdentifier) -> Result<RepoStorage, XrpcError> {
+    let state = global::get();
+    let did = match repo {
+        AtIdentifier::Did(did) => did.clone(),
+        AtIdentifier::Handle(handle) => {
+            state
+                .database
+                .connect()
+                .await?
+                .query_opt::<(Did,)>(
+                    "SELECT did FROM accounts WHERE handle = ?1 COLLATE NOCASE",
+                    [handle.as_str()],
+                )
+                .await?
+                .ok_or(RepoError::NotFound)?
+                .0
+        }
+    };
+
+    let conn = state
+        .actor_stores
+        .connect(&did)
+        .await?
+        .ok_or(RepoError::NotFound)?;
+    Ok(RepoStorage::new(conn, did))
+}
+
+/// Opens the repository of `did` for writing.
+///
+/// Accounts get their repository on their first write: the actor store, the
+/// signing key and an empty genesis commit are created if they are missing.
+async fn open_writer<'a, B: BlobStore>(
+    stores: &ActorStores,
+    blobs: &'a B,
+    did: &Did,
+) -> Result<RepoWriter<'a, B>, XrpcError> {
+    let conn = stores.create(did).await?.connect().await?;
+    let storage = RepoStorage::new(conn, did.clone());
+    storage.init(blobs).await?;
+    Ok(RepoWriter::new(storage, blobs).await?)
+}
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::moderation::create_report::{Input, Output},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.moderation.createReport`
 #[instrument(name = "com.atproto.moderation.createReport", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
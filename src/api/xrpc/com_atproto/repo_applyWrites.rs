@@ -1,7 +1,333 @@
-use tracing::instrument;
+use {
+    super::open_writer,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Auth, Json, MethodPost},
+            lex::com::atproto::repo::{
+                apply_writes::{
+                    CreateResult, DeleteResult, Input, InputWritesItem, Output, OutputResultsItem,
+                    UpdateResult,
+                },
+                defs::CommitMeta,
+            },
+            model::{AtUri, Cid, Did, Nsid, RecordKey, Tid},
+        },
+        blob::BlobStore,
+        dag_cbor::Value,
+        global,
+        lexicon::{LexiconRegistry, ValidationStatus},
+        repo::{CommitData, RepoWriter},
+    },
+    tracing::instrument,
+};
+
+/// The maximum number of writes in a single batch.
+const MAX_WRITES: usize = 200;
+
+/// What a [`Write`] does to its record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriteKind {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A write of the batch, checked before the repository is touched.
+struct Write<'a> {
+    kind: WriteKind,
+    collection: &'a Nsid,
+    rkey: RecordKey,
+    /// The path of the record, in the `<collection>/<rkey>` form.
+    path: String,
+    /// The new version of the record, unless it is deleted.
+    record: Option<&'a Value>,
+    validation_status: Option<ValidationStatus>,
+}
+
+impl<'a> Write<'a> {
+    /// Checks a write of the batch, validating its record if needed.
+    fn prepare(
+        item: &'a InputWritesItem,
+        validate: Option<bool>,
+        lexicons: &LexiconRegistry,
+    ) -> Result<Self, XrpcError> {
+        let (kind, collection, rkey, record) = match item {
+            InputWritesItem::ApplyWritesCreate(create) => {
+                let rkey = match &create.rkey {
+                    Some(rkey) => parse_rkey(rkey)?,
+                    None => RecordKey::from_tid(Tid::next()),
+                };
+                (
+                    WriteKind::Create,
+                    &create.collection,
+                    rkey,
+                    Some(&create.value),
+                )
+            }
+            InputWritesItem::ApplyWritesUpdate(update) => (
+                WriteKind::Update,
+                &update.collection,
+                parse_rkey(&update.rkey)?,
+                Some(&update.value),
+            ),
+            InputWritesItem::ApplyWritesDelete(delete) => (
+                WriteKind::Delete,
+                &delete.collection,
+                parse_rkey(&delete.rkey)?,
+                None,
+            ),
+        };
+
+        let validation_status = match record {
+            Some(record) => Some(lexicons.validate_record(
+                collection.as_str(),
+                rkey.as_str(),
+                record,
+                validate,
+            )?),
+            None => None,
+        };
+
+        Ok(Self {
+            kind,
+            collection,
+            path: format!("{collection}/{rkey}"),
+            rkey,
+            record,
+            validation_status,
+        })
+    }
+
+    /// Returns the result of the write, given the CID of the written record.
+    fn result(&self, did: &Did, cid: Option<Cid>) -> OutputResultsItem {
+        let uri = || {
+            AtUri::builder(did)
+                .collection(self.collection)
+                .rkey(&self.rkey)
+                .build()
+        };
+        let validation_status = self.validation_status.map(|s| s.as_str().to_owned());
+
+        match (self.kind, cid) {
+            (WriteKind::Create, Some(cid)) => {
+                OutputResultsItem::ApplyWritesCreateResult(Box::new(CreateResult {
+                    cid: cid.into(),
+                    uri: uri(),
+                    validation_status,
+                }))
+            }
+            (WriteKind::Update, Some(cid)) => {
+                OutputResultsItem::ApplyWritesUpdateResult(Box::new(UpdateResult {
+                    cid: cid.into(),
+                    uri: uri(),
+                    validation_status,
+                }))
+            }
+            _ => OutputResultsItem::ApplyWritesDeleteResult(Box::new(DeleteResult {})),
+        }
+    }
+}
+
+/// Parses the record key of a write.
+fn parse_rkey(rkey: &str) -> Result<RecordKey, XrpcError> {
+    RecordKey::try_from(Box::<str>::from(rkey))
+        .map_err(|_| XrpcError::invalid_request(format!("`{rkey}` is not a valid record key")))
+}
+
+/// Checks the writes of a batch, before the repository is touched.
+fn prepare<'a>(input: &'a Input, lexicons: &LexiconRegistry) -> Result<Vec<Write<'a>>, XrpcError> {
+    if input.writes.len() > MAX_WRITES {
+        return Err(XrpcError::invalid_request(format!(
+            "Too many writes, at most {MAX_WRITES} are allowed"
+        )));
+    }
+    input
+        .writes
+        .iter()
+        .map(|item| Write::prepare(item, input.validate, lexicons))
+        .collect()
+}
+
+/// Applies the writes of a batch in a single commit, returning the CID of
+/// each written record, in the order of the writes.
+///
+/// Creates fail if the record exists, and updates and deletes fail if it
+/// does not.
+async fn apply<B: BlobStore>(
+    writer: &RepoWriter<'_, B>,
+    swap_commit: Option<&Cid>,
+    writes: &[Write<'_>],
+) -> Result<(Vec<Option<Cid>>, Option<CommitData>), XrpcError> {
+    writer
+        .write(|tx| {
+            Box::pin(async move {
+                tx.check_swap_commit(swap_commit)?;
+
+                let mut cids = Vec::with_capacity(writes.len());
+                for write in writes {
+                    let exists = tx.get(&write.path).await?.is_some();
+                    let cid = match (write.kind, write.record) {
+                        (WriteKind::Create, _) if exists => {
+                            return Err(XrpcError::invalid_request(format!(
+                                "Record already exists at `{}`",
+                                write.path
+                            )));
+                        }
+                        (WriteKind::Update | WriteKind::Delete, _) if !exists => {
+                            return Err(XrpcError::invalid_request(format!(
+                                "Could not find record at `{}`",
+                                write.path
+                            )));
+                        }
+                        (_, Some(record)) => Some(tx.put(&write.path, record).await?.0),
+                        (_, None) => {
+                            tx.delete(&write.path).await?;
+                            None
+                        }
+                    };
+                    cids.push(cid);
+                }
+                Ok(cids)
+            })
+        })
+        .await
+}
 
 /// `com.atproto.repo.applyWrites`
+///
+/// All the writes are applied in a single commit. If any of them fails,
+/// none is applied.
 #[instrument(name = "com.atproto.repo.applyWrites", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodPost,
+    auth: Auth,
+    Json(input): Json<Input>,
+) -> Result<Json<Output>, XrpcError> {
+    auth.check_repo(&input.repo).await?;
+
+    let state = global::get();
+    let writes = prepare(&input, &state.lexicons)?;
+
+    let writer = open_writer(&state.actor_stores, &state.blob_store, &auth.did).await?;
+    let (cids, commit) = apply(&writer, input.swap_commit.as_deref(), &writes).await?;
+
+    let results = writes
+        .iter()
+        .zip(cids)
+        .map(|(write, cid)| write.result(&auth.did, cid))
+        .collect();
+
+    Ok(Json(Output {
+        commit: commit.map(|commit| CommitMeta {
+            cid: commit.cid.into(),
+            rev: commit.rev,
+        }),
+        results: Some(results),
+    }))
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn apply_write_batches() {
+    use crate::{
+        blob::MemoryBlobStore,
+        global::database::{TemporaryDatabase, ACTOR_MIGRATIONS},
+        repo::{RepoStorage, RepoTransaction, SigningKey},
+    };
+
+    let db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let did = Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap();
+    let storage = RepoStorage::new(db.connect().await.unwrap(), did.clone());
+    let key = SigningKey::generate();
+    storage.set_signing_key(&key).await.unwrap();
+    let blobs = MemoryBlobStore::new();
+    RepoTransaction::genesis(&storage)
+        .commit(&key, &blobs)
+        .await
+        .unwrap();
+    let writer = RepoWriter::new(storage, &blobs).await.unwrap();
+    let lexicons = LexiconRegistry::with_bundled();
+
+    let input = |writes: &[(&str, &str)]| -> Input {
+        let writes: Vec<_> = writes
+            .iter()
+            .map(|(kind, rkey)| {
+                serde_json::json!({
+                    "$type": format!("com.atproto.repo.applyWrites#{kind}"),
+                    "collection": "com.example.thing",
+                    "rkey": rkey,
+                    "value": { "$type": "com.example.thing", "version": kind },
+                })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({ "repo": did.as_str(), "writes": writes }))
+            .unwrap()
+    };
+    let get = |rkey: &'static str| {
+        let storage = writer.storage();
+        async move {
+            let mut tx = RepoTransaction::begin(storage).await.unwrap();
+            tx.get(&format!("com.example.thing/{rkey}")).await.unwrap()
+        }
+    };
+
+    // The results follow the order of the writes.
+    let batch = input(&[
+        ("create", "a"),
+        ("create", "b"),
+        ("update", "a"),
+        ("delete", "b"),
+    ]);
+    let writes = prepare(&batch, &lexicons).unwrap();
+    let (cids, commit) = apply(&writer, None, &writes).await.unwrap();
+    let results: Vec<_> = writes
+        .iter()
+        .zip(cids.iter().copied())
+        .map(|(write, cid)| write.result(&did, cid))
+        .collect();
+    assert!(matches!(
+        &results[..],
+        [
+            OutputResultsItem::ApplyWritesCreateResult(_),
+            OutputResultsItem::ApplyWritesCreateResult(_),
+            OutputResultsItem::ApplyWritesUpdateResult(_),
+            OutputResultsItem::ApplyWritesDeleteResult(_),
+        ]
+    ));
+    assert_ne!(cids[0], cids[2]);
+    assert_eq!(get("a").await, cids[2]);
+    assert_eq!(get("b").await, None);
+    let root = commit.unwrap().cid;
+    assert_eq!(writer.storage().root().await.unwrap().unwrap().cid, root);
+
+    // A failing write rolls back the whole batch.
+    for batch in [
+        input(&[("create", "c"), ("update", "missing")]),
+        input(&[("create", "c"), ("create", "a")]),
+        input(&[("create", "c"), ("delete", "missing")]),
+    ] {
+        let writes = prepare(&batch, &lexicons).unwrap();
+        assert!(matches!(
+            apply(&writer, None, &writes).await,
+            Err(err) if err.error == "InvalidRequest"
+        ));
+        assert_eq!(get("c").await, None);
+        assert_eq!(writer.storage().root().await.unwrap().unwrap().cid, root);
+    }
+
+    // The commit can be pinned.
+    let batch = input(&[("create", "c")]);
+    let writes = prepare(&batch, &lexicons).unwrap();
+    let other = Cid::compute(crate::api::xrpc::model::Codec::Raw, b"other");
+    assert!(matches!(
+        apply(&writer, Some(&other), &writes).await,
+        Err(err) if err.error == "InvalidSwap"
+    ));
+    apply(&writer, Some(&root), &writes).await.unwrap();
+
+    let batch = input(&[("create", "x"); MAX_WRITES + 1]);
+    assert!(prepare(&batch, &lexicons).is_err());
+    let batch = input(&[("create", "x"); MAX_WRITES]);
+    assert_eq!(prepare(&batch, &lexicons).unwrap().len(), MAX_WRITES);
 }
@@ -1,7 +1,149 @@
-use tracing::instrument;
+use {
+    super::open_writer,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Auth, Json, MethodPost},
+            lex::com::atproto::repo::{
+                create_record::{Input, Output},
+                defs::CommitMeta,
+            },
+            model::{AtUri, Did, RecordKey, Tid},
+        },
+        blob::BlobStore,
+        global::{self, database::ActorStores},
+        lexicon::LexiconRegistry,
+    },
+    tracing::instrument,
+};
+
+/// Creates a record in the repository of `did`, creating the repository if
+/// the account has none yet.
+async fn create_record<B: BlobStore>(
+    stores: &ActorStores,
+    blobs: &B,
+    lexicons: &LexiconRegistry,
+    did: &Did,
+    input: &Input,
+) -> Result<Output, XrpcError> {
+    let rkey = input
+        .rkey
+        .clone()
+        .unwrap_or_else(|| RecordKey::from_tid(Tid::next()));
+    let validation_status = lexicons.validate_record(
+        input.collection.as_str(),
+        rkey.as_str(),
+        &input.record,
+        input.validate,
+    )?;
+
+    let path = format!("{}/{}", input.collection, rkey);
+
+    let writer = open_writer(stores, blobs, did).await?;
+
+    let path = &path;
+    let (cid, commit) = writer
+        .write(|tx| {
+            Box::pin(async move {
+                tx.check_swap_commit(input.swap_commit.as_deref())?;
+                if tx.get(path).await?.is_some() {
+                    return Err(XrpcError::invalid_request(format!(
+                        "Record already exists at `{path}`"
+                    )));
+                }
+                Ok(tx.put(path, &input.record).await?.0)
+            })
+        })
+        .await?;
+
+    Ok(Output {
+        uri: AtUri::builder(did)
+            .collection(&input.collection)
+            .rkey(&rkey)
+            .build(),
+        cid: cid.into(),
+        commit: commit.map(|commit| CommitMeta {
+            cid: commit.cid.into(),
+            rev: commit.rev,
+        }),
+        validation_status: Some(validation_status.as_str().to_owned()),
+    })
+}
 
 /// `com.atproto.repo.createRecord`
 #[instrument(name = "com.atproto.repo.createRecord", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodPost,
+    auth: Auth,
+    Json(input): Json<Input>,
+) -> Result<Json<Output>, XrpcError> {
+    auth.check_repo(&input.repo).await?;
+
+    let state = global::get();
+    create_record(
+        &state.actor_stores,
+        &state.blob_store,
+        &state.lexicons,
+        &auth.did,
+        &input,
+    )
+    .await
+    .map(Json)
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn create_records_in_new_repos() {
+    use crate::{
+        blob::MemoryBlobStore,
+        repo::{RepoStorage, RepoTransaction},
+    };
+
+    let dir = std::env::temp_dir().join(format!("create-record-{}", Tid::next()));
+    let stores = ActorStores::new(dir.clone(), 4);
+    let blobs = MemoryBlobStore::new();
+    let lexicons = LexiconRegistry::with_bundled();
+    let did = Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap();
+    let input = |rkey: &str| -> Input {
+        serde_json::from_value(serde_json::json!({
+            "repo": did.as_str(),
+            "collection": "com.example.thing",
+            "rkey": rkey,
+            "record": { "$type": "com.example.thing" },
+        }))
+        .unwrap()
+    };
+
+    // The account has no repository yet.
+    assert!(stores.connect(&did).await.unwrap().is_none());
+
+    let first = create_record(&stores, &blobs, &lexicons, &did, &input("a"))
+        .await
+        .unwrap();
+    let first = first.commit.unwrap();
+
+    let conn = stores.connect(&did).await.unwrap().unwrap();
+    let storage = RepoStorage::new(conn, did.clone());
+    let root = storage.root().await.unwrap().unwrap();
+    assert_eq!(root.cid, *first.cid);
+    storage.signing_key().await.unwrap();
+
+    // The record was written on top of the genesis commit.
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    assert!(tx.get("com.example.thing/a").await.unwrap().is_some());
+    drop(tx);
+
+    // Later writes reuse the repository.
+    let second = create_record(&stores, &blobs, &lexicons, &did, &input("b"))
+        .await
+        .unwrap();
+    assert!(second.commit.unwrap().rev > first.rev);
+    assert!(matches!(
+        create_record(&stores, &blobs, &lexicons, &did, &input("a")).await,
+        Err(err) if err.error == "InvalidRequest"
+    ));
+
+    drop(storage);
+    drop(stores);
+    tokio::fs::remove_dir_all(dir).await.unwrap();
 }
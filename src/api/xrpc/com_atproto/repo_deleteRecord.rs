@@ -1,7 +1,54 @@
-use tracing::instrument;
+use {
+    super::open_writer,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Auth, Json, MethodPost},
+            lex::com::atproto::repo::{
+                defs::CommitMeta,
+                delete_record::{Input, Output},
+            },
+        },
+        global,
+        repo::RepoError,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.repo.deleteRecord`
+///
+/// Deleting a record that does not exist is not an error, and produces no
+/// commit.
 #[instrument(name = "com.atproto.repo.deleteRecord", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodPost,
+    auth: Auth,
+    Json(input): Json<Input>,
+) -> Result<Json<Output>, XrpcError> {
+    auth.check_repo(&input.repo).await?;
+
+    let path = format!("{}/{}", input.collection, input.rkey);
+
+    let state = global::get();
+    let writer = open_writer(&state.actor_stores, &state.blob_store, &auth.did).await?;
+
+    let (input, path) = (&input, &path);
+    let ((), commit) = writer
+        .write(|tx| {
+            Box::pin(async move {
+                tx.check_swap_commit(input.swap_commit.as_deref())?;
+                tx.check_swap_record(path, input.swap_record.as_deref().map(Some))
+                    .await?;
+                tx.delete(path).await?;
+                Ok::<_, RepoError>(())
+            })
+        })
+        .await?;
+
+    Ok(Json(Output {
+        commit: commit.map(|commit| CommitMeta {
+            cid: commit.cid.into(),
+            rev: commit.rev,
+        }),
+    }))
 }
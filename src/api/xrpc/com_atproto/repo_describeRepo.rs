@@ -1,7 +1,64 @@
-use tracing::instrument;
+use {
+    super::open_repo,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Json, MethodGet, Query},
+            lex::com::atproto::repo::describe_repo::{Output, Params},
+            model::Handle,
+        },
+        global::{self, database::Queries},
+    },
+    tracing::{instrument, warn},
+};
+
+/// The handle reported for accounts whose handle is missing or invalid.
+const INVALID_HANDLE: &str = "handle.invalid";
 
 /// `com.atproto.repo.describeRepo`
 #[instrument(name = "com.atproto.repo.describeRepo", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodGet,
+    Query(params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
+    let state = global::get();
+    let storage = open_repo(&params.repo).await?;
+    let did = storage.did().clone();
+
+    let handle = state
+        .database
+        .connect()
+        .await?
+        .query_opt::<(Option<Handle>,)>(
+            "SELECT handle FROM accounts WHERE did = ?1",
+            [did.as_str()],
+        )
+        .await?
+        .and_then(|(handle,)| handle);
+
+    let did_doc = match state.identity.resolve_did(&did).await {
+        Ok(Some(doc)) => doc,
+        ret => {
+            if let Err(err) = ret {
+                warn!("Failed to resolve the DID document of `{did}`: {err}");
+            }
+            return Err(XrpcError::invalid_request(format!(
+                "Could not resolve DID: {did}"
+            )));
+        }
+    };
+
+    let handle_is_correct = match &handle {
+        Some(handle) => state.identity.verify_handle(handle, &did, &did_doc).await,
+        None => false,
+    };
+
+    Ok(Json(Output {
+        handle: handle
+            .unwrap_or_else(|| Handle::try_from(Box::<str>::from(INVALID_HANDLE)).unwrap()),
+        collections: storage.collections().await?,
+        did,
+        did_doc,
+        handle_is_correct,
+    }))
 }
@@ -1,7 +1,46 @@
-use tracing::instrument;
+use {
+    super::open_repo,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Json, MethodGet, Query},
+            lex::com::atproto::repo::get_record::{Error, Output, Params},
+            model::AtUri,
+        },
+        repo::RepoTransaction,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.repo.getRecord`
+///
+/// When `cid` is provided, the record is only returned if it is the current
+/// version of the record.
 #[instrument(name = "com.atproto.repo.getRecord", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodGet,
+    Query(params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
+    let storage = open_repo(&params.repo).await?;
+    let uri = AtUri::builder(storage.did())
+        .collection(&params.collection)
+        .rkey(&params.rkey)
+        .build();
+
+    let mut tx = RepoTransaction::begin(&storage).await?;
+    let path = format!("{}/{}", params.collection, params.rkey);
+    let cid = match tx.get(&path).await? {
+        Some(cid) if params.cid.is_none_or(|pin| *pin == cid) => cid,
+        _ => {
+            return Err(
+                Error::RecordNotFound.with_message(format!("Could not locate record: {uri}"))
+            )
+        }
+    };
+
+    Ok(Json(Output {
+        value: storage.read_record(&cid).await?,
+        cid: Some(cid.into()),
+        uri,
+    }))
 }
@@ -1,7 +1,70 @@
-use tracing::instrument;
+use {
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Auth, MethodPost, RawBody},
+        },
+        global, identity,
+        repo::{
+            car::{CarLimits, CarReader},
+            decode_multikey, ImportError, ImportedRepo, RepoStorage,
+        },
+    },
+    tracing::{instrument, warn},
+};
 
 /// `com.atproto.repo.importRepo`
+///
+/// The body is a CAR file holding the whole repository of the account,
+/// which replaces its current repository at once, unless the current one
+/// has records and is at least as recent. The commit must be signed
+/// by the key listed in the DID document of the account.
+///
+/// The blobs referenced by the imported records are listed by
+/// `com.atproto.repo.listMissingBlobs` until they are uploaded.
 #[instrument(name = "com.atproto.repo.importRepo", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(_: MethodPost, auth: Auth, body: RawBody) -> Result<(), XrpcError> {
+    let state = global::get();
+    let limits = CarLimits::default();
+    if body
+        .content_length()
+        .is_some_and(|len| len > limits.max_size)
+    {
+        return Err(XrpcError::payload_too_large("The repository is too large"));
+    }
+
+    let doc = match state.identity.resolve_did(&auth.did).await {
+        Ok(Some(doc)) => doc,
+        ret => {
+            if let Err(err) = ret {
+                warn!(
+                    "Failed to resolve the DID document of `{}`: {err}",
+                    auth.did
+                );
+            }
+            return Err(XrpcError::invalid_request(format!(
+                "Could not resolve DID: {}",
+                auth.did
+            )));
+        }
+    };
+    let key = identity::signing_key(&doc)
+        .and_then(decode_multikey)
+        .ok_or_else(|| {
+            XrpcError::invalid_request("The DID document has no supported signing key")
+        })?;
+
+    let car = CarReader::new(body.0, limits)
+        .await
+        .map_err(ImportError::Car)?;
+    let repo = ImportedRepo::read(car, &auth.did, &key).await?;
+
+    let conn = state
+        .actor_stores
+        .create(&auth.did)
+        .await?
+        .connect()
+        .await?;
+    RepoStorage::new(conn, auth.did).import(&repo).await?;
+    Ok(())
 }
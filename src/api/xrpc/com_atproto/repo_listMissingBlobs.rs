@@ -1,7 +1,64 @@
-use tracing::instrument;
+use {
+    super::open_repo,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Auth, Json, MethodGet, Query},
+            lex::com::atproto::repo::list_missing_blobs::{Output, Params, RecordBlob},
+            model::{AtIdentifier, AtUri},
+        },
+        global,
+    },
+    tracing::instrument,
+};
+
+/// The number of blobs returned when `limit` is not specified.
+const DEFAULT_LIMIT: i64 = 500;
+/// The maximum number of blobs returned by a single call.
+const MAX_LIMIT: i64 = 1000;
 
 /// `com.atproto.repo.listMissingBlobs`
+///
+/// Lists the blobs referenced by the records of the account that the blob
+/// store does not hold, typically after `com.atproto.repo.importRepo`. The
+/// cursor is the CID of the last blob returned.
 #[instrument(name = "com.atproto.repo.listMissingBlobs", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodGet,
+    auth: Auth,
+    Query(params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(XrpcError::invalid_request(format!(
+            "`limit` must be between 1 and {MAX_LIMIT}"
+        )));
+    }
+    let limit = limit as usize;
+
+    let state = global::get();
+    let storage = open_repo(&AtIdentifier::Did(auth.did.clone())).await?;
+    let missing = storage
+        .missing_blobs(
+            params.cursor.as_deref().unwrap_or_default(),
+            limit,
+            &state.blob_store,
+        )
+        .await?;
+
+    let cursor = missing
+        .last()
+        .filter(|_| missing.len() == limit)
+        .map(|(cid, _, _)| cid.to_string());
+    let blobs = missing
+        .into_iter()
+        .map(|(cid, collection, rkey)| RecordBlob {
+            cid: cid.into(),
+            record_uri: AtUri::builder(&auth.did)
+                .collection(&collection)
+                .rkey(&rkey)
+                .build(),
+        })
+        .collect();
+    Ok(Json(Output { blobs, cursor }))
 }
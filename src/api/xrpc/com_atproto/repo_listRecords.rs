@@ -1,7 +1,70 @@
-use tracing::instrument;
+use {
+    super::open_repo,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Json, MethodGet, Query},
+            lex::com::atproto::repo::list_records::{Output, Params, Record},
+            model::AtUri,
+        },
+        repo::RepoTransaction,
+    },
+    tracing::instrument,
+};
+
+/// The number of records returned when `limit` is not specified.
+const DEFAULT_LIMIT: i64 = 50;
+/// The maximum number of records returned by a single call.
+const MAX_LIMIT: i64 = 100;
 
 /// `com.atproto.repo.listRecords`
+///
+/// Records are listed by descending record key, or ascending when `reverse`
+/// is set. The cursor is the record key of the last record returned, so
+/// pages never skip or repeat a record that was not itself written in the
+/// meantime.
 #[instrument(name = "com.atproto.repo.listRecords", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodGet,
+    Query(params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(XrpcError::invalid_request(format!(
+            "`limit` must be between 1 and {MAX_LIMIT}"
+        )));
+    }
+    let reverse = params.reverse.unwrap_or(false);
+
+    let storage = open_repo(&params.repo).await?;
+    let mut tx = RepoTransaction::begin(&storage).await?;
+
+    let entries = tx
+        .list_records(
+            &params.collection,
+            params.cursor.as_deref(),
+            reverse,
+            limit as usize,
+        )
+        .await?;
+
+    let mut records = Vec::with_capacity(entries.len());
+    let mut last = None;
+    for (rkey, cid) in entries {
+        records.push(Record {
+            uri: AtUri::builder(storage.did())
+                .collection(&params.collection)
+                .rkey(&rkey)
+                .build(),
+            cid: cid.into(),
+            value: storage.read_record(&cid).await?,
+        });
+        last = Some(rkey);
+    }
+
+    let cursor = match last {
+        Some(rkey) if records.len() == limit as usize => Some(rkey.as_str().to_owned()),
+        _ => None,
+    };
+    Ok(Json(Output { cursor, records }))
 }
@@ -1,7 +1,64 @@
-use tracing::instrument;
+use {
+    super::open_writer,
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Auth, Json, MethodPost},
+            lex::com::atproto::repo::{
+                defs::CommitMeta,
+                put_record::{Input, Output},
+            },
+            model::AtUri,
+        },
+        global,
+        repo::RepoError,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.repo.putRecord`
 #[instrument(name = "com.atproto.repo.putRecord", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodPost,
+    auth: Auth,
+    Json(input): Json<Input>,
+) -> Result<Json<Output>, XrpcError> {
+    auth.check_repo(&input.repo).await?;
+
+    let state = global::get();
+    let validation_status = state.lexicons.validate_record(
+        input.collection.as_str(),
+        input.rkey.as_str(),
+        &input.record,
+        input.validate,
+    )?;
+
+    let path = format!("{}/{}", input.collection, input.rkey);
+
+    let writer = open_writer(&state.actor_stores, &state.blob_store, &auth.did).await?;
+
+    let (input, path) = (&input, &path);
+    let (cid, commit) = writer
+        .write(|tx| {
+            Box::pin(async move {
+                tx.check_swap_commit(input.swap_commit.as_deref())?;
+                tx.check_swap_record(path, input.swap_record.as_ref().map(|swap| swap.as_deref()))
+                    .await?;
+                Ok::<_, RepoError>(tx.put(path, &input.record).await?.0)
+            })
+        })
+        .await?;
+
+    Ok(Json(Output {
+        uri: AtUri::builder(&auth.did)
+            .collection(&input.collection)
+            .rkey(&input.rkey)
+            .build(),
+        cid: cid.into(),
+        commit: commit.map(|commit| CommitMeta {
+            cid: commit.cid.into(),
+            rev: commit.rev,
+        }),
+        validation_status: Some(validation_status.as_str().to_owned()),
+    }))
 }
@@ -1,7 +1,73 @@
-use tracing::instrument;
+use {
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Auth, Json, MethodPost, RawBody},
+            lex::com::atproto::repo::upload_blob::Output,
+        },
+        blob::{sniff_mime_type, BlobRef, BlobStore, BlobStoreError, MIME_OCTET_STREAM, SNIFF_LEN},
+        global,
+        repo::RepoStorage,
+    },
+    futures::StreamExt,
+    std::io::ErrorKind,
+    tracing::instrument,
+};
 
 /// `com.atproto.repo.uploadBlob`
+///
+/// The body is streamed to the temporary area of the blob store. Its MIME
+/// type is sniffed from its content, as the `Content-Type` header sent by
+/// clients is not reliable.
+///
+/// Blobs already referenced by a record, such as those of an imported
+/// repository, are made permanent right away.
 #[instrument(name = "com.atproto.repo.uploadBlob", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(_: MethodPost, auth: Auth, body: RawBody) -> Result<Json<Output>, XrpcError> {
+    let state = global::get();
+    let max_size = state.max_blob_size;
+    let too_large =
+        || XrpcError::payload_too_large(format!("Blobs are limited to {max_size} bytes"));
+
+    if body.content_length().is_some_and(|len| len > max_size) {
+        return Err(too_large());
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    let mut size = 0;
+    let data = body.into_stream().map(|chunk| {
+        let chunk = chunk?;
+        size += chunk.len() as u64;
+        if size > max_size {
+            return Err(ErrorKind::FileTooLarge.into());
+        }
+        let missing = SNIFF_LEN.saturating_sub(head.len()).min(chunk.len());
+        head.extend_from_slice(&chunk[..missing]);
+        Ok(chunk)
+    });
+
+    let blob = match state.blob_store.put_temp(&auth.did, data).await {
+        Ok(blob) => blob,
+        Err(BlobStoreError::Io(err)) if err.kind() == ErrorKind::FileTooLarge => {
+            return Err(too_large());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if let Some(conn) = state.actor_stores.connect(&auth.did).await? {
+        RepoStorage::new(conn, auth.did)
+            .claim_blob(&blob.cid, &state.blob_store)
+            .await?;
+    }
+
+    let blob = BlobRef {
+        cid: blob.cid,
+        mime_type: sniff_mime_type(&head)
+            .unwrap_or(MIME_OCTET_STREAM)
+            .to_owned(),
+        size: blob.size,
+    };
+    Ok(Json(Output {
+        blob: blob.to_value(),
+    }))
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet},
+        lex::com::atproto::server::check_account_status::Output,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.checkAccountStatus`
+/// `com.atproto.server.checkAccountStatus`
 #[instrument(name = "com.atproto.server.checkAccountStatus", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodGet) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
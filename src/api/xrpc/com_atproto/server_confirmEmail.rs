@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::confirm_email::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.confirmEmail`
+/// `com.atproto.server.confirmEmail`
 #[instrument(name = "com.atproto.server.confirmEmail", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::create_app_password::{Input, Output},
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.createAppPassword`
+/// `com.atproto.server.createAppPassword`
 #[instrument(name = "com.atproto.server.createAppPassword", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::create_invite_code::{Input, Output},
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.createInviteCode`
+/// `com.atproto.server.createInviteCode`
 #[instrument(name = "com.atproto.server.createInviteCode", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
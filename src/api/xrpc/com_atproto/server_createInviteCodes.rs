@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::create_invite_codes::{Input, Output},
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.createInviteCodes`
+/// `com.atproto.server.createInviteCodes`
 #[instrument(name = "com.atproto.server.createInviteCodes", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::deactivate_account::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.deactivateAccount`
+/// `com.atproto.server.deactivateAccount`
 #[instrument(name = "com.atproto.server.deactivateAccount", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
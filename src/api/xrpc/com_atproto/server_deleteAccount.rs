@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::delete_account::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.deleteAccount`
+/// `com.atproto.server.deleteAccount`
 #[instrument(name = "com.atproto.server.deleteAccount", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
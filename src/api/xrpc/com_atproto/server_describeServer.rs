@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet},
+        lex::com::atproto::server::describe_server::Output,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.describeServer`
+/// `com.atproto.server.describeServer`
 #[instrument(name = "com.atproto.server.describeServer", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodGet) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
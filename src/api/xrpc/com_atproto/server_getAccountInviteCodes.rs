@@ -1,7 +1,17 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::server::get_account_invite_codes::{Output, Params},
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.getAccountInviteCodes`
+/// `com.atproto.server.getAccountInviteCodes`
 #[instrument(name = "com.atproto.server.getAccountInviteCodes", skip_all)]
-pub async fn handler() {
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet},
+        lex::com::atproto::server::get_session::Output,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.getSession`
+/// `com.atproto.server.getSession`
 #[instrument(name = "com.atproto.server.getSession", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodGet) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
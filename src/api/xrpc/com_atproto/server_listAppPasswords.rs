@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet},
+        lex::com::atproto::server::list_app_passwords::Output,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.listAppPasswords`
+/// `com.atproto.server.listAppPasswords`
 #[instrument(name = "com.atproto.server.listAppPasswords", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodGet) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
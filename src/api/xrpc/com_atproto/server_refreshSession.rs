@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::refresh_session::Output,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.refreshSession`
+/// `com.atproto.server.refreshSession`
 #[instrument(name = "com.atproto.server.refreshSession", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,10 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{error::XrpcError, handler::MethodPost},
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.requestAccountDelete`
+/// `com.atproto.server.requestAccountDelete`
 #[instrument(name = "com.atproto.server.requestAccountDelete", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost) -> Result<(), XrpcError> {
     unimplemented!();
 }
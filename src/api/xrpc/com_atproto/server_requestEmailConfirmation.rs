@@ -1,7 +1,10 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{error::XrpcError, handler::MethodPost},
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.requestEmailConfirmation`
+/// `com.atproto.server.requestEmailConfirmation`
 #[instrument(name = "com.atproto.server.requestEmailConfirmation", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::request_email_update::Output,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.requestEmailUpdate`
+/// `com.atproto.server.requestEmailUpdate`
 #[instrument(name = "com.atproto.server.requestEmailUpdate", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
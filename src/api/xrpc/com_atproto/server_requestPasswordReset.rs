@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::request_password_reset::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.requestPasswordReset`
+/// `com.atproto.server.requestPasswordReset`
 #[instrument(name = "com.atproto.server.requestPasswordReset", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
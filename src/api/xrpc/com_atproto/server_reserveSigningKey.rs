@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::reserve_signing_key::{Input, Output},
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.reserveSigningKey`
+/// `com.atproto.server.reserveSigningKey`
 #[instrument(name = "com.atproto.server.reserveSigningKey", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::reset_password::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.resetPassword`
+/// `com.atproto.server.resetPassword`
 #[instrument(name = "com.atproto.server.resetPassword", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
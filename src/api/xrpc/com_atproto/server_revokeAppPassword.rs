@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::revoke_app_password::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.revokeAppPassword`
+/// `com.atproto.server.revokeAppPassword`
 #[instrument(name = "com.atproto.server.revokeAppPassword", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
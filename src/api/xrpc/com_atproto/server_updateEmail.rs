@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::server::update_email::Input,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.repo.updateEmail`
+/// `com.atproto.server.updateEmail`
 #[instrument(name = "com.atproto.server.updateEmail", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
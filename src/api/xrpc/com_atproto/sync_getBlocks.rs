@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{MethodGet, Query},
+        lex::com::atproto::sync::get_blocks::Params,
+    },
+    tracing::instrument,
+};
 
-/// `com.atproto.sync.getBlobs`
-#[instrument(name = "com.atproto.sync.getBlobs", skip_all)]
-pub async fn handler() {
+/// `com.atproto.sync.getBlocks`
+#[instrument(name = "com.atproto.sync.getBlocks", skip_all)]
+pub async fn handler(_: MethodGet, Query(_params): Query<Params>) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,33 @@
-use tracing::instrument;
+use {
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Json, MethodGet, Query},
+            lex::com::atproto::sync::get_latest_commit::{Output, Params},
+        },
+        global,
+        repo::{RepoError, RepoStorage},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.sync.getLatestCommit`
 #[instrument(name = "com.atproto.sync.getLatestCommit", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodGet,
+    Query(params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
+    let conn = global::get()
+        .actor_stores
+        .connect(&params.did)
+        .await?
+        .ok_or(RepoError::NotFound)?;
+    let storage = RepoStorage::new(conn, params.did);
+
+    let root = storage.root().await?.ok_or(RepoError::NotFound)?;
+
+    Ok(Json(Output {
+        cid: root.cid.into(),
+        rev: root.rev,
+    }))
 }
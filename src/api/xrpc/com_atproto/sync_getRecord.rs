@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{MethodGet, Query},
+        lex::com::atproto::sync::get_record::Params,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.sync.getRecord`
 #[instrument(name = "com.atproto.sync.getRecord", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodGet, Query(_params): Query<Params>) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,35 @@
-use tracing::instrument;
+use {
+    crate::{
+        api::xrpc::{
+            error::XrpcError,
+            handler::{Json, MethodGet, Query},
+            lex::com::atproto::sync::get_repo_status::{Output, Params},
+        },
+        global,
+        repo::{RepoError, RepoStorage},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.sync.getRepoStatus`
 #[instrument(name = "com.atproto.sync.getRepoStatus", skip_all)]
-pub async fn handler() {
-    unimplemented!();
+pub async fn handler(
+    _: MethodGet,
+    Query(params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
+    let conn = global::get()
+        .actor_stores
+        .connect(&params.did)
+        .await?
+        .ok_or(RepoError::NotFound)?;
+    let storage = RepoStorage::new(conn, params.did);
+
+    let root = storage.root().await?.ok_or(RepoError::NotFound)?;
+
+    Ok(Json(Output {
+        did: storage.did().clone(),
+        active: true,
+        status: None,
+        rev: Some(root.rev),
+    }))
 }
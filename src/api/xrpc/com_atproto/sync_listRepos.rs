@@ -1,7 +1,17 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodGet, Query},
+        lex::com::atproto::sync::list_repos::{Output, Params},
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.sync.listRepos`
 #[instrument(name = "com.atproto.sync.listRepos", skip_all)]
-pub async fn handler() {
+pub async fn handler(
+    _: MethodGet,
+    Query(_params): Query<Params>,
+) -> Result<Json<Output>, XrpcError> {
     unimplemented!();
 }
@@ -1,7 +1,14 @@
-use tracing::instrument;
+use {
+    crate::api::xrpc::{
+        error::XrpcError,
+        handler::{Json, MethodPost},
+        lex::com::atproto::sync::request_crawl::Input,
+    },
+    tracing::instrument,
+};
 
 /// `com.atproto.sync.requestCrawl`
 #[instrument(name = "com.atproto.sync.requestCrawl", skip_all)]
-pub async fn handler() {
+pub async fn handler(_: MethodPost, Json(_input): Json<Input>) -> Result<(), XrpcError> {
     unimplemented!();
 }
@@ -1,6 +1,12 @@
 use {
     super::handler::IntoResponse,
-    crate::api::Response,
+    crate::{
+        api::{full_body, Response},
+        blob::BlobStoreError,
+        global::{auth::TokenError, database::ActorStoreError},
+        lexicon::ValidationError,
+        repo::{car::CarError, ImportError, RepoError},
+    },
     hyper::{
         header::{self, HeaderValue},
         StatusCode,
@@ -34,7 +40,7 @@ impl XrpcError {
     pub fn not_found(message: impl Into<Cow<'static, str>>) -> Self {
         Self {
             status: StatusCode::NOT_FOUND,
-            error: "not_found",
+            error: "NotFound",
             message: message.into(),
         }
     }
@@ -44,7 +50,7 @@ impl XrpcError {
     pub fn method_not_allowed(message: impl Into<Cow<'static, str>>) -> Self {
         Self {
             status: StatusCode::METHOD_NOT_ALLOWED,
-            error: "method_not_allowed",
+            error: "MethodNotAllowed",
             message: message.into(),
         }
     }
@@ -54,7 +60,57 @@ impl XrpcError {
     pub fn invalid_request(message: impl Into<Cow<'static, str>>) -> Self {
         Self {
             status: StatusCode::BAD_REQUEST,
-            error: "invalid_request",
+            error: "InvalidRequest",
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error indicating that the request requires a valid access
+    /// token.
+    pub fn authentication_required(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            error: "AuthenticationRequired",
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error indicating that the body of the request exceeds the
+    /// size accepted by the server.
+    pub fn payload_too_large(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            error: "PayloadTooLarge",
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error indicating that the requested XRPC method is not
+    /// implemented by the server.
+    pub fn method_not_implemented(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            status: StatusCode::NOT_IMPLEMENTED,
+            error: "MethodNotImplemented",
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error indicating that the server failed to process the
+    /// request.
+    pub fn internal_server_error(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: "InternalServerError",
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error with a custom error code, as defined by the Lexicon
+    /// of a method.
+    pub fn custom(error: &'static str, message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error,
             message: message.into(),
         }
     }
@@ -73,7 +129,7 @@ impl XrpcError {
         };
         let payload = serde_json::to_string(&payload).unwrap();
 
-        let mut response = Response::new(payload.into());
+        let mut response = Response::new(full_body(payload));
         *response.status_mut() = self.status;
         let header = response.headers_mut();
         header.insert(header::CONTENT_TYPE, MIME_JSON);
@@ -88,5 +144,76 @@ impl IntoResponse for XrpcError {
     }
 }
 
+impl From<RepoError> for XrpcError {
+    fn from(value: RepoError) -> Self {
+        match value {
+            RepoError::NotFound => Self::custom("RepoNotFound", "Could not find repo"),
+            err @ RepoError::InvalidSwap { .. } => Self::custom("InvalidSwap", err.to_string()),
+            err @ (RepoError::WrongDid(_) | RepoError::OutdatedImport(_)) => {
+                Self::invalid_request(format!("Invalid repository: {err}"))
+            }
+            RepoError::Blob(err) => err.into(),
+            err => {
+                tracing::error!("repository error: {err}");
+                Self::internal_server_error("Failed to access the repository")
+            }
+        }
+    }
+}
+
+impl From<libsql::Error> for XrpcError {
+    fn from(value: libsql::Error) -> Self {
+        tracing::error!("database error: {value}");
+        Self::internal_server_error("Failed to access the database")
+    }
+}
+
+impl From<ActorStoreError> for XrpcError {
+    fn from(value: ActorStoreError) -> Self {
+        tracing::error!("actor store error: {value}");
+        Self::internal_server_error("Failed to access the account data")
+    }
+}
+
+impl From<BlobStoreError> for XrpcError {
+    fn from(value: BlobStoreError) -> Self {
+        match value {
+            BlobStoreError::NotFound(cid) => {
+                Self::invalid_request(format!("Could not find blob `{cid}`"))
+            }
+            err => {
+                tracing::error!("blob store error: {err}");
+                Self::internal_server_error("Failed to access the blob store")
+            }
+        }
+    }
+}
+
+impl From<TokenError> for XrpcError {
+    fn from(value: TokenError) -> Self {
+        match value {
+            TokenError::Invalid => Self::custom("InvalidToken", "Token could not be verified"),
+            TokenError::Expired => Self::custom("ExpiredToken", "Token has expired"),
+        }
+    }
+}
+
+impl From<ImportError> for XrpcError {
+    fn from(value: ImportError) -> Self {
+        match value {
+            ImportError::Car(CarError::TooLarge) => {
+                Self::payload_too_large("The repository is too large")
+            }
+            err => Self::invalid_request(format!("Invalid repository: {err}")),
+        }
+    }
+}
+
+impl From<ValidationError> for XrpcError {
+    fn from(value: ValidationError) -> Self {
+        Self::invalid_request(format!("Invalid record: {value}"))
+    }
+}
+
 /// `application/json` content type.
-const MIME_JSON: HeaderValue = HeaderValue::from_static("application/json");
+pub(super) const MIME_JSON: HeaderValue = HeaderValue::from_static("application/json");
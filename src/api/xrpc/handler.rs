@@ -1,12 +1,27 @@
 use {
-    super::error::XrpcError,
-    crate::api::{Request, Response},
+    super::{
+        error::{XrpcError, MIME_JSON},
+        model::{AtIdentifier, Did},
+    },
+    crate::{
+        api::{full_body, BodyError, Request, RequestBody, Response, ResponseBody},
+        blob::read_chunks,
+        global::{
+            self,
+            auth::{ACCESS_SCOPE, APP_PASS_SCOPE},
+            database::{Connection, Queries},
+        },
+    },
+    futures::{Stream, StreamExt, TryStreamExt},
+    http_body_util::{BodyDataStream, BodyExt, StreamBody},
     hyper::{
-        body::{Body, Bytes},
+        body::{Body, Bytes, Frame},
+        header::{self, HeaderValue},
         Method,
     },
-    serde::de::DeserializeOwned,
+    serde::{de::DeserializeOwned, Serialize},
     std::{
+        convert::Infallible,
         future::Future,
         marker::PhantomData,
         net::SocketAddr,
@@ -53,6 +68,77 @@ impl FromRequestParts for SocketAddr {
     }
 }
 
+impl FromRequestParts for Connection {
+    fn from_request_parts(
+        _parts: &Request,
+    ) -> impl Send + Future<Output = Result<Self, XrpcError>> {
+        async move { Ok(global::get().database.connect().await?) }
+    }
+}
+
+/// The account authenticated by the access token of a request.
+///
+/// The token is expected in the `Authorization` header, using the `Bearer`
+/// scheme.
+#[derive(Debug, Clone)]
+pub struct Auth {
+    /// The DID of the authenticated account.
+    pub did: Did,
+}
+
+impl Auth {
+    /// Ensures that `repo` designates the repository of the authenticated
+    /// account.
+    pub async fn check_repo(&self, repo: &AtIdentifier) -> Result<(), XrpcError> {
+        let matches = match repo {
+            AtIdentifier::Did(did) => *did == self.did,
+            AtIdentifier::Handle(handle) => {
+                let conn = global::get().database.connect().await?;
+                let current = conn
+                    .query_opt::<(Option<String>,)>(
+                        "SELECT handle FROM accounts WHERE did = ?1",
+                        [self.did.as_str()],
+                    )
+                    .await?
+                    .and_then(|(handle,)| handle);
+                current.is_some_and(|current| current.eq_ignore_ascii_case(handle.as_str()))
+            }
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(XrpcError::authentication_required(
+                "The repo does not belong to the authenticated account",
+            ))
+        }
+    }
+}
+
+impl FromRequestParts for Auth {
+    fn from_request_parts(parts: &Request) -> impl Send + Future<Output = Result<Self, XrpcError>> {
+        let token = parts
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        let ret = match token {
+            Some(token) => match global::get().token_key.verify(token.trim()) {
+                Ok(claims) if [ACCESS_SCOPE, APP_PASS_SCOPE].contains(&claims.scope.as_str()) => {
+                    Ok(Self { did: claims.sub })
+                }
+                Ok(_) => Err(XrpcError::custom("InvalidToken", "Bad token scope")),
+                Err(err) => Err(err.into()),
+            },
+            None => Err(XrpcError::authentication_required(
+                "Authentication required",
+            )),
+        };
+        std::future::ready(ret)
+    }
+}
+
 /// Creates an error that indicate that the method used for the
 /// provided request was not allowed.
 fn method_not_allowed(req: &Request) -> XrpcError {
@@ -102,7 +188,7 @@ pub struct Query<T>(pub T);
 impl<T: Send + DeserializeOwned> FromRequestParts for Query<T> {
     fn from_request_parts(parts: &Request) -> impl Send + Future<Output = Result<Self, XrpcError>> {
         let query = parts.uri().query().unwrap_or_default();
-        let ret = match serde_urlencoded::from_str(query) {
+        let ret = match super::query::from_query_str(query) {
             Ok(val) => Ok(Self(val)),
             Err(err) => Err(XrpcError::invalid_request(err.to_string())),
         };
@@ -141,7 +227,7 @@ pub trait IntoResponse {
 impl IntoResponse for () {
     #[inline]
     fn into_response(self) -> impl Send + Future<Output = Response> {
-        std::future::ready(Response::new("".into()))
+        std::future::ready(Response::new(full_body("")))
     }
 }
 
@@ -194,6 +280,126 @@ where
     }
 }
 
+impl<T> IntoResponse for Json<T>
+where
+    T: Send + Serialize,
+{
+    fn into_response(self) -> impl Send + Future<Output = Response> {
+        let response = match serde_json::to_string(&self.0) {
+            Ok(payload) => {
+                let mut response = Response::new(full_body(payload));
+                response
+                    .headers_mut()
+                    .insert(header::CONTENT_TYPE, MIME_JSON);
+                response
+            }
+            Err(err) => XrpcError::internal_server_error(err.to_string()).to_response(),
+        };
+        std::future::ready(response)
+    }
+}
+
+/// A response whose body is streamed instead of being materialized in memory.
+///
+/// If the length of the body is known, it is announced in the
+/// `Content-Length` header and the body must produce exactly that many
+/// bytes. Otherwise, the body is sent with chunked framing.
+pub struct Streaming {
+    body: ResponseBody,
+    content_type: HeaderValue,
+    content_length: Option<u64>,
+}
+
+impl Streaming {
+    /// Creates a response that streams the chunks of `stream`.
+    pub fn new<S>(stream: S, content_type: HeaderValue) -> Self
+    where
+        S: 'static + Send + Stream<Item = Bytes>,
+    {
+        Self::try_new(stream.map(Ok::<_, Infallible>), content_type)
+    }
+
+    /// Creates a response that streams the chunks of a fallible `stream`.
+    ///
+    /// An error interrupts the response, closing the connection.
+    pub fn try_new<S, E>(stream: S, content_type: HeaderValue) -> Self
+    where
+        S: 'static + Send + Stream<Item = Result<Bytes, E>>,
+        E: 'static + Into<BodyError>,
+    {
+        let stream = stream.map_ok(Frame::data).map_err(Into::into);
+        Self::from_body(StreamBody::new(stream), content_type)
+    }
+
+    /// Creates a response that streams the provided body.
+    ///
+    /// The length of the body is announced if its size hint is exact.
+    pub fn from_body<B>(body: B, content_type: HeaderValue) -> Self
+    where
+        B: 'static + Send + Body<Data = Bytes>,
+        B::Error: Into<BodyError>,
+    {
+        Self {
+            content_length: body.size_hint().exact(),
+            body: body.map_err(Into::into).boxed_unsync(),
+            content_type,
+        }
+    }
+
+    /// Creates a response that streams the content of `reader`, such as a
+    /// file, until its end.
+    pub fn from_reader<R>(reader: R, content_type: HeaderValue) -> Self
+    where
+        R: 'static + Send + Unpin + tokio::io::AsyncRead,
+    {
+        Self::try_new(read_chunks(reader), content_type)
+    }
+
+    /// Announces the length of the body.
+    pub fn with_length(mut self, len: u64) -> Self {
+        self.content_length = Some(len);
+        self
+    }
+}
+
+impl IntoResponse for Streaming {
+    fn into_response(self) -> impl Send + Future<Output = Response> {
+        let mut response = Response::new(self.body);
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, self.content_type);
+        if let Some(len) = self.content_length {
+            headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+        }
+        std::future::ready(response)
+    }
+}
+
+/// The raw body of a request.
+///
+/// Unlike [`Json`], the body is not buffered: it is read chunk by chunk as
+/// the client sends it.
+pub struct RawBody(pub RequestBody);
+
+impl RawBody {
+    /// Returns the length of the body, if the client announced it.
+    pub fn content_length(&self) -> Option<u64> {
+        self.0.size_hint().exact()
+    }
+
+    /// Turns the body into a stream of chunks.
+    ///
+    /// Trailers are ignored.
+    pub fn into_stream(self) -> impl Send + Stream<Item = std::io::Result<Bytes>> {
+        BodyDataStream::new(self.0).map_err(std::io::Error::other)
+    }
+}
+
+impl FromRequest for RawBody {
+    fn from_request(req: &mut Request) -> impl Send + Future<Output = Result<Self, XrpcError>> {
+        std::future::ready(Ok(Self(std::mem::take(req.body_mut()))))
+    }
+}
+
 /// Reads the provided body and returns it into a flat buffer.
 async fn read_body<B>(body: &mut B) -> Result<Bytes, XrpcError>
 where
@@ -305,3 +511,40 @@ impl_IntoHandler_for_fn!(A, B && C);
 impl_IntoHandler_for_fn!(A, B, C && D);
 impl_IntoHandler_for_fn!(A, B, C, D && E);
 impl_IntoHandler_for_fn!(A, B, C, D, E && F);
+
+#[cfg(test)]
+#[tokio::test]
+async fn streaming_responses() {
+    use crate::blob::CHUNK_SIZE;
+
+    let content_type = HeaderValue::from_static("application/octet-stream");
+
+    // The length of buffered bodies is known, so hyper announces it.
+    let response = Json("hello").into_response().await;
+    assert_eq!(response.body().size_hint().exact(), Some(7));
+
+    let chunks = futures::stream::iter([Bytes::from("hello, "), Bytes::from("world")]);
+    let response = Streaming::new(chunks, content_type.clone())
+        .into_response()
+        .await;
+    assert_eq!(response.headers().get(header::CONTENT_LENGTH), None);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "hello, world");
+
+    let data = vec![7; CHUNK_SIZE + 1];
+    let response = Streaming::from_reader(std::io::Cursor::new(data), content_type)
+        .with_length(CHUNK_SIZE as u64 + 1)
+        .into_response()
+        .await;
+    assert_eq!(
+        response.headers()[header::CONTENT_LENGTH],
+        (CHUNK_SIZE + 1).to_string()
+    );
+    let mut frames = 0;
+    let mut body = response.into_body();
+    while let Some(frame) = body.frame().await {
+        assert!(frame.unwrap().is_data());
+        frames += 1;
+    }
+    assert_eq!(frames, 2);
+}
@@ -0,0 +1,113 @@
+//! Types generated from the Lexicon documents bundled with the server.
+//!
+//! Each document gets a module named after its NSID. For example, the types
+//! of `com.atproto.repo.createRecord` live in
+//! [`com::atproto::repo::create_record`].
+//!
+//! - Object definitions become structs, named after the definition in
+//!   PascalCase. The `main` definition is named `Main`, or `Record` for
+//!   record types.
+//!
+//! - Unions become enums, told apart by the `$type` field of their values.
+//!
+//! - Optional fields become `Option`s. Optional fields that are also
+//!   nullable become `Option<Option<T>>`, so that a missing field (`None`)
+//!   can be told apart from `null` (`Some(None)`).
+//!
+//! - XRPC methods get `Params`, `Input` and `Output` types for their query
+//!   parameters and JSON bodies, and an `Error` enum for the errors they
+//!   declare.
+//!
+//! See `build.rs` for the generator.
+
+#![allow(dead_code, clippy::enum_variant_names)]
+
+include!(concat!(env!("OUT_DIR"), "/lexicon_types.rs"));
+
+/// Deserializes an optional and nullable field, wrapping its value in
+/// `Some`. Missing fields are handled by `#[serde(default)]`.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    <Option<T> as serde::Deserialize>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+#[test]
+fn apply_writes_input() {
+    use self::com::atproto::repo::apply_writes::{Input, InputWritesItem};
+
+    let input: Input = serde_json::from_str(
+        r#"{
+            "repo": "did:plc:abc123",
+            "validate": true,
+            "writes": [
+                {
+                    "$type": "com.atproto.repo.applyWrites#create",
+                    "collection": "app.bsky.feed.post",
+                    "value": { "$type": "app.bsky.feed.post", "text": "Hello!" }
+                },
+                {
+                    "$type": "com.atproto.repo.applyWrites#delete",
+                    "collection": "app.bsky.feed.post",
+                    "rkey": "3jzfcijpj2z2a"
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(input.validate, Some(true));
+    assert!(input.swap_commit.is_none());
+    assert!(matches!(
+        input.writes.as_slice(),
+        [
+            InputWritesItem::ApplyWritesCreate(_),
+            InputWritesItem::ApplyWritesDelete(_)
+        ]
+    ));
+
+    let json = serde_json::to_value(&input).unwrap();
+    assert_eq!(
+        json["writes"][1]["$type"],
+        "com.atproto.repo.applyWrites#delete"
+    );
+    assert!(json.get("swapCommit").is_none());
+
+    assert!(serde_json::from_str::<Input>(
+        r#"{ "repo": "did:plc:abc123", "writes": [{ "$type": "com.atproto.repo.applyWrites#nope" }] }"#
+    )
+    .is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn nullable_fields() {
+    use self::com::atproto::repo::put_record::Input;
+
+    let input = |swap_record: &str| -> Input {
+        serde_json::from_str(&format!(
+            r#"{{
+                "repo": "did:plc:abc123",
+                "collection": "app.bsky.actor.profile",
+                "rkey": "self",
+                "record": {{}}
+                {swap_record}
+            }}"#
+        ))
+        .unwrap()
+    };
+
+    assert!(input("").swap_record.is_none());
+    assert!(matches!(
+        input(r#", "swapRecord": null"#).swap_record,
+        Some(None)
+    ));
+    assert!(matches!(
+        input(r#", "swapRecord": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm""#)
+            .swap_record,
+        Some(Some(_))
+    ));
+}
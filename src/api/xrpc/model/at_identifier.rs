@@ -1,5 +1,5 @@
 use {
-    super::{Did, Handle},
+    super::{validate_did, validate_handle, Did, Handle},
     serde::Serialize,
     std::fmt::Display,
 };
@@ -23,6 +23,15 @@ impl<T: AsRef<str>> AtIdentifier<T> {
     }
 }
 
+/// Validates the provided `bytes` string as either a DID or a handle.
+pub fn validate_at_identifier(bytes: &[u8]) -> bool {
+    if bytes.starts_with(b"did:") {
+        validate_did(bytes)
+    } else {
+        validate_handle(bytes)
+    }
+}
+
 impl<T: AsRef<str>> Display for AtIdentifier<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
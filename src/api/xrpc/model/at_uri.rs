@@ -1,5 +1,5 @@
 use {
-    super::{validate_did, validate_handle},
+    super::{validate_did, validate_handle, validate_nsid, Did, Nsid, RecordKey},
     memchr::{memchr, memchr3},
     serde::{Deserialize, Serialize},
 };
@@ -67,6 +67,35 @@ impl<T: ?Sized + AsRef<str>> AtUri<T> {
         unsafe { self.value.as_ref().get_unchecked(start..end) }
     }
 
+    /// Returns the segments of the path of the URI.
+    fn path_segments(&self) -> impl Iterator<Item = &str> {
+        self.path().split('/').skip(1)
+    }
+
+    /// Returns the collection referenced by the URI.
+    ///
+    /// This is the first segment of the path, if it is a valid NSID.
+    pub fn collection(&self) -> Option<Nsid<&str>> {
+        Nsid::new(self.path_segments().next()?).ok()
+    }
+
+    /// Returns the key of the record referenced by the URI.
+    ///
+    /// This is the second segment of the path, if the URI is made of exactly
+    /// a collection and a valid record key.
+    pub fn rkey(&self) -> Option<RecordKey<&str>> {
+        let mut segments = self.path_segments();
+
+        let collection = segments.next()?;
+        let rkey = segments.next()?;
+
+        if !validate_nsid(collection.as_bytes()) || segments.next().is_some() {
+            return None;
+        }
+
+        RecordKey::new(rkey).ok()
+    }
+
     /// Returns the query part of the URI.
     pub fn query(&self) -> &str {
         let (start, end) = self.parts.query;
@@ -83,6 +112,59 @@ impl<T: ?Sized + AsRef<str>> AtUri<T> {
     }
 }
 
+impl AtUri {
+    /// Creates a new [`AtUriBuilder`] for a URI whose authority is the
+    /// provided DID.
+    #[inline]
+    pub fn builder(did: &Did<impl AsRef<str>>) -> AtUriBuilder {
+        AtUriBuilder::new(did)
+    }
+}
+
+/// Assembles an [`AtUri`] referencing a repository, a collection, or a
+/// record.
+#[derive(Debug, Clone)]
+pub struct AtUriBuilder {
+    buf: String,
+    has_collection: bool,
+}
+
+impl AtUriBuilder {
+    /// Creates a new [`AtUriBuilder`] for a URI whose authority is the
+    /// provided DID.
+    pub fn new(did: &Did<impl AsRef<str>>) -> Self {
+        Self {
+            buf: format!("at://{did}"),
+            has_collection: false,
+        }
+    }
+
+    /// Sets the collection referenced by the URI.
+    pub fn collection(mut self, collection: &Nsid<impl AsRef<str>>) -> Self {
+        debug_assert!(!self.has_collection, "collection set twice");
+        self.buf.push('/');
+        self.buf.push_str(collection.as_str());
+        self.has_collection = true;
+        self
+    }
+
+    /// Sets the key of the record referenced by the URI.
+    ///
+    /// The collection must have been set beforehand.
+    pub fn rkey(mut self, rkey: &RecordKey<impl AsRef<str>>) -> Self {
+        debug_assert!(self.has_collection, "rkey set without a collection");
+        self.buf.push('/');
+        self.buf.push_str(rkey.as_str());
+        self
+    }
+
+    /// Builds the URI.
+    pub fn build(self) -> AtUri {
+        AtUri::new(self.buf.into_boxed_str())
+            .expect("DIDs, NSIDs and record keys always form valid `at://` URIs")
+    }
+}
+
 impl<T: ?Sized + AsRef<str>> std::fmt::Debug for AtUri<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("AtUri").field(&self.as_str()).finish()
@@ -235,8 +317,13 @@ fn is_valid_uri_character(c: &u8) -> bool {
 }
 
 /// Validates the provided bytes as a path component.
+///
+/// Path components may also contain colons, which are allowed in record
+/// keys.
 fn validate_path_component(bytes: &[u8]) -> bool {
-    bytes.iter().all(is_valid_uri_character)
+    bytes
+        .iter()
+        .all(|c| *c == b':' || is_valid_uri_character(c))
 }
 
 /// Validates the provided bytes as an URI fragment.
@@ -384,3 +471,41 @@ fn long_path_no_slash_terminator() {
     assert_eq!(uri.query(), "");
     assert_eq!(uri.fragment(), "");
 }
+
+#[test]
+#[cfg(test)]
+fn record_uri() {
+    let uri = AtUri::new("at://did:plc:abc123/app.bsky.feed.post/3jui7kd54zh2y").unwrap();
+    assert_eq!(uri.collection().unwrap().as_str(), "app.bsky.feed.post");
+    assert_eq!(uri.rkey().unwrap().as_str(), "3jui7kd54zh2y");
+
+    let uri = AtUri::new("at://did:plc:abc123/app.bsky.feed.post").unwrap();
+    assert_eq!(uri.collection().unwrap().as_str(), "app.bsky.feed.post");
+    assert!(uri.rkey().is_none());
+
+    let uri = AtUri::new("at://did:plc:abc123/app.bsky.feed.post/a/b").unwrap();
+    assert!(uri.rkey().is_none());
+
+    let uri = AtUri::new("at://did:plc:abc123/not-an-nsid/self").unwrap();
+    assert!(uri.collection().is_none());
+    assert!(uri.rkey().is_none());
+}
+
+#[test]
+#[cfg(test)]
+fn build_record_uri() {
+    let did = Did::try_from("did:plc:abc123").unwrap();
+    let collection = Nsid::try_from("app.bsky.feed.post").unwrap();
+    let rkey = RecordKey::try_from("pre:fix").unwrap();
+
+    let uri = AtUri::builder(&did)
+        .collection(&collection)
+        .rkey(&rkey)
+        .build();
+    assert_eq!(
+        uri.as_str(),
+        "at://did:plc:abc123/app.bsky.feed.post/pre:fix"
+    );
+    assert_eq!(uri.collection().unwrap(), collection);
+    assert_eq!(uri.rkey().unwrap(), rkey);
+}
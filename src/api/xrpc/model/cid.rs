@@ -0,0 +1,428 @@
+use {
+    crate::dag_cbor::LINK_TOKEN,
+    serde::{
+        de::{self, Visitor},
+        ser::SerializeMap,
+        Deserialize, Deserializer, Serialize, Serializer,
+    },
+    sha2::{Digest, Sha256},
+    std::{fmt::Display, ops::Deref, str::FromStr},
+};
+
+/// An error that might occur when parsing a CID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CidParseError;
+
+impl std::fmt::Display for CidParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid CID")
+    }
+}
+
+impl std::error::Error for CidParseError {}
+
+/// The multicodec of the content referenced by a [`Cid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Codec {
+    /// Raw binary data, used for blobs.
+    Raw = 0x55,
+    /// DAG-CBOR data, used for records, repository nodes and commits.
+    DagCbor = 0x71,
+}
+
+impl Codec {
+    /// Returns the codec associated with the provided multicodec code.
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0x55 => Some(Self::Raw),
+            0x71 => Some(Self::DagCbor),
+            _ => None,
+        }
+    }
+}
+
+/// The multicodec code of the CID version 1.
+const CID_V1: u8 = 0x01;
+/// The multihash code of SHA-256.
+const SHA2_256: u8 = 0x12;
+/// The length of a SHA-256 digest.
+const DIGEST_LEN: usize = 32;
+
+/// A Content IDentifier (CID), as used by the AT Protocol.
+///
+/// Only the "blessed" format is supported: CIDv1, with a SHA-256 hash and
+/// either the `dag-cbor` or the `raw` codec.
+///
+/// In string form, CIDs are encoded using the base32 multibase (starting with
+/// a `b`). In DAG-CBOR, they are encoded as CID links (tag `42`), and in JSON
+/// as `{"$link": "..."}` objects.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cid {
+    codec: Codec,
+    digest: [u8; DIGEST_LEN],
+}
+
+impl Cid {
+    /// The length of the binary representation of a CID.
+    pub const BYTE_LEN: usize = 4 + DIGEST_LEN;
+
+    /// Creates a new [`Cid`] from its codec and the SHA-256 digest of the
+    /// referenced content.
+    #[inline]
+    pub const fn new(codec: Codec, digest: [u8; DIGEST_LEN]) -> Self {
+        Self { codec, digest }
+    }
+
+    /// Computes the CID of the provided content.
+    pub fn compute(codec: Codec, data: &[u8]) -> Self {
+        Self::new(codec, Sha256::digest(data).into())
+    }
+
+    /// Returns the codec of the referenced content.
+    #[inline]
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// Returns the SHA-256 digest of the referenced content.
+    #[inline]
+    pub fn digest(&self) -> &[u8; DIGEST_LEN] {
+        &self.digest
+    }
+
+    /// Returns the binary representation of this CID.
+    pub fn to_bytes(self) -> [u8; Self::BYTE_LEN] {
+        let mut bytes = [0u8; Self::BYTE_LEN];
+        bytes[0] = CID_V1;
+        bytes[1] = self.codec as u8;
+        bytes[2] = SHA2_256;
+        bytes[3] = DIGEST_LEN as u8;
+        bytes[4..].copy_from_slice(&self.digest);
+        bytes
+    }
+
+    /// Reads a CID from the start of `bytes`, returning it along with the
+    /// remaining bytes.
+    pub fn read_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), CidParseError> {
+        let (head, rest) = bytes
+            .split_at_checked(Self::BYTE_LEN)
+            .ok_or(CidParseError)?;
+
+        // All the codes we support fit in a single varint byte, so we can
+        // compare them directly.
+        let [CID_V1, codec, SHA2_256, len, digest @ ..] = head else {
+            return Err(CidParseError);
+        };
+
+        if *len as usize != DIGEST_LEN {
+            return Err(CidParseError);
+        }
+
+        let codec = Codec::from_code(*codec).ok_or(CidParseError)?;
+        let digest = digest.try_into().map_err(|_| CidParseError)?;
+
+        Ok((Self::new(codec, digest), rest))
+    }
+
+    /// Parses the binary representation of a CID.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CidParseError> {
+        match Self::read_bytes(bytes)? {
+            (cid, []) => Ok(cid),
+            _ => Err(CidParseError),
+        }
+    }
+}
+
+impl std::fmt::Debug for Cid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Cid").field(&format_args!("{self}")).finish()
+    }
+}
+
+impl Display for Cid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut buf = [0u8; BASE32_LEN];
+        base32_encode(&self.to_bytes(), &mut buf);
+        // SAFETY: The base32 alphabet only contains ASCII characters.
+        let s = unsafe { std::str::from_utf8_unchecked(&buf) };
+        f.pad(s)
+    }
+}
+
+impl FromStr for Cid {
+    type Err = CidParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(encoded) = s.strip_prefix('b') else {
+            return Err(CidParseError);
+        };
+
+        let mut buf = [0u8; Cid::BYTE_LEN];
+        base32_decode(encoded.as_bytes(), &mut buf)?;
+        Self::from_bytes(&buf)
+    }
+}
+
+/// The length of a CID encoded as a multibase base32 string, including the
+/// `b` prefix.
+const BASE32_LEN: usize = 1 + (Cid::BYTE_LEN * 8).div_ceil(5);
+
+/// The lowercase RFC 4648 base32 alphabet.
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Encodes a CID into its multibase base32 representation.
+fn base32_encode(bytes: &[u8; Cid::BYTE_LEN], out: &mut [u8; BASE32_LEN]) {
+    out[0] = b'b';
+
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    let mut i = 1;
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            out[i] = BASE32_ALPHABET[((buffer >> bits) & 0x1F) as usize];
+            i += 1;
+        }
+    }
+
+    if bits > 0 {
+        out[i] = BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize];
+    }
+}
+
+/// Decodes an unpadded base32 string into `out`, which must be exactly the
+/// size of the decoded data.
+fn base32_decode(encoded: &[u8], out: &mut [u8; Cid::BYTE_LEN]) -> Result<(), CidParseError> {
+    if encoded.len() != BASE32_LEN - 1 {
+        return Err(CidParseError);
+    }
+
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    let mut i = 0;
+
+    for &c in encoded {
+        let value = match c {
+            b'a'..=b'z' => c - b'a',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => return Err(CidParseError),
+        };
+
+        buffer = (buffer << 5) | value as u16;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            out[i] = (buffer >> bits) as u8;
+            i += 1;
+        }
+    }
+
+    // The padding bits must be zero for the encoding to be canonical.
+    if buffer & ((1 << bits) - 1) != 0 {
+        return Err(CidParseError);
+    }
+
+    Ok(())
+}
+
+/// Serializes the binary representation of a CID using `serialize_bytes`.
+struct CidBytes<'a>(&'a [u8]);
+
+impl Serialize for CidBytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl Serialize for Cid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry("$link", &format_args!("{self}"))?;
+            map.end()
+        } else {
+            serializer.serialize_newtype_struct(LINK_TOKEN, &CidBytes(&self.to_bytes()))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Cid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CidVisitor;
+
+        impl<'de> Visitor<'de> for CidVisitor {
+            type Value = Cid;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a CID link")
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Cid, D::Error> {
+                deserializer.deserialize_any(self)
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Cid, E> {
+                Cid::from_bytes(v).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Cid, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Cid, A::Error> {
+                let Some(key) = map.next_key::<std::borrow::Cow<str>>()? else {
+                    return Err(de::Error::missing_field("$link"));
+                };
+                if key != "$link" {
+                    return Err(de::Error::unknown_field(&key, &["$link"]));
+                }
+                let link = map.next_value::<std::borrow::Cow<str>>()?;
+                if map.next_key::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::custom("unexpected field in `$link` object"));
+                }
+                link.parse().map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(LINK_TOKEN, CidVisitor)
+    }
+}
+
+/// Serializes a [`Cid`] as a plain string, as expected by the `cid` string
+/// format of Lexicon schemas.
+///
+/// Use with `#[serde(serialize_with = "...")]`.
+pub fn serialize_cid_string<S: Serializer>(cid: &Cid, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(cid)
+}
+
+/// A [`Cid`] that is serialized as a plain string, as expected by the `cid`
+/// string format of Lexicon schemas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CidString(pub Cid);
+
+impl From<Cid> for CidString {
+    #[inline]
+    fn from(cid: Cid) -> Self {
+        Self(cid)
+    }
+}
+
+impl Deref for CidString {
+    type Target = Cid;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for CidString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Serialize for CidString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for CidString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <std::borrow::Cow<'de, str>>::deserialize(deserializer)
+            .and_then(|s| s.parse().map(Self).map_err(de::Error::custom))
+    }
+}
+
+/// Validates the provided `bytes` string against the `cid` string format of
+/// Lexicon schemas.
+///
+/// Unlike [`Cid::from_str`], this only checks the general shape of the
+/// string and accepts any CID version, codec or hash function.
+pub fn validate_cid_string(bytes: &[u8]) -> bool {
+    #[inline]
+    fn is_cid_char(c: &u8) -> bool {
+        matches!(c, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'+' | b'=')
+    }
+
+    (8..=256).contains(&bytes.len()) && bytes.iter().all(is_cid_char)
+}
+
+#[cfg(test)]
+#[test]
+fn cid_string_round_trip() {
+    let s = "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm";
+    let cid: Cid = s.parse().unwrap();
+    assert_eq!(cid.codec(), Codec::DagCbor);
+    assert_eq!(cid.to_string(), s);
+}
+
+#[cfg(test)]
+#[test]
+fn cid_compute() {
+    let cid = Cid::compute(Codec::Raw, b"hello world");
+    assert_eq!(
+        cid.to_string(),
+        "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+    );
+    assert_eq!(Cid::from_bytes(&cid.to_bytes()), Ok(cid));
+}
+
+#[cfg(test)]
+#[test]
+fn invalid_cids() {
+    // CIDv0.
+    assert!("QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR"
+        .parse::<Cid>()
+        .is_err());
+    // Uppercase base32.
+    assert!(
+        "BAFYREIE5737GDXLW5I64VZICHCALBA3Z2V5N6ICIFVX5XYTVSKE7MR3HPM"
+            .parse::<Cid>()
+            .is_err()
+    );
+    // Truncated.
+    assert!("bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hp"
+        .parse::<Cid>()
+        .is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn cid_serde() {
+    let cid = Cid::compute(Codec::DagCbor, b"{}");
+
+    let json = serde_json::to_string(&cid).unwrap();
+    assert_eq!(json, format!(r#"{{"$link":"{cid}"}}"#));
+    assert_eq!(serde_json::from_str::<Cid>(&json).unwrap(), cid);
+
+    let cbor = crate::dag_cbor::to_vec(&cid).unwrap();
+    assert_eq!(&cbor[..2], b"\xd8\x2a");
+    assert_eq!(crate::dag_cbor::from_slice::<Cid>(&cbor).unwrap(), cid);
+}
+
+#[cfg(test)]
+#[test]
+fn cid_string_format() {
+    assert!(validate_cid_string(
+        b"bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm"
+    ));
+    assert!(validate_cid_string(
+        b"QmZfSNpHVzTNi9gezLcgq64Wbj1xhwi9wk4AxYyxMZgtCG"
+    ));
+    assert!(!validate_cid_string(b"bafy"));
+    assert!(!validate_cid_string(
+        b"bafyreie5737gdxlw5i64vz/chcalba3z2v5n6"
+    ));
+}
@@ -0,0 +1,255 @@
+use {
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::fmt::Display,
+};
+
+/// Errors that can occur when parsing a datetime from a string.
+#[derive(Debug, Clone)]
+pub struct DatetimeParseError;
+
+impl std::fmt::Display for DatetimeParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid datetime format")
+    }
+}
+
+impl std::error::Error for DatetimeParseError {}
+
+/// Represents a timestamp, as defined by the `datetime` string format of the
+/// [AT Protocol data model](https://atproto.com/specs/lexicon#datetime).
+///
+/// This is the intersection of RFC 3339 and ISO 8601: a full date and time
+/// with an explicit timezone, such as `1985-04-12T23:20:50.123Z`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Datetime<T: ?Sized = Box<str>>(T);
+
+impl<T> Datetime<T> {
+    /// Creates a new [`Datetime`] instance from the provided value without
+    /// validating it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the provided value is a valid datetime.
+    #[inline]
+    pub const unsafe fn new_unchecked(val: T) -> Self {
+        Datetime(val)
+    }
+}
+
+impl<T> Datetime<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    /// Creates a new [`Datetime`] instance from the provided value.
+    ///
+    /// If the value is not a valid datetime, this function fails.
+    pub fn new(val: T) -> Result<Self, DatetimeParseError>
+    where
+        T: Sized,
+    {
+        if validate_datetime(val.as_ref().as_bytes()) {
+            Ok(unsafe { Datetime::new_unchecked(val) })
+        } else {
+            Err(DatetimeParseError)
+        }
+    }
+
+    /// Returns the underlying string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> Display for Datetime<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Returns the number of days in the provided month.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parses exactly `N` ASCII digits at the start of `bytes`, returning their
+/// value and the remaining bytes.
+fn parse_digits<const N: usize>(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let (digits, rest) = bytes.split_at_checked(N)?;
+
+    let mut value = 0;
+    for &c in digits {
+        if !c.is_ascii_digit() {
+            return None;
+        }
+        value = value * 10 + (c - b'0') as u32;
+    }
+
+    Some((value, rest))
+}
+
+/// Removes the provided separator from the start of `bytes`.
+#[inline]
+fn expect(bytes: &[u8], c: u8) -> Option<&[u8]> {
+    bytes.strip_prefix(&[c])
+}
+
+/// Validates the provided `bytes` string as a datetime.
+pub fn validate_datetime(bytes: &[u8]) -> bool {
+    parse_datetime(bytes).is_some()
+}
+
+/// Parses the provided `bytes` string as a datetime, validating it along the
+/// way.
+fn parse_datetime(bytes: &[u8]) -> Option<()> {
+    // Valid datetimes are much shorter than this, even with a very precise
+    // fractional part.
+    if bytes.len() > 64 {
+        return None;
+    }
+
+    let (year, bytes) = parse_digits::<4>(bytes)?;
+    let bytes = expect(bytes, b'-')?;
+    let (month, bytes) = parse_digits::<2>(bytes)?;
+    let bytes = expect(bytes, b'-')?;
+    let (day, bytes) = parse_digits::<2>(bytes)?;
+
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    // The separator must be an uppercase `T`.
+    let bytes = expect(bytes, b'T')?;
+    let (hour, bytes) = parse_digits::<2>(bytes)?;
+    let bytes = expect(bytes, b':')?;
+    let (minute, bytes) = parse_digits::<2>(bytes)?;
+    let bytes = expect(bytes, b':')?;
+    let (second, mut bytes) = parse_digits::<2>(bytes)?;
+
+    // Leap seconds are allowed.
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    if let Some(fraction) = expect(bytes, b'.') {
+        let len = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        bytes = &fraction[len..];
+    }
+
+    // The timezone is mandatory. `-00:00` is forbidden by RFC 3339 as it
+    // means "unknown local offset".
+    match bytes {
+        b"Z" => Some(()),
+        b"-00:00" => None,
+        [b'+' | b'-', rest @ ..] => {
+            let (hour, rest) = parse_digits::<2>(rest)?;
+            let rest = expect(rest, b':')?;
+            let (minute, rest) = parse_digits::<2>(rest)?;
+
+            (rest.is_empty() && hour <= 23 && minute <= 59).then_some(())
+        }
+        _ => None,
+    }
+}
+
+impl<T> Serialize for Datetime<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Datetime<T>
+where
+    T: Deserialize<'de> + AsRef<str>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+            .and_then(|inner| Self::new(inner).map_err(serde::de::Error::custom))
+    }
+}
+
+impl<T> AsRef<str> for Datetime<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Datetime<&'a str> {
+    type Error = DatetimeParseError;
+
+    #[inline]
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<Box<str>> for Datetime<Box<str>> {
+    type Error = DatetimeParseError;
+
+    #[inline]
+    fn try_from(value: Box<str>) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<String> for Datetime<String> {
+    type Error = DatetimeParseError;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn valid_datetimes() {
+    assert!(validate_datetime(b"1985-04-12T23:20:50.123Z"));
+    assert!(validate_datetime(b"1985-04-12T23:20:50.123456Z"));
+    assert!(validate_datetime(b"1985-04-12T23:20:50.120Z"));
+    assert!(validate_datetime(b"1985-04-12T23:20:50Z"));
+    assert!(validate_datetime(b"1985-04-12T23:20:50.1+00:00"));
+    assert!(validate_datetime(b"1985-04-12T23:20:50.12-07:00"));
+    assert!(validate_datetime(b"2024-02-29T00:00:00Z"));
+}
+
+#[cfg(test)]
+#[test]
+fn invalid_datetimes() {
+    assert!(!validate_datetime(b"1985-04-12"));
+    assert!(!validate_datetime(b"1985-04-12T23:20Z"));
+    assert!(!validate_datetime(b"1985-04-12T23:20:50.123"));
+    assert!(!validate_datetime(b"1985-04-12t23:20:50.123Z"));
+    assert!(!validate_datetime(b"1985-04-12T23:20:50.123z"));
+    assert!(!validate_datetime(b"1985-04-12 23:20:50.123Z"));
+    assert!(!validate_datetime(b"1985-04-12T23:20:50.123-00:00"));
+    assert!(!validate_datetime(b"1985-04-12T23:20:50.Z"));
+    assert!(!validate_datetime(b"1985-04-12T23:20:50.123+0000"));
+    assert!(!validate_datetime(b"1985-13-12T23:20:50Z"));
+    assert!(!validate_datetime(b"2023-02-29T00:00:00Z"));
+    assert!(!validate_datetime(b"1985-04-12T24:00:00Z"));
+    assert!(!validate_datetime(b"85-04-12T23:20:50Z"));
+}
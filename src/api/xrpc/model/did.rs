@@ -133,6 +133,11 @@ pub fn validate_did(mut bytes: &[u8]) -> bool {
             return false;
         }
 
+        // Components of the identifier can't be empty.
+        if c == b':' && i > 0 && unsafe { *bytes.get_unchecked(i - 1) } == b':' {
+            return false;
+        }
+
         i += 1;
     }
 
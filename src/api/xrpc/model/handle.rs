@@ -1,5 +1,5 @@
 use {
-    memchr::{memchr, memrchr},
+    memchr::memrchr,
     serde::{Deserialize, Serialize, Serializer},
 };
 
@@ -102,18 +102,10 @@ pub fn validate_handle(handle: &[u8]) -> bool {
     };
 
     // SAFETY: `memrchr` returns a valid index.
-    let mut rest = unsafe { handle.get_unchecked(..last_dot) };
+    let labels = unsafe { handle.get_unchecked(..last_dot) };
+    let tld = unsafe { handle.get_unchecked(last_dot + 1..) };
 
-    loop {
-        let index = memchr(b'.', rest).unwrap_or(rest.len());
-
-        let label = unsafe { rest.get_unchecked(..index) };
-        rest = unsafe { rest.get_unchecked((index + 1)..) };
-
-        if !validate_label(label) {
-            return false;
-        }
-    }
+    labels.split(|&c| c == b'.').all(validate_label) && validate_tld(tld)
 }
 
 fn validate_tld(tld: &[u8]) -> bool {
@@ -135,20 +127,13 @@ fn validate_label(label: &[u8]) -> bool {
         return false;
     }
 
-    if let [unique] = label {
-        return unique.is_ascii_lowercase();
-    }
-
-    let first = unsafe { *label.get_unchecked(0) };
-    let last = unsafe { *label.get_unchecked(label.len() - 1) };
-    let rest = unsafe { label.get_unchecked(1..(label.len() - 1)) };
-
-    if !first.is_ascii_lowercase() || !last.is_ascii_lowercase() {
+    if label.first() == Some(&b'-') || label.last() == Some(&b'-') {
         return false;
     }
 
-    rest.iter()
-        .all(|&c| matches!(c, b'a'..=b'z' | b'0'..=b'9' | b'-' ))
+    label
+        .iter()
+        .all(|&c| matches!(c, b'a'..=b'z' | b'0'..=b'9' | b'-'))
 }
 
 impl<T: ?Sized + AsRef<str>> Serialize for Handle<T> {
@@ -172,3 +157,19 @@ where
             .and_then(|val| Handle::new(val).map_err(serde::de::Error::custom))
     }
 }
+
+#[cfg(test)]
+#[test]
+fn basic_handle() {
+    assert!(validate_handle(b"alice.bsky.social"));
+    assert!(validate_handle(b"8.cn"));
+}
+
+#[cfg(test)]
+#[test]
+fn handle_with_invalid_labels() {
+    assert!(!validate_handle(b"alice..social"));
+    assert!(!validate_handle(b"-alice.social"));
+    assert!(!validate_handle(b"alice.social."));
+    assert!(!validate_handle(b"alice.123"));
+}
@@ -0,0 +1,171 @@
+use {
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::fmt::Display,
+};
+
+/// Errors that can occur when parsing a language tag from a string.
+#[derive(Debug, Clone)]
+pub struct LanguageParseError;
+
+impl std::fmt::Display for LanguageParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid language tag format")
+    }
+}
+
+impl std::error::Error for LanguageParseError {}
+
+/// Represents a BCP 47 language tag, as defined by the `language` string
+/// format of the [AT Protocol data model](https://atproto.com/specs/lexicon#language).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Language<T: ?Sized = Box<str>>(T);
+
+impl<T> Language<T> {
+    /// Creates a new [`Language`] instance from the provided value without
+    /// validating it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the provided value is a valid language tag.
+    #[inline]
+    pub const unsafe fn new_unchecked(val: T) -> Self {
+        Language(val)
+    }
+}
+
+impl<T> Language<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    /// Creates a new [`Language`] instance from the provided value.
+    ///
+    /// If the value is not a valid language tag, this function fails.
+    pub fn new(val: T) -> Result<Self, LanguageParseError>
+    where
+        T: Sized,
+    {
+        if validate_language(val.as_ref().as_bytes()) {
+            Ok(unsafe { Language::new_unchecked(val) })
+        } else {
+            Err(LanguageParseError)
+        }
+    }
+
+    /// Returns the underlying string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> Display for Language<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Validates the provided `bytes` string as a language tag.
+///
+/// Only the overall structure of the tag is checked: a two or three letter
+/// primary language code (or the `i` grandfathered prefix), followed by
+/// alphanumeric subtags.
+pub fn validate_language(bytes: &[u8]) -> bool {
+    if bytes.len() > 128 {
+        return false;
+    }
+
+    let mut subtags = bytes.split(|&c| c == b'-');
+
+    let Some(primary) = subtags.next() else {
+        return false;
+    };
+
+    let primary_valid = primary == b"i"
+        || ((2..=3).contains(&primary.len()) && primary.iter().all(u8::is_ascii_lowercase));
+
+    primary_valid
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.iter().all(u8::is_ascii_alphanumeric)
+        })
+}
+
+impl<T> Serialize for Language<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Language<T>
+where
+    T: Deserialize<'de> + AsRef<str>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+            .and_then(|inner| Self::new(inner).map_err(serde::de::Error::custom))
+    }
+}
+
+impl<T> AsRef<str> for Language<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Language<&'a str> {
+    type Error = LanguageParseError;
+
+    #[inline]
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<Box<str>> for Language<Box<str>> {
+    type Error = LanguageParseError;
+
+    #[inline]
+    fn try_from(value: Box<str>) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<String> for Language<String> {
+    type Error = LanguageParseError;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn language_tags() {
+    assert!(validate_language(b"en"));
+    assert!(validate_language(b"ja"));
+    assert!(validate_language(b"pt-BR"));
+    assert!(validate_language(b"zh-Hans-CN"));
+    assert!(validate_language(b"i-default"));
+    assert!(validate_language(b"ast"));
+
+    assert!(!validate_language(b""));
+    assert!(!validate_language(b"x"));
+    assert!(!validate_language(b"EN"));
+    assert!(!validate_language(b"english"));
+    assert!(!validate_language(b"en-"));
+    assert!(!validate_language(b"en--US"));
+    assert!(!validate_language(b"en_US"));
+}
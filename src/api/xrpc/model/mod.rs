@@ -9,3 +9,27 @@ pub use self::handle::*;
 
 mod at_identifier;
 pub use self::at_identifier::*;
+
+mod cid;
+pub use self::cid::*;
+
+mod tid;
+pub use self::tid::*;
+
+mod nsid;
+pub use self::nsid::*;
+
+mod record_key;
+pub use self::record_key::*;
+
+mod datetime;
+pub use self::datetime::*;
+
+mod language;
+pub use self::language::*;
+
+mod uri;
+pub use self::uri::*;
+
+mod string_format;
+pub use self::string_format::*;
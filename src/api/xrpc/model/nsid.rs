@@ -0,0 +1,262 @@
+use {
+    memchr::memrchr,
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::fmt::Display,
+};
+
+/// Errors that can occur when parsing an NSID from a string.
+#[derive(Debug, Clone)]
+pub struct NsidParseError;
+
+impl std::fmt::Display for NsidParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid NSID format")
+    }
+}
+
+impl std::error::Error for NsidParseError {}
+
+/// The maximum length of an NSID.
+pub const MAX_NSID_LEN: usize = 317;
+
+/// The maximum length of the authority of an NSID.
+const MAX_AUTHORITY_LEN: usize = 253;
+
+/// The maximum length of a single segment of an NSID.
+const MAX_SEGMENT_LEN: usize = 63;
+
+/// Represents a Namespaced IDentifier (NSID), as defined in the
+/// [AT Protocol specification](https://atproto.com/specs/nsid).
+///
+/// An NSID is made of a reversed domain name (the authority) followed by a
+/// name, such as `com.atproto.repo.getRecord`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Nsid<T: ?Sized = Box<str>>(T);
+
+impl<T> Nsid<T> {
+    /// Creates a new [`Nsid`] instance from the provided value without
+    /// validating it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the provided value is a valid NSID.
+    #[inline]
+    pub const unsafe fn new_unchecked(val: T) -> Self {
+        Nsid(val)
+    }
+}
+
+impl<T> Nsid<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    /// Creates a new [`Nsid`] instance from the provided value.
+    ///
+    /// If the value is not a valid NSID, this function fails.
+    pub fn new(val: T) -> Result<Self, NsidParseError>
+    where
+        T: Sized,
+    {
+        if validate_nsid(val.as_ref().as_bytes()) {
+            Ok(unsafe { Nsid::new_unchecked(val) })
+        } else {
+            Err(NsidParseError)
+        }
+    }
+
+    /// Returns the underlying string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
+
+    /// Returns the position of the last dot of the NSID.
+    #[inline]
+    fn name_separator(&self) -> usize {
+        // SAFETY: A valid NSID always contains at least two dots.
+        unsafe { memrchr(b'.', self.as_str().as_bytes()).unwrap_unchecked() }
+    }
+
+    /// Returns the authority of the NSID, as a reversed domain name.
+    ///
+    /// For `com.atproto.repo.getRecord`, this is `com.atproto.repo`.
+    pub fn authority(&self) -> &str {
+        let s = self.as_str();
+        unsafe { s.get_unchecked(..self.name_separator()) }
+    }
+
+    /// Returns the name of the NSID.
+    ///
+    /// For `com.atproto.repo.getRecord`, this is `getRecord`.
+    pub fn name(&self) -> &str {
+        let s = self.as_str();
+        unsafe { s.get_unchecked(self.name_separator() + 1..) }
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> Display for Nsid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Validates the provided `bytes` string as an NSID.
+pub fn validate_nsid(bytes: &[u8]) -> bool {
+    if bytes.len() > MAX_NSID_LEN {
+        return false;
+    }
+
+    let Some(name_start) = memrchr(b'.', bytes) else {
+        return false;
+    };
+
+    let authority = &bytes[..name_start];
+    let name = &bytes[name_start + 1..];
+
+    validate_authority(authority) && validate_name(name)
+}
+
+/// Validates the authority of an NSID.
+///
+/// The authority must contain at least two segments, all of them lowercase.
+/// The first segment is the top-level domain and may not start with a digit.
+fn validate_authority(authority: &[u8]) -> bool {
+    #[inline]
+    fn is_segment_char(c: &u8) -> bool {
+        matches!(c, b'a'..=b'z' | b'0'..=b'9' | b'-')
+    }
+
+    fn validate_segment(segment: &[u8]) -> bool {
+        !segment.is_empty()
+            && segment.len() <= MAX_SEGMENT_LEN
+            && segment.iter().all(is_segment_char)
+            && segment.first() != Some(&b'-')
+            && segment.last() != Some(&b'-')
+    }
+
+    if authority.len() > MAX_AUTHORITY_LEN {
+        return false;
+    }
+
+    let mut segments = authority.split(|&c| c == b'.');
+
+    let Some(tld) = segments.next() else {
+        return false;
+    };
+    if !validate_segment(tld) || tld[0].is_ascii_digit() {
+        return false;
+    }
+
+    let mut count = 1;
+    for segment in segments {
+        if !validate_segment(segment) {
+            return false;
+        }
+        count += 1;
+    }
+
+    count >= 2
+}
+
+/// Validates the name of an NSID.
+///
+/// The name is made of ASCII letters and digits, and may not start with a
+/// digit. It is usually written in camelCase.
+fn validate_name(name: &[u8]) -> bool {
+    match name.first() {
+        Some(c) if c.is_ascii_alphabetic() => (),
+        _ => return false,
+    }
+
+    name.len() <= MAX_SEGMENT_LEN && name.iter().all(u8::is_ascii_alphanumeric)
+}
+
+impl<T> Serialize for Nsid<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Nsid<T>
+where
+    T: Deserialize<'de> + AsRef<str>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+            .and_then(|inner| Self::new(inner).map_err(serde::de::Error::custom))
+    }
+}
+
+impl<T> AsRef<str> for Nsid<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Nsid<&'a str> {
+    type Error = NsidParseError;
+
+    #[inline]
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<Box<str>> for Nsid<Box<str>> {
+    type Error = NsidParseError;
+
+    #[inline]
+    fn try_from(value: Box<str>) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<String> for Nsid<String> {
+    type Error = NsidParseError;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn basic_nsid() {
+    let nsid = Nsid::try_from("com.atproto.repo.getRecord").unwrap();
+    assert_eq!(nsid.authority(), "com.atproto.repo");
+    assert_eq!(nsid.name(), "getRecord");
+
+    assert!(validate_nsid(b"com.example.fooBar"));
+    assert!(validate_nsid(b"net.users.bob.ping"));
+    assert!(validate_nsid(b"a-0.b-1.c"));
+    assert!(validate_nsid(b"cn.8.lex.stuff"));
+}
+
+#[cfg(test)]
+#[test]
+fn invalid_nsids() {
+    assert!(!validate_nsid(b"com.example"));
+    assert!(!validate_nsid(b"com.example."));
+    assert!(!validate_nsid(b"com..example.foo"));
+    assert!(!validate_nsid(b"Com.Example.foo"));
+    assert!(!validate_nsid(b"com.example.3foo"));
+    assert!(!validate_nsid(b"com.example.foo-bar"));
+    assert!(!validate_nsid(b"com.-example.foo"));
+    assert!(!validate_nsid(b"1com.example.foo"));
+    assert!(!validate_nsid(b"com.example.foo/bar"));
+    assert!(!validate_nsid("com.example.".repeat(30).as_bytes()));
+}
@@ -0,0 +1,178 @@
+use {
+    super::Tid,
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::fmt::Display,
+};
+
+/// Errors that can occur when parsing a record key from a string.
+#[derive(Debug, Clone)]
+pub struct RecordKeyParseError;
+
+impl std::fmt::Display for RecordKeyParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid record key format")
+    }
+}
+
+impl std::error::Error for RecordKeyParseError {}
+
+/// The maximum length of a record key.
+pub const MAX_RECORD_KEY_LEN: usize = 512;
+
+/// Represents the key of a record within a collection, as defined in the
+/// [AT Protocol specification](https://atproto.com/specs/record-key).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecordKey<T: ?Sized = Box<str>>(T);
+
+impl<T> RecordKey<T> {
+    /// Creates a new [`RecordKey`] instance from the provided value without
+    /// validating it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the provided value is a valid record key.
+    #[inline]
+    pub const unsafe fn new_unchecked(val: T) -> Self {
+        RecordKey(val)
+    }
+}
+
+impl<T> RecordKey<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    /// Creates a new [`RecordKey`] instance from the provided value.
+    ///
+    /// If the value is not a valid record key, this function fails.
+    pub fn new(val: T) -> Result<Self, RecordKeyParseError>
+    where
+        T: Sized,
+    {
+        if validate_record_key(val.as_ref().as_bytes()) {
+            Ok(unsafe { RecordKey::new_unchecked(val) })
+        } else {
+            Err(RecordKeyParseError)
+        }
+    }
+
+    /// Returns the underlying string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+impl RecordKey {
+    /// Creates a record key from the provided TID.
+    pub fn from_tid(tid: Tid) -> Self {
+        // SAFETY: TIDs are always valid record keys.
+        unsafe { Self::new_unchecked(tid.to_string().into_boxed_str()) }
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> Display for RecordKey<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Validates the provided `bytes` string as a record key.
+pub fn validate_record_key(bytes: &[u8]) -> bool {
+    #[inline]
+    fn is_record_key_char(c: &u8) -> bool {
+        matches!(c, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b':' | b'~')
+    }
+
+    !bytes.is_empty()
+        && bytes.len() <= MAX_RECORD_KEY_LEN
+        && bytes != b"."
+        && bytes != b".."
+        && bytes.iter().all(is_record_key_char)
+}
+
+impl<T> Serialize for RecordKey<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for RecordKey<T>
+where
+    T: Deserialize<'de> + AsRef<str>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+            .and_then(|inner| Self::new(inner).map_err(serde::de::Error::custom))
+    }
+}
+
+impl<T> AsRef<str> for RecordKey<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for RecordKey<&'a str> {
+    type Error = RecordKeyParseError;
+
+    #[inline]
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<Box<str>> for RecordKey<Box<str>> {
+    type Error = RecordKeyParseError;
+
+    #[inline]
+    fn try_from(value: Box<str>) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<String> for RecordKey<String> {
+    type Error = RecordKeyParseError;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn basic_record_keys() {
+    assert!(validate_record_key(b"3jui7kd54zh2y"));
+    assert!(validate_record_key(b"self"));
+    assert!(validate_record_key(b"example.com"));
+    assert!(validate_record_key(b"~1.2-3_"));
+    assert!(validate_record_key(b"dHJ1ZQ"));
+    assert!(validate_record_key(b"pre:fix"));
+    assert!(validate_record_key(b"..."));
+}
+
+#[cfg(test)]
+#[test]
+fn invalid_record_keys() {
+    assert!(!validate_record_key(b""));
+    assert!(!validate_record_key(b"."));
+    assert!(!validate_record_key(b".."));
+    assert!(!validate_record_key(b"alpha/beta"));
+    assert!(!validate_record_key(b"@handle"));
+    assert!(!validate_record_key(b"any space"));
+    assert!(!validate_record_key(b"number#sign"));
+    assert!(!validate_record_key(&[b'a'; 513]));
+}
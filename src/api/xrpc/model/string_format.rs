@@ -0,0 +1,126 @@
+use super::{
+    validate_at_identifier, validate_at_uri, validate_cid_string, validate_datetime, validate_did,
+    validate_handle, validate_language, validate_nsid, validate_record_key, validate_tid,
+    validate_uri,
+};
+
+/// A string format that can be declared by a Lexicon schema.
+///
+/// See the [Lexicon specification](https://atproto.com/specs/lexicon#string-formats)
+/// for the list of formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StringFormat {
+    /// `at-identifier`: a DID or a handle.
+    AtIdentifier,
+    /// `at-uri`: an `at://` URI.
+    AtUri,
+    /// `cid`: a CID in its string form.
+    Cid,
+    /// `datetime`: an RFC 3339 timestamp with a timezone.
+    Datetime,
+    /// `did`: a DID.
+    Did,
+    /// `handle`: a handle.
+    Handle,
+    /// `nsid`: an NSID.
+    Nsid,
+    /// `tid`: a TID.
+    Tid,
+    /// `record-key`: a record key.
+    RecordKey,
+    /// `uri`: a generic URI.
+    Uri,
+    /// `language`: a BCP 47 language tag.
+    Language,
+}
+
+impl StringFormat {
+    /// Returns the format with the provided name, as it appears in Lexicon
+    /// schemas.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "at-identifier" => Self::AtIdentifier,
+            "at-uri" => Self::AtUri,
+            "cid" => Self::Cid,
+            "datetime" => Self::Datetime,
+            "did" => Self::Did,
+            "handle" => Self::Handle,
+            "nsid" => Self::Nsid,
+            "tid" => Self::Tid,
+            "record-key" => Self::RecordKey,
+            "uri" => Self::Uri,
+            "language" => Self::Language,
+            _ => return None,
+        })
+    }
+
+    /// Returns the name of the format, as it appears in Lexicon schemas.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AtIdentifier => "at-identifier",
+            Self::AtUri => "at-uri",
+            Self::Cid => "cid",
+            Self::Datetime => "datetime",
+            Self::Did => "did",
+            Self::Handle => "handle",
+            Self::Nsid => "nsid",
+            Self::Tid => "tid",
+            Self::RecordKey => "record-key",
+            Self::Uri => "uri",
+            Self::Language => "language",
+        }
+    }
+
+    /// Returns whether the provided string follows this format.
+    pub fn validate(self, s: &str) -> bool {
+        let bytes = s.as_bytes();
+
+        match self {
+            Self::AtIdentifier => validate_at_identifier(bytes),
+            Self::AtUri => validate_at_uri(bytes),
+            Self::Cid => validate_cid_string(bytes),
+            Self::Datetime => validate_datetime(bytes),
+            Self::Did => validate_did(bytes),
+            Self::Handle => validate_handle(bytes),
+            Self::Nsid => validate_nsid(bytes),
+            Self::Tid => validate_tid(bytes),
+            Self::RecordKey => validate_record_key(bytes),
+            Self::Uri => validate_uri(bytes),
+            Self::Language => validate_language(bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn string_formats() {
+    let cases = [
+        ("at-identifier", "alice.example.com"),
+        ("at-identifier", "did:plc:abc123"),
+        (
+            "at-uri",
+            "at://did:plc:abc123/app.bsky.feed.post/3jui7kd54zh2y",
+        ),
+        (
+            "cid",
+            "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+        ),
+        ("datetime", "1985-04-12T23:20:50.123Z"),
+        ("did", "did:web:example.com"),
+        ("handle", "alice.example.com"),
+        ("nsid", "app.bsky.feed.post"),
+        ("tid", "3jui7kd54zh2y"),
+        ("record-key", "self"),
+        ("uri", "https://example.com"),
+        ("language", "en-US"),
+    ];
+
+    for (name, value) in cases {
+        let format = StringFormat::from_name(name).unwrap();
+        assert_eq!(format.name(), name);
+        assert!(format.validate(value), "{value:?} is not a valid {name}");
+        assert!(!format.validate("not valid!"), "{name} accepted garbage");
+    }
+
+    assert_eq!(StringFormat::from_name("unknown"), None);
+}
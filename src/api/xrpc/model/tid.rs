@@ -0,0 +1,230 @@
+use {
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::{
+        fmt::Display,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            OnceLock,
+        },
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Errors that can occur when parsing a TID from a string.
+#[derive(Debug, Clone)]
+pub struct TidParseError;
+
+impl std::fmt::Display for TidParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid TID format")
+    }
+}
+
+impl std::error::Error for TidParseError {}
+
+/// The characters used by the sortable base32 encoding of TIDs, in
+/// increasing order.
+const ALPHABET: &[u8; 32] = b"234567abcdefghijklmnopqrstuvwxyz";
+
+/// The length of an encoded TID.
+pub const TID_LEN: usize = 13;
+
+/// The number of bits used by the clock identifier of a TID.
+const CLOCK_ID_BITS: u32 = 10;
+
+/// The largest timestamp that fits in a TID.
+const MAX_TIMESTAMP: u64 = (1 << 53) - 1;
+
+/// Represents a Timestamp IDentifier (TID), as defined in the
+/// [AT Protocol specification](https://atproto.com/specs/tid).
+///
+/// A TID is a 64-bit integer made of a microsecond timestamp and a random
+/// clock identifier, encoded as a 13-character sortable base32 string. TIDs
+/// compare in the same order as their string representation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tid(u64);
+
+impl Tid {
+    /// Creates a new [`Tid`] from a timestamp (in microseconds since the UNIX
+    /// epoch) and a clock identifier.
+    ///
+    /// Extra bits are truncated.
+    pub const fn from_parts(timestamp: u64, clock_id: u16) -> Self {
+        let timestamp = timestamp & MAX_TIMESTAMP;
+        let clock_id = clock_id as u64 & ((1 << CLOCK_ID_BITS) - 1);
+        Self((timestamp << CLOCK_ID_BITS) | clock_id)
+    }
+
+    /// Returns the timestamp of the TID, in microseconds since the UNIX
+    /// epoch.
+    #[inline]
+    pub const fn timestamp(self) -> u64 {
+        self.0 >> CLOCK_ID_BITS
+    }
+
+    /// Returns the clock identifier of the TID.
+    #[inline]
+    pub const fn clock_id(self) -> u16 {
+        (self.0 & ((1 << CLOCK_ID_BITS) - 1)) as u16
+    }
+
+    /// Returns the integer representation of the TID.
+    #[inline]
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Returns a new TID for the current time.
+    ///
+    /// TIDs returned by this function are strictly increasing for the whole
+    /// lifetime of the process, even if the system clock goes backwards or
+    /// the function is called concurrently from multiple threads. All of
+    /// them share the same clock identifier, randomly chosen when the
+    /// function is first called.
+    pub fn next() -> Self {
+        static CLOCK_ID: OnceLock<u16> = OnceLock::new();
+        static LAST_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+
+        let clock_id = *CLOCK_ID.get_or_init(rand::random);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+
+        // If the clock did not move forward since the last TID was generated,
+        // pretend that it did.
+        let prev = LAST_TIMESTAMP
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(now.max(last + 1))
+            })
+            .unwrap();
+
+        Self::from_parts(now.max(prev + 1), clock_id)
+    }
+}
+
+impl Display for Tid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut buf = [0u8; TID_LEN];
+        for (i, c) in buf.iter_mut().enumerate() {
+            let shift = 5 * (TID_LEN - 1 - i);
+            *c = ALPHABET[((self.0 >> shift) & 0x1F) as usize];
+        }
+
+        // SAFETY: The alphabet only contains ASCII characters.
+        f.pad(unsafe { std::str::from_utf8_unchecked(&buf) })
+    }
+}
+
+/// Returns the value of the provided base32 character.
+#[inline]
+fn decode_char(c: u8) -> Option<u64> {
+    match c {
+        b'2'..=b'7' => Some((c - b'2') as u64),
+        b'a'..=b'z' => Some((c - b'a') as u64 + 6),
+        _ => None,
+    }
+}
+
+/// Validates the provided `bytes` string as a TID.
+pub fn validate_tid(bytes: &[u8]) -> bool {
+    parse_tid(bytes).is_some()
+}
+
+/// Parses the provided `bytes` string as a TID.
+fn parse_tid(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() != TID_LEN {
+        return None;
+    }
+
+    // The first character only encodes the top four bits of the integer,
+    // which restricts it to the first half of the alphabet.
+    if !matches!(bytes[0], b'2'..=b'7' | b'a'..=b'j') {
+        return None;
+    }
+
+    bytes
+        .iter()
+        .try_fold(0u64, |acc, &c| Some((acc << 5) | decode_char(c)?))
+}
+
+impl FromStr for Tid {
+    type Err = TidParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_tid(s.as_bytes()).map(Self).ok_or(TidParseError)
+    }
+}
+
+impl TryFrom<&str> for Tid {
+    type Error = TidParseError;
+
+    #[inline]
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Serialize for Tid {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tid {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <std::borrow::Cow<'de, str>>::deserialize(deserializer)
+            .and_then(|s| s.parse().map_err(serde::de::Error::custom))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn tid_string_round_trip() {
+    let tid: Tid = "3jzfcijpj2z2a".parse().unwrap();
+    assert_eq!(tid.to_string(), "3jzfcijpj2z2a");
+    assert_eq!(Tid::from_parts(tid.timestamp(), tid.clock_id()), tid);
+
+    assert_eq!(Tid::from_parts(0, 0).to_string(), "2222222222222");
+    assert_eq!(
+        Tid::from_parts(u64::MAX, u16::MAX).to_string(),
+        "bzzzzzzzzzzzz"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn invalid_tids() {
+    assert!(validate_tid(b"7777777777777"));
+    assert!(!validate_tid(b"3jzfcijpj2z2"));
+    assert!(!validate_tid(b"3jzfcijpj2z2aa"));
+    assert!(!validate_tid(b"3jzfcijpj2z21"));
+    assert!(!validate_tid(b"3JZFCIJPJ2Z2A"));
+    assert!(!validate_tid(b"kjzfcijpj2z2a"));
+}
+
+#[cfg(test)]
+#[test]
+fn generated_tids_increase() {
+    let threads: Vec<_> = (0..4)
+        .map(|_| std::thread::spawn(|| (0..1000).map(|_| Tid::next()).collect::<Vec<_>>()))
+        .collect();
+
+    let mut all = Vec::new();
+    for thread in threads {
+        let tids = thread.join().unwrap();
+        assert!(tids.windows(2).all(|w| w[0] < w[1]));
+        all.extend(tids);
+    }
+
+    all.sort_unstable();
+    all.dedup();
+    assert_eq!(all.len(), 4000);
+}
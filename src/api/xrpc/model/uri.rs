@@ -0,0 +1,175 @@
+use {
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::fmt::Display,
+};
+
+/// Errors that can occur when parsing a URI from a string.
+#[derive(Debug, Clone)]
+pub struct UriParseError;
+
+impl std::fmt::Display for UriParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid URI format")
+    }
+}
+
+impl std::error::Error for UriParseError {}
+
+/// Represents a generic URI, as defined by the `uri` string format of the
+/// [AT Protocol data model](https://atproto.com/specs/lexicon#uri).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uri<T: ?Sized = Box<str>>(T);
+
+impl<T> Uri<T> {
+    /// Creates a new [`Uri`] instance from the provided value without
+    /// validating it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the provided value is a valid URI.
+    #[inline]
+    pub const unsafe fn new_unchecked(val: T) -> Self {
+        Uri(val)
+    }
+}
+
+impl<T> Uri<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    /// Creates a new [`Uri`] instance from the provided value.
+    ///
+    /// If the value is not a valid URI, this function fails.
+    pub fn new(val: T) -> Result<Self, UriParseError>
+    where
+        T: Sized,
+    {
+        if validate_uri(val.as_ref().as_bytes()) {
+            Ok(unsafe { Uri::new_unchecked(val) })
+        } else {
+            Err(UriParseError)
+        }
+    }
+
+    /// Returns the underlying string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> Display for Uri<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// The maximum length of a URI.
+pub const MAX_URI_LEN: usize = 8 * 1024;
+
+/// Validates the provided `bytes` string as a URI.
+///
+/// The URI must start with a scheme and must not contain whitespace or
+/// non-ASCII characters. The rest of the syntax is not checked.
+pub fn validate_uri(bytes: &[u8]) -> bool {
+    #[inline]
+    fn is_scheme_char(c: &u8) -> bool {
+        matches!(c, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'+' | b'.' | b'-')
+    }
+
+    if bytes.len() > MAX_URI_LEN {
+        return false;
+    }
+
+    let Some(colon) = memchr::memchr(b':', bytes) else {
+        return false;
+    };
+
+    let (scheme, rest) = (&bytes[..colon], &bytes[colon + 1..]);
+
+    scheme.first().is_some_and(u8::is_ascii_alphabetic)
+        && scheme.iter().all(is_scheme_char)
+        && !rest.is_empty()
+        && rest.iter().all(u8::is_ascii_graphic)
+}
+
+impl<T> Serialize for Uri<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Uri<T>
+where
+    T: Deserialize<'de> + AsRef<str>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+            .and_then(|inner| Self::new(inner).map_err(serde::de::Error::custom))
+    }
+}
+
+impl<T> AsRef<str> for Uri<T>
+where
+    T: ?Sized + AsRef<str>,
+{
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Uri<&'a str> {
+    type Error = UriParseError;
+
+    #[inline]
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<Box<str>> for Uri<Box<str>> {
+    type Error = UriParseError;
+
+    #[inline]
+    fn try_from(value: Box<str>) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<String> for Uri<String> {
+    type Error = UriParseError;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn uris() {
+    assert!(validate_uri(b"https://example.com/path?query#fragment"));
+    assert!(validate_uri(
+        b"at://did:plc:abc123/app.bsky.feed.post/3jui7kd54zh2y"
+    ));
+    assert!(validate_uri(b"dns:example.com"));
+    assert!(validate_uri(b"did:plc:abc123"));
+    assert!(validate_uri(b"mailto:alice@example.com"));
+
+    assert!(!validate_uri(b""));
+    assert!(!validate_uri(b"example.com"));
+    assert!(!validate_uri(b"https:"));
+    assert!(!validate_uri(b"1http://example.com"));
+    assert!(!validate_uri(b"https://example.com/with space"));
+    assert!(!validate_uri("https://exämple.com".as_bytes()));
+}
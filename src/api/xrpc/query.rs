@@ -0,0 +1,215 @@
+//! Deserialization of the query parameters of XRPC requests.
+//!
+//! Query parameters are decoded with `serde_urlencoded`, but unlike it, a
+//! parameter may be repeated to provide an array (`?cids=a&cids=b`), as
+//! expected by XRPC methods.
+
+use {
+    serde::{
+        de::{
+            value::{Error, MapDeserializer, SeqDeserializer},
+            DeserializeOwned, Error as _, IntoDeserializer, Visitor,
+        },
+        forward_to_deserialize_any, Deserializer,
+    },
+    std::collections::BTreeMap,
+};
+
+/// Deserializes a `T` from the provided query string.
+pub fn from_query_str<T: DeserializeOwned>(query: &str) -> Result<T, Error> {
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(query).map_err(Error::custom)?;
+
+    let mut params = BTreeMap::<String, Vec<String>>::new();
+    for (key, value) in pairs {
+        params.entry(key).or_default().push(value);
+    }
+
+    T::deserialize(MapDeserializer::new(
+        params.into_iter().map(|(k, v)| (k, Values(v))),
+    ))
+}
+
+/// All the values provided for a single parameter.
+struct Values(Vec<String>);
+
+impl Values {
+    /// Returns the value of the parameter, failing if it was repeated.
+    fn single(mut self) -> Result<Scalar, Error> {
+        match self.0.len() {
+            1 => Ok(Scalar(self.0.pop().unwrap())),
+            _ => Err(Error::custom("expected a single value")),
+        }
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Values {
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Forwards the provided methods to the [`Scalar`] value of the parameter.
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Values {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0.len() {
+            1 => self.single()?.deserialize_any(visitor),
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(SeqDeserializer::new(self.0.into_iter().map(Scalar)))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 bytes byte_buf unit unit_struct tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// A single value of a parameter.
+struct Scalar(String);
+
+impl IntoDeserializer<'_, Error> for Scalar {
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Implements the provided methods by parsing the value.
+macro_rules! parse_scalar {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(val) => visitor.$visit(val),
+                    Err(_) => Err(Error::custom(format_args!("invalid value: `{}`", self.0))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Scalar {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0
+            .into_deserializer()
+            .deserialize_enum(name, variants, visitor)
+    }
+
+    parse_scalar! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn repeated_params() {
+    #[derive(serde::Deserialize)]
+    struct Params {
+        did: super::model::Did,
+        cids: Vec<super::model::CidString>,
+        limit: Option<i64>,
+        reverse: Option<bool>,
+        cursor: Option<String>,
+    }
+
+    let cid = "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm";
+    let params: Params = from_query_str(&format!(
+        "did=did%3Aplc%3Aabc123&cids={cid}&cids={cid}&limit=10&reverse=true"
+    ))
+    .unwrap();
+
+    assert_eq!(params.did.as_str(), "did:plc:abc123");
+    assert_eq!(params.cids.len(), 2);
+    assert_eq!(params.limit, Some(10));
+    assert_eq!(params.reverse, Some(true));
+    assert_eq!(params.cursor, None);
+
+    assert!(from_query_str::<Params>("did=did%3Aplc%3Aabc123&cids=x&limit=ten").is_err());
+    assert!(from_query_str::<Params>("cids=x").is_err());
+    assert!(from_query_str::<Params>("did=did%3Aplc%3Aa&did=did%3Aplc%3Ab&cids=x").is_err());
+}
@@ -0,0 +1,289 @@
+use {
+    super::{read_chunks, BlobStore, BlobStoreError, StoredBlob, TempBlob},
+    crate::api::xrpc::model::{Cid, Codec, Did},
+    futures::{Stream, StreamExt},
+    hyper::body::Bytes,
+    rand::RngCore,
+    sha2::{Digest, Sha256},
+    std::{
+        future::Future,
+        io::ErrorKind,
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+    tokio::io::AsyncWriteExt,
+};
+
+/// A [`BlobStore`] keeping blobs in a directory of the filesystem.
+///
+/// Blobs are stored in files named after their CID:
+///
+/// - `tmp/<did>/<cid>` for the temporary blobs of each account,
+/// - `blobs/<did>/<cid>` for the blobs of each account.
+///
+/// Uploads are first written to `tmp/.upload-<random>`, and renamed once
+/// their CID is known, so a blob file is never seen partially written. Blobs
+/// are made permanent by hard-linking the temporary file into the directory
+/// of the account.
+#[derive(Debug)]
+pub struct FsBlobStore {
+    /// The directory holding the temporary blobs.
+    temp_dir: PathBuf,
+    /// The directory holding the directories of each account.
+    blob_dir: PathBuf,
+}
+
+impl FsBlobStore {
+    /// Creates a new [`FsBlobStore`] storing its blobs in the provided
+    /// directory.
+    ///
+    /// The directory is created if it does not exist.
+    pub fn new(root: &Path) -> std::io::Result<Self> {
+        let temp_dir = root.join("tmp");
+        let blob_dir = root.join("blobs");
+        std::fs::create_dir_all(&temp_dir)?;
+        std::fs::create_dir_all(&blob_dir)?;
+        Ok(Self { temp_dir, blob_dir })
+    }
+
+    /// Returns the directory holding the temporary blobs of the provided
+    /// account.
+    fn temp_account_dir(&self, did: &Did) -> PathBuf {
+        self.temp_dir.join(did.as_str())
+    }
+
+    /// Returns the path of a temporary blob of the provided account.
+    fn temp_path(&self, did: &Did, cid: &Cid) -> PathBuf {
+        self.temp_account_dir(did).join(cid.to_string())
+    }
+
+    /// Returns the directory holding the blobs of the provided account.
+    fn account_dir(&self, did: &Did) -> PathBuf {
+        self.blob_dir.join(did.as_str())
+    }
+
+    /// Returns the path of a blob of the provided account.
+    fn blob_path(&self, did: &Did, cid: &Cid) -> PathBuf {
+        self.account_dir(did).join(cid.to_string())
+    }
+
+    /// Writes `data` to `path`, returning its CID and size.
+    async fn write_upload<S>(path: &Path, data: S) -> Result<TempBlob, BlobStoreError>
+    where
+        S: Send + Stream<Item = std::io::Result<Bytes>>,
+    {
+        let mut data = std::pin::pin!(data);
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+
+        while let Some(chunk) = data.next().await {
+            let chunk = chunk?;
+            hasher.update(&chunk);
+            size += chunk.len() as u64;
+            file.write_all(&chunk).await?;
+        }
+        file.sync_all().await?;
+
+        Ok(TempBlob {
+            cid: Cid::new(Codec::Raw, hasher.finalize().into()),
+            size,
+        })
+    }
+}
+
+impl BlobStore for FsBlobStore {
+    fn put_temp<S>(
+        &self,
+        did: &Did,
+        data: S,
+    ) -> impl Send + Future<Output = Result<TempBlob, BlobStoreError>>
+    where
+        S: Send + Stream<Item = std::io::Result<Bytes>>,
+    {
+        let dir = self.temp_account_dir(did);
+
+        async move {
+            let upload = self
+                .temp_dir
+                .join(format!(".upload-{:016x}", rand::thread_rng().next_u64()));
+
+            match Self::write_upload(&upload, data).await {
+                Ok(blob) => {
+                    tokio::fs::create_dir_all(&dir).await?;
+                    tokio::fs::rename(&upload, dir.join(blob.cid.to_string())).await?;
+                    Ok(blob)
+                }
+                Err(err) => {
+                    let _ = tokio::fs::remove_file(&upload).await;
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    fn make_permanent(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<(), BlobStoreError>> {
+        let temp = self.temp_path(did, cid);
+        let dir = self.account_dir(did);
+        let path = self.blob_path(did, cid);
+
+        async move {
+            tokio::fs::create_dir_all(&dir).await?;
+            // The temporary blob is linked rather than moved, so that a
+            // commit that fails after making it permanent can be retried. It
+            // is removed once it expires.
+            match tokio::fs::hard_link(&temp, &path).await {
+                Ok(()) => Ok(()),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(()),
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    if tokio::fs::try_exists(&path).await? {
+                        Ok(())
+                    } else {
+                        Err(BlobStoreError::NotFound(*cid))
+                    }
+                }
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+
+    fn get(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<Option<StoredBlob>, BlobStoreError>> {
+        let path = self.blob_path(did, cid);
+
+        async move {
+            let file = match tokio::fs::File::open(&path).await {
+                Ok(file) => file,
+                Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let size = file.metadata().await?.len();
+
+            Ok(Some(StoredBlob {
+                size,
+                stream: Box::pin(read_chunks(file)),
+            }))
+        }
+    }
+
+    fn contains(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<bool, BlobStoreError>> {
+        let path = self.blob_path(did, cid);
+        async move { Ok(tokio::fs::try_exists(&path).await?) }
+    }
+
+    fn delete(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<bool, BlobStoreError>> {
+        let path = self.blob_path(did, cid);
+        async move {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => Ok(true),
+                Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+
+    fn list(&self, did: &Did) -> impl Send + Future<Output = Result<Vec<Cid>, BlobStoreError>> {
+        let dir = self.account_dir(did);
+
+        async move {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(err) => return Err(err.into()),
+            };
+
+            let mut cids = Vec::new();
+            while let Some(entry) = entries.next_entry().await? {
+                // Ignore the files that were not written by the store.
+                if let Some(cid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+                    cids.push(cid);
+                }
+            }
+            cids.sort_unstable();
+            Ok(cids)
+        }
+    }
+
+    fn expire_temp(
+        &self,
+        max_age: Duration,
+    ) -> impl Send + Future<Output = Result<usize, BlobStoreError>> {
+        async move {
+            let mut count = 0;
+
+            // Uploads that were interrupted without being cleaned up (for
+            // example by a crash) are removed as well.
+            let mut entries = tokio::fs::read_dir(&self.temp_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    count += expire_files(&entry.path(), max_age).await?;
+                } else if expire_file(&entry.path(), max_age).await? {
+                    count += 1;
+                }
+            }
+
+            Ok(count)
+        }
+    }
+}
+
+/// Removes the files of `dir` that were modified at least `max_age` ago,
+/// returning how many were removed.
+async fn expire_files(dir: &Path, max_age: Duration) -> Result<usize, BlobStoreError> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut count = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        if expire_file(&entry.path(), max_age).await? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Removes the file at `path` if it was modified at least `max_age` ago,
+/// returning whether it was removed.
+async fn expire_file(path: &Path, max_age: Duration) -> Result<bool, BlobStoreError> {
+    let modified = match tokio::fs::metadata(path).await {
+        Ok(metadata) => metadata.modified()?,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+    if modified.elapsed().unwrap_or_default() < max_age {
+        return Ok(false);
+    }
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn fs_blob_store() {
+    let root = std::env::temp_dir().join(format!("rpds-test-blobs-{}", std::process::id()));
+    let store = FsBlobStore::new(&root).unwrap();
+    super::check_blob_store(&store).await;
+
+    // Failed uploads leave nothing behind.
+    let uploads = std::fs::read_dir(root.join("tmp"))
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().file_type().unwrap().is_file());
+    assert_eq!(uploads.count(), 0);
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,254 @@
+use {
+    super::{BlobStore, BlobStoreError},
+    crate::{
+        api::xrpc::model::{Cid, Did},
+        global::{
+            self,
+            database::{ActorStoreError, Connection, Queries},
+        },
+        try_get_and_parse_env,
+    },
+    std::{collections::HashSet, time::Duration},
+    tracing::{error, info},
+};
+
+/// An error that might occur while collecting the garbage of a
+/// [`BlobStore`].
+#[derive(Debug)]
+pub enum BlobGcError {
+    /// The blob store failed.
+    Store(BlobStoreError),
+    /// The actor store of an account could not be opened.
+    ActorStore(ActorStoreError),
+    /// The database failed.
+    Database(libsql::Error),
+}
+
+impl std::fmt::Display for BlobGcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Store(err) => std::fmt::Display::fmt(err, f),
+            Self::ActorStore(err) => std::fmt::Display::fmt(err, f),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BlobGcError {}
+
+impl From<BlobStoreError> for BlobGcError {
+    #[inline]
+    fn from(value: BlobStoreError) -> Self {
+        Self::Store(value)
+    }
+}
+
+impl From<ActorStoreError> for BlobGcError {
+    #[inline]
+    fn from(value: ActorStoreError) -> Self {
+        Self::ActorStore(value)
+    }
+}
+
+impl From<libsql::Error> for BlobGcError {
+    #[inline]
+    fn from(value: libsql::Error) -> Self {
+        Self::Database(value)
+    }
+}
+
+/// Removes the blobs of an account that no record references anymore.
+///
+/// `conn` must be a connection to the actor store of the account. The blobs
+/// are removed while holding the write lock of the store: commits make the
+/// blobs they reference permanent under the same lock, so a blob can't be
+/// referenced again while it is being removed.
+///
+/// Returns the number of blobs removed.
+pub async fn sweep_account(
+    store: &impl BlobStore,
+    conn: &Connection,
+    did: &Did,
+) -> Result<usize, BlobGcError> {
+    let tx = conn.transaction().await?;
+
+    let referenced: HashSet<Cid> = tx
+        .query_all::<(Cid,)>("SELECT DISTINCT blob_cid FROM record_blob", ())
+        .await?
+        .into_iter()
+        .map(|(cid,)| cid)
+        .collect();
+
+    let mut count = 0;
+    for cid in store.list(did).await? {
+        if !referenced.contains(&cid) && store.delete(did, &cid).await? {
+            count += 1;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(count)
+}
+
+/// Periodically removes the expired temporary blobs, and the blobs that no
+/// record references anymore.
+///
+/// Temporary blobs expire after `RPDS_BLOB_TEMP_TTL` seconds (one hour by
+/// default). The garbage is collected every `RPDS_BLOB_GC_INTERVAL` seconds
+/// (one hour by default), starting immediately.
+pub async fn run_gc() {
+    let ttl = Duration::from_secs(try_get_and_parse_env("RPDS_BLOB_TEMP_TTL").unwrap_or(3600));
+    let period = try_get_and_parse_env("RPDS_BLOB_GC_INTERVAL").unwrap_or(3600);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(period));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        if let Err(err) = collect_garbage(ttl).await {
+            error!("Failed to collect the garbage of the blob store: {err}");
+        }
+    }
+}
+
+/// Runs a single pass of the blob garbage collector.
+async fn collect_garbage(ttl: Duration) -> Result<(), BlobGcError> {
+    let state = global::get();
+    let expired = state.blob_store.expire_temp(ttl).await?;
+
+    let dids = state
+        .database
+        .connect()
+        .await?
+        .query_all::<(Did,)>("SELECT did FROM accounts", ())
+        .await?;
+
+    let mut removed = 0;
+    for (did,) in dids {
+        let swept = match state.actor_stores.connect(&did).await {
+            Ok(Some(conn)) => sweep_account(&state.blob_store, &conn, &did).await,
+            Ok(None) => continue,
+            Err(err) => Err(err.into()),
+        };
+        match swept {
+            Ok(count) => removed += count,
+            Err(err) => error!("Failed to collect the blobs of `{did}`: {err}"),
+        }
+    }
+
+    if expired > 0 || removed > 0 {
+        info!("Removed {expired} expired temporary blobs and {removed} unreferenced blobs");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn sweep_unreferenced_blobs() {
+    use {
+        super::{BlobRef, MemoryBlobStore},
+        crate::{
+            dag_cbor::Value,
+            global::database::{TemporaryDatabase, ACTOR_MIGRATIONS},
+            repo::{RepoStorage, RepoTransaction, SigningKey},
+        },
+        hyper::body::Bytes,
+    };
+
+    let db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let conn = db.connect().await.unwrap();
+    let did = Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap();
+    let storage = RepoStorage::new(conn.clone(), did.clone());
+    let store = MemoryBlobStore::new();
+    let key = SigningKey::generate();
+
+    let upload = |data: &'static [u8]| {
+        let (store, did) = (&store, &did);
+        async move {
+            let blob = store
+                .put_temp(did, futures::stream::iter([Ok(Bytes::from_static(data))]))
+                .await
+                .unwrap();
+            BlobRef {
+                cid: blob.cid,
+                mime_type: "image/png".to_owned(),
+                size: blob.size,
+            }
+        }
+    };
+    let post = |blobs: &[&BlobRef]| {
+        let images = blobs.iter().map(|blob| blob.to_value()).collect();
+        Value::Map([("images".to_owned(), Value::List(images))].into())
+    };
+    let referenced = || async {
+        conn.query_all::<(Cid,)>("SELECT blob_cid FROM record_blob ORDER BY blob_cid", ())
+            .await
+            .unwrap()
+    };
+
+    RepoTransaction::genesis(&storage)
+        .commit(&key, &store)
+        .await
+        .unwrap();
+
+    let (first, second) = (upload(b"first").await, upload(b"second").await);
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    tx.put("app.bsky.feed.post/a", &post(&[&first, &second]))
+        .await
+        .unwrap();
+    tx.commit(&key, &store).await.unwrap();
+    assert_eq!(store.list(&did).await.unwrap().len(), 2);
+    assert_eq!(referenced().await.len(), 2);
+    assert_eq!(sweep_account(&store, &conn, &did).await.unwrap(), 0);
+
+    // Blobs referenced by a record that failed to commit are collected.
+    let stale = upload(b"stale").await;
+    store.make_permanent(&did, &stale.cid).await.unwrap();
+
+    // Updating a record drops the references of its previous version.
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    tx.put("app.bsky.feed.post/a", &post(&[&second]))
+        .await
+        .unwrap();
+    tx.put("app.bsky.feed.post/b", &post(&[&second]))
+        .await
+        .unwrap();
+    tx.commit(&key, &store).await.unwrap();
+    assert_eq!(referenced().await, [(second.cid,), (second.cid,)]);
+    assert_eq!(sweep_account(&store, &conn, &did).await.unwrap(), 2);
+    assert_eq!(store.list(&did).await.unwrap(), [second.cid]);
+
+    // The blob is kept until its last reference is deleted.
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    tx.delete("app.bsky.feed.post/a").await.unwrap();
+    tx.commit(&key, &store).await.unwrap();
+    assert_eq!(sweep_account(&store, &conn, &did).await.unwrap(), 0);
+
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    tx.delete("app.bsky.feed.post/b").await.unwrap();
+    tx.commit(&key, &store).await.unwrap();
+    assert!(referenced().await.is_empty());
+    assert_eq!(sweep_account(&store, &conn, &did).await.unwrap(), 1);
+    assert!(store.list(&did).await.unwrap().is_empty());
+
+    // The commit that failed can be retried, as the temporary copy of its
+    // blob is left in place.
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    tx.put("app.bsky.feed.post/c", &post(&[&stale]))
+        .await
+        .unwrap();
+    tx.commit(&key, &store).await.unwrap();
+    assert_eq!(store.list(&did).await.unwrap(), [stale.cid]);
+
+    // Records referencing a missing blob can't be committed, once its
+    // temporary copy expired.
+    store.expire_temp(Duration::ZERO).await.unwrap();
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    tx.put("app.bsky.feed.post/d", &post(&[&first]))
+        .await
+        .unwrap();
+    assert!(matches!(
+        tx.commit(&key, &store).await,
+        Err(crate::repo::RepoError::Blob(BlobStoreError::NotFound(_)))
+    ));
+}
@@ -0,0 +1,154 @@
+use {
+    super::{BlobStore, BlobStoreError, StoredBlob, TempBlob},
+    crate::api::xrpc::model::{Cid, Codec, Did},
+    futures::{Stream, StreamExt},
+    hyper::body::Bytes,
+    sha2::{Digest, Sha256},
+    std::{
+        collections::{BTreeMap, HashMap},
+        future::Future,
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// A [`BlobStore`] keeping blobs in memory.
+///
+/// This is mostly useful for tests.
+#[derive(Debug, Default)]
+pub struct MemoryBlobStore {
+    /// The temporary blobs of each account, with the time they were
+    /// written.
+    temp: Mutex<HashMap<(Did, Cid), (Bytes, Instant)>>,
+    /// The permanent blobs of each account.
+    permanent: Mutex<HashMap<Did, BTreeMap<Cid, Bytes>>>,
+}
+
+impl MemoryBlobStore {
+    /// Creates a new empty [`MemoryBlobStore`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BlobStore for MemoryBlobStore {
+    fn put_temp<S>(
+        &self,
+        did: &Did,
+        data: S,
+    ) -> impl Send + Future<Output = Result<TempBlob, BlobStoreError>>
+    where
+        S: Send + Stream<Item = std::io::Result<Bytes>>,
+    {
+        let did = did.clone();
+
+        async move {
+            let mut data = std::pin::pin!(data);
+            let mut buf = Vec::new();
+            while let Some(chunk) = data.next().await {
+                buf.extend_from_slice(&chunk?);
+            }
+
+            let cid = Cid::new(Codec::Raw, Sha256::digest(&buf).into());
+            let size = buf.len() as u64;
+            self.temp
+                .lock()
+                .unwrap()
+                .insert((did, cid), (buf.into(), Instant::now()));
+            Ok(TempBlob { cid, size })
+        }
+    }
+
+    fn make_permanent(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<(), BlobStoreError>> {
+        let mut permanent = self.permanent.lock().unwrap();
+        let blobs = permanent.entry(did.clone()).or_default();
+
+        let ret = match self.temp.lock().unwrap().get(&(did.clone(), *cid)) {
+            Some((data, _)) => {
+                blobs.insert(*cid, data.clone());
+                Ok(())
+            }
+            None if blobs.contains_key(cid) => Ok(()),
+            None => Err(BlobStoreError::NotFound(*cid)),
+        };
+        std::future::ready(ret)
+    }
+
+    fn get(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<Option<StoredBlob>, BlobStoreError>> {
+        let data = self
+            .permanent
+            .lock()
+            .unwrap()
+            .get(did)
+            .and_then(|blobs| blobs.get(cid))
+            .cloned();
+
+        std::future::ready(Ok(data.map(|data| StoredBlob {
+            size: data.len() as u64,
+            stream: Box::pin(futures::stream::once(std::future::ready(Ok(data)))),
+        })))
+    }
+
+    fn contains(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<bool, BlobStoreError>> {
+        let found = self
+            .permanent
+            .lock()
+            .unwrap()
+            .get(did)
+            .is_some_and(|blobs| blobs.contains_key(cid));
+        std::future::ready(Ok(found))
+    }
+
+    fn delete(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<bool, BlobStoreError>> {
+        let removed = self
+            .permanent
+            .lock()
+            .unwrap()
+            .get_mut(did)
+            .is_some_and(|blobs| blobs.remove(cid).is_some());
+        std::future::ready(Ok(removed))
+    }
+
+    fn list(&self, did: &Did) -> impl Send + Future<Output = Result<Vec<Cid>, BlobStoreError>> {
+        let cids = self
+            .permanent
+            .lock()
+            .unwrap()
+            .get(did)
+            .map(|blobs| blobs.keys().copied().collect())
+            .unwrap_or_default();
+        std::future::ready(Ok(cids))
+    }
+
+    fn expire_temp(
+        &self,
+        max_age: Duration,
+    ) -> impl Send + Future<Output = Result<usize, BlobStoreError>> {
+        let mut temp = self.temp.lock().unwrap();
+        let count = temp.len();
+        temp.retain(|_, (_, written_at)| written_at.elapsed() < max_age);
+        std::future::ready(Ok(count - temp.len()))
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn memory_blob_store() {
+    super::check_blob_store(&MemoryBlobStore::new()).await;
+}
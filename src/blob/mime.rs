@@ -0,0 +1,104 @@
+/// The number of bytes at the start of a blob that [`sniff_mime_type`] needs
+/// to recognize every format it knows about.
+pub const SNIFF_LEN: usize = 64;
+
+/// The MIME type used for blobs whose format is not recognized.
+pub const MIME_OCTET_STREAM: &str = "application/octet-stream";
+
+/// Guesses the MIME type of a blob from its first bytes.
+///
+/// Only the formats that are commonly embedded in records (images, videos,
+/// audio and documents) are recognized. Text-based formats are deliberately
+/// not detected, as they can't be told apart from one another reliably.
+///
+/// `head` should hold the first [`SNIFF_LEN`] bytes of the blob, or the whole
+/// blob if it is shorter.
+pub fn sniff_mime_type(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"II*\0", "image/tiff"),
+        (b"MM\0*", "image/tiff"),
+        (b"%PDF-", "application/pdf"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"ID3", "audio/mpeg"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| head.starts_with(sig)) {
+        return Some(mime);
+    }
+
+    match head {
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Some("audio/wav"),
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] if brand.len() >= 4 => {
+            Some(iso_media_mime_type(&brand[..4]))
+        }
+        [0x1a, 0x45, 0xdf, 0xa3, rest @ ..] => {
+            // The document type of the EBML header tells WebM apart from
+            // other Matroska files.
+            if rest.windows(4).any(|w| w == b"webm") {
+                Some("video/webm")
+            } else {
+                Some("video/x-matroska")
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the MIME type of an ISO base media file (MP4, QuickTime, HEIF...)
+/// from its major brand.
+fn iso_media_mime_type(brand: &[u8]) -> &'static str {
+    match brand {
+        b"avif" | b"avis" => "image/avif",
+        b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" | b"hevm" | b"hevs" => {
+            "image/heic"
+        }
+        b"mif1" | b"msf1" => "image/heif",
+        b"qt  " => "video/quicktime",
+        b"M4A " | b"M4B " => "audio/mp4",
+        _ => "video/mp4",
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn sniff() {
+    assert_eq!(
+        sniff_mime_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+        Some("image/png")
+    );
+    assert_eq!(
+        sniff_mime_type(b"\xff\xd8\xff\xe0\0\x10JFIF"),
+        Some("image/jpeg")
+    );
+    assert_eq!(
+        sniff_mime_type(b"RIFF\x24\0\0\0WEBPVP8 "),
+        Some("image/webp")
+    );
+    assert_eq!(
+        sniff_mime_type(b"\0\0\0\x1cftypavif\0\0\0\0"),
+        Some("image/avif")
+    );
+    assert_eq!(
+        sniff_mime_type(b"\0\0\0\x20ftypisom\0\0\x02\0"),
+        Some("video/mp4")
+    );
+    assert_eq!(
+        sniff_mime_type(b"\x1a\x45\xdf\xa3\x9f\x42\x86\x81\x01\x42\x82\x84webm"),
+        Some("video/webm")
+    );
+
+    // Truncated or unknown content.
+    assert_eq!(sniff_mime_type(b"RIFF\x24\0"), None);
+    assert_eq!(sniff_mime_type(b"\0\0\0\x1cftyp"), None);
+    assert_eq!(
+        sniff_mime_type(b"<svg xmlns=\"http://www.w3.org/2000/svg\">"),
+        None
+    );
+    assert_eq!(sniff_mime_type(b""), None);
+}
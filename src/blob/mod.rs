@@ -0,0 +1,355 @@
+//! Storage of the blobs (images, videos, ...) uploaded by accounts.
+//!
+//! Blobs are stored separately from repositories, and referenced by their CID
+//! from the records. An uploaded blob is first stored in a temporary area,
+//! and only becomes part of an account once a committed record references
+//! it. Temporary blobs that are never referenced can then be discarded.
+
+mod fs;
+pub use self::fs::*;
+
+mod gc;
+pub use self::gc::*;
+
+mod memory;
+pub use self::memory::*;
+
+mod mime;
+pub use self::mime::*;
+
+use {
+    crate::{
+        api::xrpc::model::{Cid, Did},
+        dag_cbor::Value,
+    },
+    futures::Stream,
+    hyper::body::Bytes,
+    std::{collections::BTreeMap, future::Future, pin::Pin, time::Duration},
+    tokio::io::{AsyncRead, AsyncReadExt},
+};
+
+/// An error that might occur when accessing a [`BlobStore`].
+#[derive(Debug)]
+pub enum BlobStoreError {
+    /// The temporary blob to make permanent does not exist.
+    NotFound(Cid),
+    /// The storage backend failed.
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for BlobStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(cid) => write!(f, "blob `{cid}` not found"),
+            Self::Io(err) => write!(f, "failed to access the blob store: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BlobStoreError {}
+
+impl From<std::io::Error> for BlobStoreError {
+    #[inline]
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// The content of a blob, read in chunks.
+pub type BlobStream = Pin<Box<dyn Send + Stream<Item = std::io::Result<Bytes>>>>;
+
+/// The size of the chunks returned by [`read_chunks`].
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Reads `reader` until its end, in chunks of at most [`CHUNK_SIZE`] bytes.
+///
+/// The stream ends after the first error.
+pub fn read_chunks<R>(reader: R) -> impl Send + Stream<Item = std::io::Result<Bytes>>
+where
+    R: Send + Unpin + AsyncRead,
+{
+    futures::stream::unfold(Some(reader), |reader| async move {
+        let mut reader = reader?;
+        let mut buf = vec![0; CHUNK_SIZE];
+        match reader.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(buf.into()), Some(reader)))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+}
+
+/// A blob written to the temporary area of a [`BlobStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempBlob {
+    /// The CID of the blob, using the `raw` codec.
+    pub cid: Cid,
+    /// The size of the blob, in bytes.
+    pub size: u64,
+}
+
+/// A reference to a blob, as embedded in records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobRef {
+    /// The CID of the blob, using the `raw` codec.
+    pub cid: Cid,
+    /// The MIME type of the blob.
+    pub mime_type: String,
+    /// The size of the blob, in bytes.
+    pub size: u64,
+}
+
+impl BlobRef {
+    /// Parses a `blob` object.
+    ///
+    /// Returns `None` if the value is not a blob reference in the current
+    /// format.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        if value.get("$type")?.as_str()? != "blob" {
+            return None;
+        }
+        let Value::Link(cid) = value.get("ref")? else {
+            return None;
+        };
+        let mime_type = value.get("mimeType")?.as_str()?.to_owned();
+        let Value::Integer(size) = value.get("size")? else {
+            return None;
+        };
+
+        Some(Self {
+            cid: *cid,
+            mime_type,
+            size: u64::try_from(*size).ok()?,
+        })
+    }
+
+    /// Returns the references to the blobs found anywhere in the provided
+    /// record.
+    pub fn find_all(record: &Value) -> Vec<Self> {
+        fn visit(value: &Value, found: &mut Vec<BlobRef>) {
+            match value {
+                Value::Map(map) => match BlobRef::from_value(value) {
+                    Some(blob) => found.push(blob),
+                    None => map.values().for_each(|value| visit(value, found)),
+                },
+                Value::List(list) => list.iter().for_each(|value| visit(value, found)),
+                _ => (),
+            }
+        }
+
+        let mut found = Vec::new();
+        visit(record, &mut found);
+        found
+    }
+
+    /// Returns the `blob` object referencing the blob.
+    pub fn to_value(&self) -> Value {
+        let size = i64::try_from(self.size).unwrap_or(i64::MAX);
+        Value::Map(BTreeMap::from([
+            ("$type".to_owned(), Value::String("blob".to_owned())),
+            ("ref".to_owned(), Value::Link(self.cid)),
+            ("mimeType".to_owned(), Value::String(self.mime_type.clone())),
+            ("size".to_owned(), Value::Integer(size)),
+        ]))
+    }
+}
+
+/// A blob read from a [`BlobStore`].
+pub struct StoredBlob {
+    /// The size of the blob, in bytes.
+    pub size: u64,
+    /// The content of the blob.
+    pub stream: BlobStream,
+}
+
+/// A storage backend for blobs.
+///
+/// Blobs belong to the account that uploaded them: each account has its own
+/// temporary area, whose blobs only that account can make permanent. The
+/// same blob may be owned by several accounts.
+pub trait BlobStore: Sync {
+    /// Writes a new blob to the temporary area of the provided account.
+    ///
+    /// If the account already uploaded a temporary blob with the same
+    /// content, it is replaced.
+    fn put_temp<S>(
+        &self,
+        did: &Did,
+        data: S,
+    ) -> impl Send + Future<Output = Result<TempBlob, BlobStoreError>>
+    where
+        S: Send + Stream<Item = std::io::Result<Bytes>>;
+
+    /// Copies a temporary blob of the provided account to its blobs.
+    ///
+    /// The temporary blob is left in place until it expires. Making a blob
+    /// that the account already owns permanent is not an error, even if the
+    /// temporary blob no longer exists.
+    fn make_permanent(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<(), BlobStoreError>>;
+
+    /// Returns a blob of the provided account, or `None` if the account does
+    /// not own the blob.
+    fn get(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<Option<StoredBlob>, BlobStoreError>>;
+
+    /// Returns whether the provided account owns a blob.
+    fn contains(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<bool, BlobStoreError>>;
+
+    /// Removes a blob of the provided account.
+    ///
+    /// Returns whether the account owned the blob.
+    fn delete(
+        &self,
+        did: &Did,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<bool, BlobStoreError>>;
+
+    /// Returns the CIDs of the blobs of the provided account, in ascending
+    /// order.
+    fn list(&self, did: &Did) -> impl Send + Future<Output = Result<Vec<Cid>, BlobStoreError>>;
+
+    /// Removes the temporary blobs that were written at least `max_age` ago.
+    ///
+    /// Returns the number of blobs removed.
+    fn expire_temp(
+        &self,
+        max_age: Duration,
+    ) -> impl Send + Future<Output = Result<usize, BlobStoreError>>;
+}
+
+#[cfg(test)]
+#[test]
+fn find_blob_refs() {
+    let blob = |data: &[u8], mime_type: &str| BlobRef {
+        cid: Cid::compute(crate::api::xrpc::model::Codec::Raw, data),
+        mime_type: mime_type.to_owned(),
+        size: data.len() as u64,
+    };
+    let (image, video) = (blob(b"image", "image/png"), blob(b"video", "video/mp4"));
+
+    assert_eq!(BlobRef::from_value(&image.to_value()), Some(image.clone()));
+
+    let record = Value::Map(BTreeMap::from([
+        ("text".to_owned(), Value::String("hello".to_owned())),
+        (
+            "images".to_owned(),
+            Value::List(vec![Value::Map(BTreeMap::from([
+                ("alt".to_owned(), Value::String("".to_owned())),
+                ("image".to_owned(), image.to_value()),
+            ]))]),
+        ),
+        ("video".to_owned(), video.to_value()),
+    ]));
+    assert_eq!(BlobRef::find_all(&record), [image, video]);
+}
+
+#[cfg(test)]
+async fn check_blob_store(store: &impl BlobStore) {
+    use futures::StreamExt;
+
+    async fn read(store: &impl BlobStore, did: &Did, cid: &Cid) -> Option<Vec<u8>> {
+        let blob = store.get(did, cid).await.unwrap()?;
+        let mut data = Vec::new();
+        let mut stream = blob.stream;
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(blob.size, data.len() as u64);
+        Some(data)
+    }
+
+    let did = |s: &str| Did::try_from(Box::<str>::from(s)).unwrap();
+    let (alice, bob) = (did("did:plc:alice"), did("did:plc:bob"));
+
+    let chunks = [Bytes::from_static(b"hello, "), Bytes::from_static(b"world")];
+    let blob = store
+        .put_temp(&alice, futures::stream::iter(chunks.map(Ok)))
+        .await
+        .unwrap();
+    assert_eq!(
+        blob.cid,
+        Cid::compute(crate::api::xrpc::model::Codec::Raw, b"hello, world")
+    );
+    assert_eq!(blob.size, 12);
+
+    // Temporary blobs are not visible.
+    assert_eq!(read(store, &alice, &blob.cid).await, None);
+    assert!(!store.contains(&alice, &blob.cid).await.unwrap());
+    assert!(store.list(&alice).await.unwrap().is_empty());
+
+    store.make_permanent(&alice, &blob.cid).await.unwrap();
+    store.make_permanent(&alice, &blob.cid).await.unwrap();
+    assert_eq!(
+        read(store, &alice, &blob.cid).await.as_deref(),
+        Some(&b"hello, world"[..])
+    );
+    assert_eq!(store.list(&alice).await.unwrap(), [blob.cid]);
+    assert!(store.contains(&alice, &blob.cid).await.unwrap());
+    assert!(!store.contains(&bob, &blob.cid).await.unwrap());
+
+    // Other accounts can only use the blobs they uploaded themselves.
+    assert!(matches!(
+        store.make_permanent(&bob, &blob.cid).await,
+        Err(BlobStoreError::NotFound(_))
+    ));
+    let chunks = futures::stream::iter([Ok(Bytes::from_static(b"hello, world"))]);
+    assert_eq!(store.put_temp(&bob, chunks).await.unwrap(), blob);
+    store.make_permanent(&bob, &blob.cid).await.unwrap();
+    assert_eq!(
+        read(store, &bob, &blob.cid).await.as_deref(),
+        Some(&b"hello, world"[..])
+    );
+    assert!(store.delete(&bob, &blob.cid).await.unwrap());
+
+    let failing = futures::stream::iter([
+        Ok(Bytes::from_static(b"partial")),
+        Err(std::io::ErrorKind::ConnectionReset.into()),
+    ]);
+    assert!(store.put_temp(&alice, failing).await.is_err());
+
+    assert!(store.delete(&alice, &blob.cid).await.unwrap());
+    assert!(!store.delete(&alice, &blob.cid).await.unwrap());
+    assert!(!store.contains(&alice, &blob.cid).await.unwrap());
+    assert_eq!(read(store, &alice, &blob.cid).await, None);
+
+    // Temporary blobs expire, permanent ones don't.
+    let temp = store
+        .put_temp(
+            &alice,
+            futures::stream::iter([Ok(Bytes::from_static(b"temp"))]),
+        )
+        .await
+        .unwrap();
+    let kept = store
+        .put_temp(
+            &alice,
+            futures::stream::iter([Ok(Bytes::from_static(b"kept"))]),
+        )
+        .await
+        .unwrap();
+    store.make_permanent(&alice, &kept.cid).await.unwrap();
+    assert_eq!(
+        store.expire_temp(Duration::from_secs(3600)).await.unwrap(),
+        0
+    );
+    assert_eq!(store.expire_temp(Duration::ZERO).await.unwrap(), 4);
+    assert!(matches!(
+        store.make_permanent(&alice, &temp.cid).await,
+        Err(BlobStoreError::NotFound(_))
+    ));
+    assert!(read(store, &alice, &kept.cid).await.is_some());
+}
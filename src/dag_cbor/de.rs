@@ -0,0 +1,246 @@
+use {
+    super::{
+        decode::Decoder,
+        encode::{
+            MAJOR_ARRAY, MAJOR_BYTES, MAJOR_MAP, MAJOR_NEGATIVE, MAJOR_SIMPLE, MAJOR_TAG,
+            MAJOR_TEXT, MAJOR_UNSIGNED, SIMPLE_FALSE, SIMPLE_NULL, SIMPLE_TRUE, TAG_LINK,
+        },
+        Error, LINK_TOKEN,
+    },
+    serde::de::{self, IntoDeserializer, Visitor},
+};
+
+impl<'de> de::Deserializer<'de> for &mut Decoder<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let head = self.read_head()?;
+
+        match head.major {
+            MAJOR_UNSIGNED | MAJOR_NEGATIVE => visitor.visit_i64(Decoder::integer_from_head(head)?),
+            MAJOR_BYTES => visitor.visit_borrowed_bytes(self.read_payload(head.arg)?),
+            MAJOR_TEXT => visitor.visit_borrowed_str(self.read_text_payload(head.arg)?),
+            MAJOR_ARRAY => {
+                self.enter()?;
+                let ret = visitor.visit_seq(ListAccess {
+                    de: self,
+                    remaining: head.arg,
+                });
+                self.leave();
+                ret
+            }
+            MAJOR_MAP => {
+                self.enter()?;
+                let ret = visitor.visit_map(MapAccess {
+                    de: self,
+                    remaining: head.arg,
+                    prev_key: None,
+                });
+                self.leave();
+                ret
+            }
+            MAJOR_TAG if head.arg == TAG_LINK => {
+                let cid = self.read_link_payload()?;
+                visitor.visit_newtype_struct(LinkDeserializer(cid))
+            }
+            MAJOR_TAG => Err(Error::UnsupportedTag(head.arg)),
+            MAJOR_SIMPLE => match head.arg as u8 {
+                SIMPLE_FALSE => visitor.visit_bool(false),
+                SIMPLE_TRUE => visitor.visit_bool(true),
+                SIMPLE_NULL => visitor.visit_unit(),
+                other => Err(Error::UnsupportedSimpleValue(other)),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.peek_byte()? == (MAJOR_SIMPLE << 5) | SIMPLE_NULL {
+            self.read_head()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if name == LINK_TOKEN {
+            let cid = self.read_link()?;
+            visitor.visit_newtype_struct(LinkDeserializer(cid))
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.peek_major()? {
+            MAJOR_TEXT => visitor.visit_enum(self.read_text()?.into_deserializer()),
+            MAJOR_MAP => {
+                let head = self.read_head()?;
+                if head.arg != 1 {
+                    return Err(Error::Custom(
+                        "expected a map with a single entry for an enum".into(),
+                    ));
+                }
+                self.enter()?;
+                let ret = visitor.visit_enum(EnumAccess { de: self });
+                self.leave();
+                ret
+            }
+            _ => Err(Error::Custom(
+                "expected a string or a map for an enum".into(),
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Gives access to the elements of an array.
+struct ListAccess<'a, 'de> {
+    de: &'a mut Decoder<'de>,
+    remaining: u64,
+}
+
+impl<'de> de::SeqAccess<'de> for ListAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        usize::try_from(self.remaining).ok()
+    }
+}
+
+/// Gives access to the entries of a map, ensuring that its keys are sorted
+/// canonically.
+struct MapAccess<'a, 'de> {
+    de: &'a mut Decoder<'de>,
+    remaining: u64,
+    prev_key: Option<&'de str>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        let key = self.de.read_key(self.prev_key.map(str::as_bytes))?;
+        self.prev_key = Some(key);
+
+        seed.deserialize(de::value::BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        usize::try_from(self.remaining).ok()
+    }
+}
+
+/// Gives access to an externally tagged enum variant encoded as a
+/// single-entry map.
+struct EnumAccess<'a, 'de> {
+    de: &'a mut Decoder<'de>,
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for EnumAccess<'a, 'de> {
+    type Error = Error;
+    type Variant = &'a mut Decoder<'de>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let key = self.de.read_key(None)?;
+        let value = seed.deserialize(de::value::BorrowedStrDeserializer::<Error>::new(key))?;
+        Ok((value, self.de))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut Decoder<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+/// A deserializer that yields the binary representation of a CID link.
+///
+/// This is passed to [`Visitor::visit_newtype_struct`] when a CID link is
+/// found, which allows types that care about links to tell them apart from
+/// regular byte strings.
+pub struct LinkDeserializer<'de>(pub &'de [u8]);
+
+impl<'de> de::Deserializer<'de> for LinkDeserializer<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_bytes(self.0)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,221 @@
+use super::{
+    encode::{
+        cmp_keys, MAJOR_BYTES, MAJOR_NEGATIVE, MAJOR_SIMPLE, MAJOR_TAG, MAJOR_TEXT, MAJOR_UNSIGNED,
+        SIMPLE_FALSE, SIMPLE_NULL, SIMPLE_TRUE, TAG_LINK,
+    },
+    Error,
+};
+
+/// The maximum nesting depth of arrays and maps that the decoder accepts.
+pub const MAX_DEPTH: usize = 128;
+
+/// The head of a data item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Head {
+    /// The major type of the item.
+    pub major: u8,
+    /// The argument of the item (a value, a length, or a tag number).
+    pub arg: u64,
+}
+
+/// A strict DAG-CBOR decoder reading from a byte slice.
+///
+/// The decoder rejects anything that is not in the canonical form expected by
+/// the AT Protocol, such that decoding and re-encoding a value always yields
+/// the same bytes.
+pub struct Decoder<'de> {
+    /// The remaining input.
+    input: &'de [u8],
+    /// The current nesting depth.
+    depth: usize,
+}
+
+impl<'de> Decoder<'de> {
+    /// Creates a new [`Decoder`] reading from the provided input.
+    pub fn new(input: &'de [u8]) -> Self {
+        Self { input, depth: 0 }
+    }
+
+    /// Returns whether the whole input has been consumed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    /// Returns an error if the input has not been consumed entirely.
+    pub fn end(&self) -> Result<(), Error> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::TrailingBytes)
+        }
+    }
+
+    /// Returns the next byte of the input without consuming it.
+    pub fn peek_byte(&self) -> Result<u8, Error> {
+        self.input.first().copied().ok_or(Error::UnexpectedEof)
+    }
+
+    /// Returns the major type of the next item without consuming it.
+    #[inline]
+    pub fn peek_major(&self) -> Result<u8, Error> {
+        self.peek_byte().map(|b| b >> 5)
+    }
+
+    /// Consumes `n` bytes of the input.
+    fn take(&mut self, n: usize) -> Result<&'de [u8], Error> {
+        let (taken, rest) = self.input.split_at_checked(n).ok_or(Error::UnexpectedEof)?;
+        self.input = rest;
+        Ok(taken)
+    }
+
+    /// Reads the head of the next data item.
+    ///
+    /// Non-minimal encodings and indefinite lengths are rejected. Floats and
+    /// unsupported simple values are rejected as well.
+    pub fn read_head(&mut self) -> Result<Head, Error> {
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let info = initial & 0x1F;
+
+        let arg = match info {
+            0..=23 => info as u64,
+            25..=27 if major == MAJOR_SIMPLE => return Err(Error::FloatsNotSupported),
+            24 => {
+                let n = self.take(1)?[0] as u64;
+                if n < 24 {
+                    return Err(Error::NonCanonicalInteger);
+                }
+                n
+            }
+            25 => {
+                let n = u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64;
+                if n <= u8::MAX as u64 {
+                    return Err(Error::NonCanonicalInteger);
+                }
+                n
+            }
+            26 => {
+                let n = u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64;
+                if n <= u16::MAX as u64 {
+                    return Err(Error::NonCanonicalInteger);
+                }
+                n
+            }
+            27 => {
+                let n = u64::from_be_bytes(self.take(8)?.try_into().unwrap());
+                if n <= u32::MAX as u64 {
+                    return Err(Error::NonCanonicalInteger);
+                }
+                n
+            }
+            31 => return Err(Error::IndefiniteLength),
+            _ => return Err(Error::ReservedAdditionalInfo(info)),
+        };
+
+        if major == MAJOR_SIMPLE && !matches!(arg as u8, SIMPLE_FALSE | SIMPLE_TRUE | SIMPLE_NULL) {
+            return Err(Error::UnsupportedSimpleValue(arg as u8));
+        }
+
+        Ok(Head { major, arg })
+    }
+
+    /// Reads a head, ensuring its major type is `major`, and returns its
+    /// argument.
+    fn read_head_of(&mut self, major: u8, error: Error) -> Result<u64, Error> {
+        let head = self.read_head()?;
+        if head.major == major {
+            Ok(head.arg)
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Reads the payload of a byte or text string whose head has already been
+    /// read.
+    pub fn read_payload(&mut self, len: u64) -> Result<&'de [u8], Error> {
+        let len = usize::try_from(len).map_err(|_| Error::UnexpectedEof)?;
+        self.take(len)
+    }
+
+    /// Reads the payload of a text string whose head has already been read.
+    pub fn read_text_payload(&mut self, len: u64) -> Result<&'de str, Error> {
+        let bytes = self.read_payload(len)?;
+        std::str::from_utf8(bytes).map_err(|_| Error::InvalidUtf8)
+    }
+
+    /// Reads a text string.
+    pub fn read_text(&mut self) -> Result<&'de str, Error> {
+        let len = self.read_head_of(MAJOR_TEXT, Error::Custom("expected a text string".into()))?;
+        self.read_text_payload(len)
+    }
+
+    /// Reads a map key, ensuring that it comes after `prev` in the canonical
+    /// order.
+    pub fn read_key(&mut self, prev: Option<&[u8]>) -> Result<&'de str, Error> {
+        let len = self.read_head_of(MAJOR_TEXT, Error::NonStringKey)?;
+        let key = self.read_text_payload(len)?;
+
+        if let Some(prev) = prev {
+            match cmp_keys(prev, key.as_bytes()) {
+                std::cmp::Ordering::Less => (),
+                std::cmp::Ordering::Equal => return Err(Error::DuplicateKey),
+                std::cmp::Ordering::Greater => return Err(Error::UnsortedKeys),
+            }
+        }
+
+        Ok(key)
+    }
+
+    /// Reads the payload of a CID link whose tag has already been read.
+    ///
+    /// The returned slice contains the binary CID, without the leading
+    /// multibase prefix.
+    pub fn read_link_payload(&mut self) -> Result<&'de [u8], Error> {
+        let len = self.read_head_of(MAJOR_BYTES, Error::InvalidLink)?;
+        match self.read_payload(len)?.split_first() {
+            Some((0x00, cid)) if !cid.is_empty() => Ok(cid),
+            _ => Err(Error::InvalidLink),
+        }
+    }
+
+    /// Reads a CID link, including its tag.
+    pub fn read_link(&mut self) -> Result<&'de [u8], Error> {
+        match self.read_head()? {
+            Head {
+                major: MAJOR_TAG,
+                arg: TAG_LINK,
+            } => self.read_link_payload(),
+            Head {
+                major: MAJOR_TAG,
+                arg,
+            } => Err(Error::UnsupportedTag(arg)),
+            _ => Err(Error::InvalidLink),
+        }
+    }
+
+    /// Converts the argument of an integer head into a signed integer.
+    pub fn integer_from_head(head: Head) -> Result<i64, Error> {
+        let n = i64::try_from(head.arg).map_err(|_| Error::IntegerOutOfRange)?;
+        match head.major {
+            MAJOR_UNSIGNED => Ok(n),
+            MAJOR_NEGATIVE => Ok(-1 - n),
+            _ => Err(Error::Custom("expected an integer".into())),
+        }
+    }
+
+    /// Increments the nesting depth, failing if the limit is exceeded.
+    pub fn enter(&mut self) -> Result<(), Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::RecursionLimitExceeded);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Decrements the nesting depth.
+    #[inline]
+    pub fn leave(&mut self) {
+        self.depth -= 1;
+    }
+}
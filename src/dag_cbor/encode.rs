@@ -0,0 +1,125 @@
+use {
+    super::Value,
+    crate::api::xrpc::model::Cid,
+    std::{cmp::Ordering, collections::BTreeMap},
+};
+
+/// The major type of unsigned integers.
+pub const MAJOR_UNSIGNED: u8 = 0;
+/// The major type of negative integers.
+pub const MAJOR_NEGATIVE: u8 = 1;
+/// The major type of byte strings.
+pub const MAJOR_BYTES: u8 = 2;
+/// The major type of text strings.
+pub const MAJOR_TEXT: u8 = 3;
+/// The major type of arrays.
+pub const MAJOR_ARRAY: u8 = 4;
+/// The major type of maps.
+pub const MAJOR_MAP: u8 = 5;
+/// The major type of tags.
+pub const MAJOR_TAG: u8 = 6;
+/// The major type of simple values and floats.
+pub const MAJOR_SIMPLE: u8 = 7;
+
+/// The tag used to mark CID links.
+pub const TAG_LINK: u64 = 42;
+
+/// The simple value `false`.
+pub const SIMPLE_FALSE: u8 = 20;
+/// The simple value `true`.
+pub const SIMPLE_TRUE: u8 = 21;
+/// The simple value `null`.
+pub const SIMPLE_NULL: u8 = 22;
+
+/// Writes the head of a data item using the shortest possible encoding for
+/// the argument `n`.
+pub fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+/// Writes a signed integer.
+pub fn write_integer(out: &mut Vec<u8>, n: i64) {
+    if n >= 0 {
+        write_head(out, MAJOR_UNSIGNED, n as u64);
+    } else {
+        // `-1 - n` can't overflow when `n` is negative.
+        write_head(out, MAJOR_NEGATIVE, (-1 - n) as u64);
+    }
+}
+
+/// Writes a byte string.
+pub fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_head(out, MAJOR_BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Writes a text string.
+pub fn write_text(out: &mut Vec<u8>, text: &str) {
+    write_head(out, MAJOR_TEXT, text.len() as u64);
+    out.extend_from_slice(text.as_bytes());
+}
+
+/// Writes a CID link.
+pub fn write_link(out: &mut Vec<u8>, cid: &Cid) {
+    write_head(out, MAJOR_TAG, TAG_LINK);
+    // The byte string starts with the multibase prefix for raw binary data.
+    write_head(out, MAJOR_BYTES, Cid::BYTE_LEN as u64 + 1);
+    out.push(0x00);
+    out.extend_from_slice(&cid.to_bytes());
+}
+
+/// Compares two map keys according to the DAG-CBOR canonical ordering.
+///
+/// Shorter keys come first, and keys with the same length are sorted
+/// bytewise.
+pub fn cmp_keys(a: &[u8], b: &[u8]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Writes the provided map, sorting its keys canonically.
+fn write_map(out: &mut Vec<u8>, map: &BTreeMap<String, Value>) {
+    let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| cmp_keys(a.0.as_bytes(), b.0.as_bytes()));
+
+    write_head(out, MAJOR_MAP, entries.len() as u64);
+    for (key, value) in entries {
+        write_text(out, key);
+        write_value(out, value);
+    }
+}
+
+/// Writes the provided value in its canonical form.
+pub fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => write_head(out, MAJOR_SIMPLE, SIMPLE_NULL as u64),
+        Value::Bool(false) => write_head(out, MAJOR_SIMPLE, SIMPLE_FALSE as u64),
+        Value::Bool(true) => write_head(out, MAJOR_SIMPLE, SIMPLE_TRUE as u64),
+        Value::Integer(n) => write_integer(out, *n),
+        Value::Bytes(bytes) => write_bytes(out, bytes),
+        Value::String(text) => write_text(out, text),
+        Value::List(list) => {
+            write_head(out, MAJOR_ARRAY, list.len() as u64);
+            for item in list {
+                write_value(out, item);
+            }
+        }
+        Value::Map(map) => write_map(out, map),
+        Value::Link(cid) => write_link(out, cid),
+    }
+}
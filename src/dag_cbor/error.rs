@@ -0,0 +1,82 @@
+use std::fmt::Display;
+
+/// An error that might occur when encoding or decoding DAG-CBOR data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The input ended before a complete item could be read.
+    UnexpectedEof,
+    /// The input contains bytes after the end of the top-level item.
+    TrailingBytes,
+    /// An integer or a length was not encoded using the shortest possible
+    /// form.
+    NonCanonicalInteger,
+    /// An indefinite-length item was found.
+    IndefiniteLength,
+    /// The input contains a reserved or unsupported additional information
+    /// value.
+    ReservedAdditionalInfo(u8),
+    /// A floating-point number was found or was attempted to be encoded.
+    ///
+    /// The AT Protocol data model does not allow floats.
+    FloatsNotSupported,
+    /// A simple value other than `true`, `false` or `null` was found.
+    UnsupportedSimpleValue(u8),
+    /// A tag other than `42` (CID link) was found.
+    UnsupportedTag(u64),
+    /// An integer does not fit in a signed 64-bit integer.
+    IntegerOutOfRange,
+    /// A text string is not valid UTF-8.
+    InvalidUtf8,
+    /// A map key is not a text string.
+    NonStringKey,
+    /// The keys of a map are not sorted in the canonical order.
+    UnsortedKeys,
+    /// A map contains the same key twice.
+    DuplicateKey,
+    /// A CID link is malformed.
+    InvalidLink,
+    /// The input is nested too deeply.
+    RecursionLimitExceeded,
+    /// A custom error reported by a `Serialize` or `Deserialize`
+    /// implementation.
+    Custom(Box<str>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEof => f.write_str("unexpected end of input"),
+            Self::TrailingBytes => f.write_str("trailing bytes after the end of the input"),
+            Self::NonCanonicalInteger => f.write_str("integer is not minimally encoded"),
+            Self::IndefiniteLength => f.write_str("indefinite-length items are not allowed"),
+            Self::ReservedAdditionalInfo(info) => {
+                write!(f, "reserved additional information value `{info}`")
+            }
+            Self::FloatsNotSupported => f.write_str("floating-point numbers are not allowed"),
+            Self::UnsupportedSimpleValue(val) => write!(f, "unsupported simple value `{val}`"),
+            Self::UnsupportedTag(tag) => write!(f, "unsupported tag `{tag}`"),
+            Self::IntegerOutOfRange => f.write_str("integer out of range"),
+            Self::InvalidUtf8 => f.write_str("text string is not valid UTF-8"),
+            Self::NonStringKey => f.write_str("map keys must be text strings"),
+            Self::UnsortedKeys => f.write_str("map keys are not sorted canonically"),
+            Self::DuplicateKey => f.write_str("duplicate map key"),
+            Self::InvalidLink => f.write_str("invalid CID link"),
+            Self::RecursionLimitExceeded => f.write_str("recursion limit exceeded"),
+            Self::Custom(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string().into_boxed_str())
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string().into_boxed_str())
+    }
+}
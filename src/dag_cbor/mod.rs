@@ -0,0 +1,196 @@
+//! An implementation of DAG-CBOR, the binary encoding used by the AT Protocol
+//! to store and transmit records, repository nodes and commits.
+//!
+//! Only the subset of CBOR allowed by the
+//! [AT Protocol data model](https://atproto.com/specs/data-model) is
+//! supported: no floats, no indefinite lengths, string map keys sorted in
+//! canonical order, and the tag `42` for CID links.
+//!
+//! Types implementing [`serde::Serialize`] and [`serde::Deserialize`] can be
+//! converted using [`to_vec`] and [`from_slice`]. Dynamically-typed data can
+//! be manipulated through [`Value`].
+
+mod decode;
+mod encode;
+
+mod de;
+mod ser;
+
+mod error;
+pub use self::error::*;
+
+mod value;
+pub use self::value::*;
+
+pub use self::{de::LinkDeserializer, decode::Decoder, ser::ValueSerializer};
+
+use serde::{Deserialize, Serialize};
+
+/// The name of the newtype struct used to tell CID links apart from regular
+/// byte strings when going through `serde`.
+///
+/// A type that wants to be encoded as a CID link should serialize itself as a
+/// newtype struct with this name wrapping the binary CID (without the
+/// multibase prefix). Conversely, when a link is decoded, the visitor's
+/// `visit_newtype_struct` method is called with a deserializer that yields
+/// the binary CID.
+pub const LINK_TOKEN: &str = "$__rpds_dag_cbor_link";
+
+/// Converts the provided value into a [`Value`].
+pub fn to_value<T: ?Sized + Serialize>(value: &T) -> Result<Value, Error> {
+    value.serialize(ValueSerializer)
+}
+
+/// Encodes the provided value as canonical DAG-CBOR.
+pub fn to_vec<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    to_value(value).map(|value| value.encode())
+}
+
+/// Decodes a value from the provided DAG-CBOR bytes.
+///
+/// The input must contain exactly one data item in its canonical form.
+pub fn from_slice<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, Error> {
+    let mut decoder = Decoder::new(bytes);
+    let value = T::deserialize(&mut decoder)?;
+    decoder.end()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+#[test]
+fn canonical_key_order() {
+    #[derive(Serialize)]
+    struct Record {
+        text: &'static str,
+        a: i64,
+        bb: bool,
+    }
+
+    let bytes = to_vec(&Record {
+        text: "hi",
+        a: -2,
+        bb: true,
+    })
+    .unwrap();
+
+    assert_eq!(bytes, b"\xa3\x61a\x21\x62bb\xf5\x64text\x62hi".as_slice());
+}
+
+#[cfg(test)]
+#[test]
+fn struct_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record<'a> {
+        text: &'a str,
+        count: u32,
+        tags: Vec<String>,
+        parent: Option<i64>,
+    }
+
+    let record = Record {
+        text: "hello",
+        count: 1234,
+        tags: vec!["a".into(), "b".into()],
+        parent: None,
+    };
+
+    let bytes = to_vec(&record).unwrap();
+    assert_eq!(from_slice::<Record>(&bytes).unwrap(), record);
+}
+
+#[cfg(test)]
+#[test]
+fn model_types_round_trip() {
+    use crate::api::xrpc::model::{AtUri, Did, Handle};
+
+    let did = Did::new("did:plc:ewvi7nxzyoun6zhxrhs64oiz").unwrap();
+    let bytes = to_vec(&did).unwrap();
+    assert_eq!(from_slice::<Did<&str>>(&bytes).unwrap(), did);
+
+    let handle = Handle::new("alice.bsky.social").unwrap();
+    let bytes = to_vec(&handle).unwrap();
+    assert_eq!(from_slice::<Handle<&str>>(&bytes).unwrap(), handle);
+
+    let uri = AtUri::new("at://did:example:123/app.bsky.feed.post/abc").unwrap();
+    let bytes = to_vec(&uri).unwrap();
+    assert_eq!(
+        from_slice::<AtUri<&str>>(&bytes).unwrap().as_str(),
+        uri.as_str()
+    );
+
+    assert!(from_slice::<Did<&str>>(&to_vec("not a did").unwrap()).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn value_round_trip() {
+    use crate::api::xrpc::model::{Cid, Codec};
+
+    let cid = Cid::compute(Codec::DagCbor, b"");
+    let mut bytes = b"\xa4\x61a\x80\x61b\x43\x01\x02\x03\x61c\xd8\x2a\x58\x25\x00".to_vec();
+    bytes.extend_from_slice(&cid.to_bytes());
+    bytes.extend_from_slice(b"\x62dd\xf6");
+
+    let value: Value = from_slice(&bytes).unwrap();
+    assert_eq!(value.get("b"), Some(&Value::Bytes(vec![1, 2, 3])));
+    assert_eq!(value.get("c"), Some(&Value::Link(cid)));
+    assert_eq!(value.encode(), bytes);
+}
+
+#[cfg(test)]
+#[test]
+fn rejects_non_canonical_input() {
+    // Unsorted keys.
+    assert_eq!(
+        from_slice::<Value>(b"\xa2\x62bb\x01\x61a\x02"),
+        Err(Error::UnsortedKeys)
+    );
+    // Duplicate keys.
+    assert_eq!(
+        from_slice::<Value>(b"\xa2\x61a\x01\x61a\x02"),
+        Err(Error::DuplicateKey)
+    );
+    // Integer that fits in the initial byte encoded on two bytes.
+    assert_eq!(
+        from_slice::<Value>(b"\x18\x05"),
+        Err(Error::NonCanonicalInteger)
+    );
+    // Indefinite-length array.
+    assert_eq!(
+        from_slice::<Value>(b"\x9f\xff"),
+        Err(Error::IndefiniteLength)
+    );
+    // Double-precision float.
+    assert_eq!(
+        from_slice::<Value>(b"\xfb\x3f\xf0\x00\x00\x00\x00\x00\x00"),
+        Err(Error::FloatsNotSupported)
+    );
+    // Unknown tag.
+    assert_eq!(
+        from_slice::<Value>(b"\xc1\x00"),
+        Err(Error::UnsupportedTag(1))
+    );
+    // Non-string key.
+    assert_eq!(
+        from_slice::<Value>(b"\xa1\x01\x02"),
+        Err(Error::NonStringKey)
+    );
+    // Trailing bytes.
+    assert_eq!(from_slice::<Value>(b"\x01\x02"), Err(Error::TrailingBytes));
+}
+
+#[cfg(test)]
+#[test]
+fn rejects_floats_on_encode() {
+    assert_eq!(to_vec(&1.5f64), Err(Error::FloatsNotSupported));
+}
+
+#[cfg(test)]
+#[test]
+fn json_special_objects() {
+    let json = r#"{"data":{"$bytes":"AQID"},"link":{"$link":"bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm"}}"#;
+    let value: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(value.get("data"), Some(&Value::Bytes(vec![1, 2, 3])));
+    assert!(matches!(value.get("link"), Some(Value::Link(_))));
+    assert_eq!(serde_json::to_string(&value).unwrap(), json);
+}
@@ -0,0 +1,724 @@
+use {
+    super::{Error, Value, LINK_TOKEN},
+    crate::api::xrpc::model::Cid,
+    serde::{ser, Serialize},
+    std::collections::BTreeMap,
+};
+
+/// A [`serde::Serializer`] that turns any serializable value into a [`Value`].
+///
+/// Maps are collected into a [`BTreeMap`] and sorted canonically only when
+/// the value is finally encoded, which means that the order in which struct
+/// fields are declared does not matter.
+pub struct ValueSerializer;
+
+/// Converts an unsigned integer into an [`Value::Integer`], failing if it does
+/// not fit in a signed 64-bit integer.
+fn unsigned(n: u64) -> Result<Value, Error> {
+    i64::try_from(n)
+        .map(Value::Integer)
+        .map_err(|_| Error::IntegerOutOfRange)
+}
+
+/// Creates a single-entry map, used to represent enum variants.
+fn variant_map(variant: &'static str, value: Value) -> Value {
+    let mut map = BTreeMap::new();
+    map.insert(variant.to_owned(), value);
+    Value::Map(map)
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::Integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Error> {
+        i64::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| Error::IntegerOutOfRange)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::Integer(v as i64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        unsigned(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Error> {
+        u64::try_from(v)
+            .map_err(|_| Error::IntegerOutOfRange)
+            .and_then(unsigned)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Value, Error> {
+        Err(Error::FloatsNotSupported)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Value, Error> {
+        Err(Error::FloatsNotSupported)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        if name == LINK_TOKEN {
+            value.serialize(LinkSerializer)
+        } else {
+            value.serialize(self)
+        }
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        Ok(variant_map(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, Error> {
+        Ok(SerializeList {
+            variant: None,
+            list: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, Error> {
+        Ok(SerializeList {
+            variant: Some(variant),
+            list: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, Error> {
+        Ok(SerializeMap {
+            variant: None,
+            map: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<SerializeMap, Error> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeMap, Error> {
+        Ok(SerializeMap {
+            variant: Some(variant),
+            map: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+}
+
+/// Collects the elements of a list.
+pub struct SerializeList {
+    /// When serializing a tuple variant, the name of that variant.
+    variant: Option<&'static str>,
+    /// The elements serialized so far.
+    list: Vec<Value>,
+}
+
+impl SerializeList {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.list.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, Error> {
+        let list = Value::List(self.list);
+        match self.variant {
+            Some(variant) => Ok(variant_map(variant, list)),
+            None => Ok(list),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+/// Collects the entries of a map.
+pub struct SerializeMap {
+    /// When serializing a struct variant, the name of that variant.
+    variant: Option<&'static str>,
+    /// The entries serialized so far.
+    map: BTreeMap<String, Value>,
+    /// The key passed to `serialize_key`, waiting for its value.
+    next_key: Option<String>,
+}
+
+impl SerializeMap {
+    fn insert(&mut self, key: String, value: Value) -> Result<(), Error> {
+        match self.map.insert(key, value) {
+            Some(_) => Err(Error::DuplicateKey),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(self) -> Result<Value, Error> {
+        let map = Value::Map(self.map);
+        match self.variant {
+            Some(variant) => Ok(variant_map(variant, map)),
+            None => Ok(map),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .next_key
+            .take()
+            .expect("`serialize_value` called before `serialize_key`");
+        let value = value.serialize(ValueSerializer)?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let value = value.serialize(ValueSerializer)?;
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let value = value.serialize(ValueSerializer)?;
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+/// Builds an error for a type that can't be used in a specific position.
+fn unsupported(what: &str) -> Error {
+    Error::Custom(format!("{what} is not supported here").into_boxed_str())
+}
+
+/// A serializer that only accepts strings, used for map keys.
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, Error> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(Error::NonStringKey)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Error::NonStringKey)
+    }
+}
+
+/// A serializer that only accepts byte strings, used for the content of CID
+/// links.
+struct LinkSerializer;
+
+impl ser::Serializer for LinkSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<Value, Error>;
+    type SerializeTuple = ser::Impossible<Value, Error>;
+    type SerializeTupleStruct = ser::Impossible<Value, Error>;
+    type SerializeTupleVariant = ser::Impossible<Value, Error>;
+    type SerializeMap = ser::Impossible<Value, Error>;
+    type SerializeStruct = ser::Impossible<Value, Error>;
+    type SerializeStructVariant = ser::Impossible<Value, Error>;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Cid::from_bytes(v)
+            .map(Value::Link)
+            .map_err(|_| Error::InvalidLink)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<Value, Error> {
+        Err(unsupported("a boolean"))
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Value, Error> {
+        Err(unsupported("an integer"))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Value, Error> {
+        Err(Error::FloatsNotSupported)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Value, Error> {
+        Err(Error::FloatsNotSupported)
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Value, Error> {
+        Err(unsupported("a character"))
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Value, Error> {
+        Err(unsupported("a string"))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Err(unsupported("an option"))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Value, Error> {
+        Err(unsupported("an option"))
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Err(unsupported("a unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Err(unsupported("a unit struct"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Value, Error> {
+        Err(unsupported("an enum"))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value, Error> {
+        Err(unsupported("an enum"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(unsupported("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(unsupported("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("an enum"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("a map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(unsupported("a struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported("an enum"))
+    }
+}
@@ -0,0 +1,227 @@
+use {
+    super::encode::write_value,
+    crate::api::xrpc::model::Cid,
+    base64ct::Encoding,
+    serde::{
+        de::{self, DeserializeSeed, Visitor},
+        ser::SerializeMap,
+        Deserialize, Deserializer, Serialize, Serializer,
+    },
+    std::collections::BTreeMap,
+};
+
+/// The type used to encode and decode `$bytes` objects in JSON.
+type B64 = base64ct::Base64Unpadded;
+
+/// A value of the AT Protocol data model.
+///
+/// This is the dynamically-typed representation of anything that can be
+/// stored in a record. It can be converted to and from DAG-CBOR, and to and
+/// from its JSON representation (where byte strings become `{"$bytes": ...}`
+/// objects and CID links become `{"$link": ...}` objects).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// The `null` value.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A signed integer.
+    Integer(i64),
+    /// A UTF-8 string.
+    String(String),
+    /// A byte string.
+    Bytes(Vec<u8>),
+    /// A list of values.
+    List(Vec<Value>),
+    /// A map with string keys.
+    ///
+    /// Keys are sorted canonically when the value is encoded, regardless of
+    /// the order used by this map.
+    Map(BTreeMap<String, Value>),
+    /// A CID link.
+    Link(Cid),
+}
+
+impl Value {
+    /// Encodes this value as canonical DAG-CBOR.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_value(&mut out, self);
+        out
+    }
+
+    /// Returns the string stored in this value, if it is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the map stored in this value, if it is one.
+    pub fn as_map(&self) -> Option<&BTreeMap<String, Value>> {
+        match self {
+            Self::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// If this value is a map, returns the value associated with `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_map().and_then(|map| map.get(key))
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Integer(n) => serializer.serialize_i64(*n),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Bytes(bytes) if serializer.is_human_readable() => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("$bytes", &B64::encode_string(bytes))?;
+                map.end()
+            }
+            Self::Bytes(bytes) => serializer.serialize_bytes(bytes),
+            Self::List(list) => serializer.collect_seq(list),
+            Self::Map(map) => serializer.collect_map(map),
+            Self::Link(cid) => cid.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let seed = ValueSeed {
+            human_readable: deserializer.is_human_readable(),
+        };
+        seed.deserialize(deserializer)
+    }
+}
+
+/// Deserializes a [`Value`], remembering whether the data format is human
+/// readable.
+///
+/// In human readable formats, `{"$bytes": ...}` objects are turned into byte
+/// strings, and `{"$link": ...}` objects are turned into CID links.
+#[derive(Clone, Copy)]
+struct ValueSeed {
+    human_readable: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a value of the AT Protocol data model")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        i64::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| E::custom("integer out of range"))
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<Value, E> {
+        Err(E::custom("floating-point numbers are not allowed"))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        struct LinkVisitor;
+
+        impl Visitor<'_> for LinkVisitor {
+            type Value = Value;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a CID link")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+                Cid::from_bytes(v).map(Value::Link).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(LinkVisitor)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1024));
+        while let Some(item) = seq.next_element_seed(self)? {
+            list.push(item);
+        }
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = BTreeMap::new();
+        while let Some(key) = access.next_key::<String>()? {
+            let value = access.next_value_seed(self)?;
+            if map.insert(key, value).is_some() {
+                return Err(de::Error::custom("duplicate map key"));
+            }
+        }
+
+        if self.human_readable && map.len() == 1 {
+            if let Some(Value::String(encoded)) = map.get("$bytes") {
+                return B64::decode_vec(encoded)
+                    .map(Value::Bytes)
+                    .map_err(|_| de::Error::custom("invalid base64 in `$bytes` object"));
+            }
+
+            if let Some(Value::String(link)) = map.get("$link") {
+                return link.parse().map(Value::Link).map_err(de::Error::custom);
+            }
+        }
+
+        Ok(Value::Map(map))
+    }
+}
@@ -0,0 +1,172 @@
+use {
+    crate::{api::xrpc::model::Did, expect_env},
+    base64ct::Encoding,
+    hmac::{Hmac, Mac},
+    serde::{Deserialize, Serialize},
+    sha2::Sha256,
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The type used to encode and decode the parts of a token.
+type B64 = base64ct::Base64UrlUnpadded;
+
+/// The scope of the access tokens obtained with the password of an account.
+pub const ACCESS_SCOPE: &str = "com.atproto.access";
+/// The scope of the access tokens obtained with an app password.
+pub const APP_PASS_SCOPE: &str = "com.atproto.appPass";
+
+/// The header of the tokens signed by [`TokenKey`].
+const HEADER: &str = r#"{"typ":"at+jwt","alg":"HS256"}"#;
+
+/// The minimum length of the secret used to sign tokens, in bytes.
+pub const MIN_SECRET_LEN: usize = 32;
+
+/// An error that might occur when verifying a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    /// The token is not a valid JWT, or was not signed by the server.
+    Invalid,
+    /// The token has expired.
+    Expired,
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid => f.write_str("invalid token"),
+            Self::Expired => f.write_str("expired token"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// The claims of a session token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// What the token grants access to, such as [`ACCESS_SCOPE`].
+    pub scope: String,
+    /// The DID of the account the token was issued to.
+    pub sub: Did,
+    /// When the token was issued, in seconds since the Unix epoch.
+    pub iat: u64,
+    /// When the token expires, in seconds since the Unix epoch.
+    pub exp: u64,
+}
+
+/// The key used to sign and verify the session tokens of the accounts.
+///
+/// Tokens are JWTs signed with HMAC-SHA256 (`HS256`), using the secret
+/// stored in `RPDS_JWT_SECRET`.
+pub struct TokenKey {
+    /// The MAC, initialized with the secret.
+    mac: Hmac<Sha256>,
+}
+
+impl TokenKey {
+    /// Creates a new [`TokenKey`] using the secret stored in the
+    /// `RPDS_JWT_SECRET` environment variable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the secret is missing, or shorter than
+    /// [`MIN_SECRET_LEN`] bytes.
+    pub fn new() -> Self {
+        let secret = expect_env("RPDS_JWT_SECRET");
+        if secret.len() < MIN_SECRET_LEN {
+            panic!("`RPDS_JWT_SECRET` must be at least {MIN_SECRET_LEN} bytes long");
+        }
+        Self::from_secret(secret.as_bytes())
+    }
+
+    /// Creates a new [`TokenKey`] using the provided secret.
+    pub fn from_secret(secret: &[u8]) -> Self {
+        Self {
+            mac: Hmac::new_from_slice(secret).expect("HMAC accepts keys of any size"),
+        }
+    }
+
+    /// Signs a token holding the provided claims.
+    pub fn sign(&self, claims: &Claims) -> String {
+        let payload = serde_json::to_vec(claims).unwrap();
+        let mut token = B64::encode_string(HEADER.as_bytes());
+        token.push('.');
+        token.push_str(&B64::encode_string(&payload));
+
+        let mut mac = self.mac.clone();
+        mac.update(token.as_bytes());
+        token.push('.');
+        token.push_str(&B64::encode_string(&mac.finalize().into_bytes()));
+        token
+    }
+
+    /// Verifies the signature and the expiration date of the provided token,
+    /// and returns its claims.
+    ///
+    /// The scope of the token is not checked.
+    pub fn verify(&self, token: &str) -> Result<Claims, TokenError> {
+        let (signed, signature) = token.rsplit_once('.').ok_or(TokenError::Invalid)?;
+        let (header, payload) = signed.split_once('.').ok_or(TokenError::Invalid)?;
+
+        let signature = B64::decode_vec(signature).map_err(|_| TokenError::Invalid)?;
+        let mut mac = self.mac.clone();
+        mac.update(signed.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| TokenError::Invalid)?;
+
+        #[derive(Deserialize)]
+        struct Header<'a> {
+            alg: &'a str,
+        }
+
+        let header = B64::decode_vec(header).map_err(|_| TokenError::Invalid)?;
+        let header: Header = serde_json::from_slice(&header).map_err(|_| TokenError::Invalid)?;
+        if header.alg != "HS256" {
+            return Err(TokenError::Invalid);
+        }
+
+        let payload = B64::decode_vec(payload).map_err(|_| TokenError::Invalid)?;
+        let claims: Claims = serde_json::from_slice(&payload).map_err(|_| TokenError::Invalid)?;
+        if claims.exp <= now() {
+            return Err(TokenError::Expired);
+        }
+
+        Ok(claims)
+    }
+}
+
+/// Returns the current time, in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+#[cfg(test)]
+#[test]
+fn tokens() {
+    let key = TokenKey::from_secret(b"secret");
+    let claims = Claims {
+        scope: ACCESS_SCOPE.to_owned(),
+        sub: Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap(),
+        iat: now(),
+        exp: now() + 60,
+    };
+
+    let token = key.sign(&claims);
+    assert_eq!(key.verify(&token), Ok(claims.clone()));
+
+    // Tokens signed with another secret, or tampered with, are rejected.
+    let other = TokenKey::from_secret(b"other").sign(&claims);
+    assert_eq!(key.verify(&other), Err(TokenError::Invalid));
+    let (signed, signature) = token.rsplit_once('.').unwrap();
+    let forged = format!("{}x.{signature}", signed);
+    assert_eq!(key.verify(&forged), Err(TokenError::Invalid));
+    assert_eq!(key.verify("not a token"), Err(TokenError::Invalid));
+
+    let expired = key.sign(&Claims {
+        exp: now() - 1,
+        ..claims
+    });
+    assert_eq!(key.verify(&expired), Err(TokenError::Expired));
+}
@@ -1,13 +1,33 @@
+mod actor_store;
+pub use self::actor_store::*;
+
 use {
-    crate::{expect_env, try_get_and_parse_env, try_get_env},
+    crate::{
+        api::xrpc::model::{AtUri, Cid, Did, Handle, Nsid, RecordKey, Tid},
+        expect_env, try_get_and_parse_env, try_get_env,
+    },
     argon2::{Argon2, PasswordHash, PasswordVerifier},
     base64ct::Encoding,
     libsql::params::IntoParams,
     rand::{rngs::StdRng, RngCore, SeedableRng},
     serde::{Deserialize, Serialize},
-    std::{cell::RefCell, str::FromStr},
+    std::{cell::RefCell, future::Future, ops::Deref, str::FromStr},
+    tracing::info,
 };
 
+/// The number of times [`Connection::transaction`] tries to begin a
+/// transaction before giving up.
+const BEGIN_ATTEMPTS: u32 = 4;
+
+/// The statements executed on every new connection.
+///
+/// Connections wait up to 5 seconds for the locks held by other connections
+/// before failing with `SQLITE_BUSY`.
+const CONNECTION_PRAGMAS: &str = "\
+    PRAGMA journal_mode = WAL; \
+    PRAGMA busy_timeout = 5000; \
+    PRAGMA foreign_keys = ON;";
+
 /// Wraps an SQLite database object responsible for storing the application's
 /// data.
 pub struct Database {
@@ -16,27 +36,673 @@ pub struct Database {
 }
 
 impl Database {
-    /// Creates a new database object.
+    /// Creates a new database object, using the environment variables to
+    /// configure it.
+    ///
+    /// Pending migrations are applied before the function returns.
     ///
     /// # Panics
     ///
-    /// This function panics if the database object cannot be created.
+    /// This function panics if the database object cannot be created, or if
+    /// the database cannot be migrated to the schema expected by the server.
     pub async fn new() -> Self {
-        let db = create_db_object().await;
+        let path = expect_env("RPDS_DATABASE_FILE");
+        Self::open(&path, SERVICE_MIGRATIONS)
+            .await
+            .unwrap_or_else(|err| panic!("Failed to open the database at `{path}`: {err}"))
+    }
+
+    /// Opens the database stored in the provided file, creating it if
+    /// needed.
+    ///
+    /// The pending `migrations` are applied before the function returns.
+    pub async fn open(path: &str, migrations: &[Migration]) -> Result<Self, MigrationError> {
+        let db = Self {
+            db: libsql::Builder::new_local(path).build().await?,
+        };
+
+        let conn = db.connect().await?;
+        migrate(&conn, migrations).await?;
+
+        Ok(db)
+    }
+
+    /// Opens a new connection to the database.
+    ///
+    /// The connection uses WAL journaling, waits for the locks held by other
+    /// connections, and enforces foreign keys.
+    pub async fn connect(&self) -> libsql::Result<Connection> {
+        let conn = self.db.connect()?;
+        conn.execute_batch(CONNECTION_PRAGMAS).await?;
+        Ok(Connection(conn))
+    }
+}
+
+/// A connection to the [`Database`].
+///
+/// The methods of [`libsql::Connection`] are available through [`Deref`],
+/// and the typed query helpers of [`Queries`] are available on both
+/// connections and transactions.
+#[derive(Clone)]
+pub struct Connection(libsql::Connection);
+
+impl Connection {
+    /// Begins a new transaction.
+    ///
+    /// Unlike [`libsql::Connection::transaction`], the transaction takes the
+    /// write lock of the database right away (`BEGIN IMMEDIATE`). In WAL mode,
+    /// this is the only point where a transaction may fail with
+    /// `SQLITE_BUSY`, which is retried a few times, after a random delay,
+    /// before being reported.
+    ///
+    /// The transaction is rolled back when dropped without being committed.
+    pub async fn transaction(&self) -> libsql::Result<libsql::Transaction> {
+        let mut attempt = 1;
+        loop {
+            match self
+                .0
+                .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+                .await
+            {
+                Err(err) if is_busy(&err) && attempt < BEGIN_ATTEMPTS => {
+                    let delay = rand::thread_rng().next_u32() % (50 << attempt);
+                    tokio::time::sleep(std::time::Duration::from_millis(delay.into())).await;
+                    attempt += 1;
+                }
+                ret => return ret,
+            }
+        }
+    }
+}
+
+impl Deref for Connection {
+    type Target = libsql::Connection;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Returns whether the provided error is caused by a lock held by another
+/// connection (`SQLITE_BUSY`).
+pub fn is_busy(err: &libsql::Error) -> bool {
+    const SQLITE_BUSY: std::ffi::c_int = 5;
+
+    // The code might be an extended result code, whose lower byte is the
+    // primary result code.
+    matches!(err, libsql::Error::SqliteFailure(code, _) if code & 0xff == SQLITE_BUSY)
+}
+
+/// A type that can be read from a column of a row.
+///
+/// Values that cannot be converted to the requested type are reported as
+/// [`libsql::Error::InvalidColumnType`].
+pub trait FromColumn: Sized {
+    /// Converts the value of a column.
+    fn from_column(value: libsql::Value) -> libsql::Result<Self>;
+}
+
+impl FromColumn for libsql::Value {
+    #[inline]
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        Ok(value)
+    }
+}
+
+impl<T: FromColumn> FromColumn for Option<T> {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        match value {
+            libsql::Value::Null => Ok(None),
+            value => T::from_column(value).map(Some),
+        }
+    }
+}
+
+impl FromColumn for i64 {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        match value {
+            libsql::Value::Integer(val) => Ok(val),
+            _ => Err(libsql::Error::InvalidColumnType),
+        }
+    }
+}
+
+impl FromColumn for u32 {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        i64::from_column(value)?
+            .try_into()
+            .map_err(|_| libsql::Error::InvalidColumnType)
+    }
+}
+
+impl FromColumn for u64 {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        i64::from_column(value)?
+            .try_into()
+            .map_err(|_| libsql::Error::InvalidColumnType)
+    }
+}
+
+impl FromColumn for bool {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        i64::from_column(value).map(|val| val != 0)
+    }
+}
+
+impl FromColumn for String {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        match value {
+            libsql::Value::Text(val) => Ok(val),
+            _ => Err(libsql::Error::InvalidColumnType),
+        }
+    }
+}
+
+impl FromColumn for Box<str> {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        String::from_column(value).map(String::into_boxed_str)
+    }
+}
+
+impl FromColumn for Vec<u8> {
+    fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+        match value {
+            libsql::Value::Blob(val) => Ok(val),
+            _ => Err(libsql::Error::InvalidColumnType),
+        }
+    }
+}
+
+/// Implements [`FromColumn`] for types stored as text and parsed with
+/// [`FromStr`].
+macro_rules! from_column_parse {
+    ($($ty:ty),*) => {
+        $(
+            impl FromColumn for $ty {
+                fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+                    String::from_column(value)?
+                        .parse()
+                        .map_err(|_| libsql::Error::InvalidColumnType)
+                }
+            }
+        )*
+    };
+}
+
+from_column_parse!(Cid, Tid);
+
+/// Implements [`FromColumn`] for types stored as text and validated with
+/// [`TryFrom<Box<str>>`].
+macro_rules! from_column_try_from {
+    ($($ty:ty),*) => {
+        $(
+            impl FromColumn for $ty {
+                fn from_column(value: libsql::Value) -> libsql::Result<Self> {
+                    Self::try_from(Box::<str>::from_column(value)?)
+                        .map_err(|_| libsql::Error::InvalidColumnType)
+                }
+            }
+        )*
+    };
+}
+
+from_column_try_from!(AtUri, Did, Handle, Nsid, RecordKey);
+
+/// A type that can be read from a row.
+///
+/// This is implemented for tuples of [`FromColumn`] types, read from the
+/// columns of the row in order.
+pub trait FromRow: Sized {
+    /// Reads the row.
+    fn from_row(row: &libsql::Row) -> libsql::Result<Self>;
+}
+
+/// Implements [`FromRow`] for a tuple.
+macro_rules! from_row_tuple {
+    ($($ty:ident $idx:literal),*) => {
+        impl<$($ty: FromColumn),*> FromRow for ($($ty,)*) {
+            fn from_row(row: &libsql::Row) -> libsql::Result<Self> {
+                Ok(($($ty::from_column(row.get_value($idx)?)?,)*))
+            }
+        }
+    };
+}
+
+from_row_tuple!(A 0);
+from_row_tuple!(A 0, B 1);
+from_row_tuple!(A 0, B 1, C 2);
+from_row_tuple!(A 0, B 1, C 2, D 3);
+from_row_tuple!(A 0, B 1, C 2, D 3, E 4);
+from_row_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Typed query helpers, available on connections and transactions.
+pub trait Queries {
+    /// Runs a query and reads its first row, if any.
+    fn query_opt<T: FromRow>(
+        &self,
+        sql: &str,
+        params: impl Send + IntoParams,
+    ) -> impl Send + Future<Output = libsql::Result<Option<T>>>;
+
+    /// Runs a query and reads all of its rows.
+    fn query_all<T: FromRow + Send>(
+        &self,
+        sql: &str,
+        params: impl Send + IntoParams,
+    ) -> impl Send + Future<Output = libsql::Result<Vec<T>>>;
+}
 
-        Self { db }
+impl Queries for libsql::Connection {
+    fn query_opt<T: FromRow>(
+        &self,
+        sql: &str,
+        params: impl Send + IntoParams,
+    ) -> impl Send + Future<Output = libsql::Result<Option<T>>> {
+        async move {
+            let mut rows = self.query(sql, params).await?;
+            match rows.next().await? {
+                Some(row) => T::from_row(&row).map(Some),
+                None => Ok(None),
+            }
+        }
     }
+
+    fn query_all<T: FromRow + Send>(
+        &self,
+        sql: &str,
+        params: impl Send + IntoParams,
+    ) -> impl Send + Future<Output = libsql::Result<Vec<T>>> {
+        async move {
+            let mut rows = self.query(sql, params).await?;
+            let mut ret = Vec::new();
+            while let Some(row) = rows.next().await? {
+                ret.push(T::from_row(&row)?);
+            }
+            Ok(ret)
+        }
+    }
+}
+
+/// A database stored in a temporary file, removed when dropped.
+#[cfg(test)]
+pub struct TemporaryDatabase {
+    db: Database,
+    path: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TemporaryDatabase {
+    /// Creates a new temporary database, to which the provided `migrations`
+    /// are applied.
+    pub async fn new(migrations: &[Migration]) -> Self {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "rpds-test-{}-{}.db",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+        ));
+        let db = Database::open(path.to_str().unwrap(), migrations)
+            .await
+            .unwrap();
+        Self { db, path }
+    }
+}
+
+#[cfg(test)]
+impl Deref for TemporaryDatabase {
+    type Target = Database;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.db
+    }
+}
+
+#[cfg(test)]
+impl Drop for TemporaryDatabase {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.path.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A migration of the database schema.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// The version of the schema after the migration has been applied.
+    pub version: u32,
+    /// The name of the migration file, without its extension.
+    pub name: &'static str,
+    /// The SQL statements of the migration.
+    pub sql: &'static str,
+}
+
+/// Declares a [`Migration`] from a file of a sub-directory of `migrations`.
+///
+/// The version of the migration is the number that starts its name.
+macro_rules! migration {
+    ($dir:literal, $version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            sql: include_str!(concat!("../../migrations/", $dir, "/", $name, ".sql")),
+        }
+    };
+}
+
+/// The migrations of the service database, in the order they must be
+/// applied.
+///
+/// The service database holds the data shared by all accounts.
+pub const SERVICE_MIGRATIONS: &[Migration] = &[
+    migration!("service", 0, "000-2024-12-12"),
+    migration!("service", 1, "001-2024-12-20"),
+];
+
+/// The migrations of the actor stores, in the order they must be applied.
+///
+/// See [`ActorStores`].
+pub const ACTOR_MIGRATIONS: &[Migration] = &[
+    migration!("actor", 0, "000-2024-12-16"),
+    migration!("actor", 1, "001-2024-12-18"),
+];
+
+/// An error that might occur when migrating the database.
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The database has tables, but does not record which migrations were
+    /// applied to it.
+    ///
+    /// Such a database was created before migrations were tracked, and must
+    /// be migrated by hand.
+    Unversioned,
+    /// The database was migrated by a more recent version of the server.
+    Ahead {
+        /// The version of the database schema.
+        database: u32,
+        /// The latest version known to the server.
+        binary: u32,
+    },
+    /// A migration applied to the database does not match the one known to
+    /// the server for the same version.
+    Mismatch {
+        /// The version of the migration.
+        version: u32,
+        /// The name of the migration, as recorded in the database.
+        name: String,
+    },
+    /// The database failed.
+    Database(libsql::Error),
+}
+
+impl std::fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unversioned => f.write_str(
+                "the database has tables but no migration history; refusing to modify it",
+            ),
+            Self::Ahead { database, binary } => write!(
+                f,
+                "the database schema (version {database}) is more recent than the one \
+                 supported by the server (version {binary})",
+            ),
+            Self::Mismatch { version, name } => write!(
+                f,
+                "migration {version} was applied as `{name}`, which the server does not know",
+            ),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<libsql::Error> for MigrationError {
+    #[inline]
+    fn from(value: libsql::Error) -> Self {
+        Self::Database(value)
+    }
+}
+
+/// Applies the pending `migrations` to the database.
+///
+/// Applied migrations are recorded in the `schema_migrations` table. Each
+/// migration runs in its own transaction, alongside the update of that
+/// table, so a failed migration leaves the database untouched.
+///
+/// Returns the number of migrations that were applied.
+pub async fn migrate(
+    conn: &libsql::Connection,
+    migrations: &[Migration],
+) -> Result<usize, MigrationError> {
+    let mut count = 0;
+    loop {
+        let tx = conn
+            .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+            .await?;
+
+        // The history is read again in each transaction, in case another
+        // process is migrating the same database.
+        let applied = applied_migrations(&tx, migrations).await?;
+        let Some(m) = migrations.get(applied) else {
+            tx.commit().await?;
+            return Ok(count);
+        };
+
+        tx.execute_batch(m.sql).await?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            libsql::params![m.version, m.name],
+        )
+        .await?;
+        tx.commit().await?;
+
+        info!("Applied database migration `{}`", m.name);
+        count += 1;
+    }
+}
+
+/// Returns the number of `migrations` that were applied to the database,
+/// creating the `schema_migrations` table if needed.
+async fn applied_migrations(
+    conn: &libsql::Connection,
+    migrations: &[Migration],
+) -> Result<usize, MigrationError> {
+    let mut rows = conn
+        .query(
+            "SELECT \
+                EXISTS (SELECT 1 FROM sqlite_schema \
+                        WHERE type = 'table' AND name = 'schema_migrations'), \
+                EXISTS (SELECT 1 FROM sqlite_schema \
+                        WHERE type = 'table' AND name NOT LIKE 'sqlite_%')",
+            (),
+        )
+        .await?;
+    let row = rows.next().await?.expect("the query always returns a row");
+    let (versioned, has_tables) = (row.get::<bool>(0)?, row.get::<bool>(1)?);
+    drop(rows);
+
+    // Never run the migrations against a database that might hold data
+    // they do not expect.
+    if !versioned && has_tables {
+        return Err(MigrationError::Unversioned);
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (\
+            version INTEGER PRIMARY KEY, \
+            name TEXT NOT NULL, \
+            applied_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))\
+         ) STRICT",
+        (),
+    )
+    .await?;
+
+    let mut applied = 0;
+    let mut rows = conn
+        .query(
+            "SELECT version, name FROM schema_migrations ORDER BY version",
+            (),
+        )
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let version = row.get::<u32>(0)?;
+        let name = row.get::<String>(1)?;
+
+        match migrations.get(applied) {
+            Some(m) if m.version == version && m.name == name => applied += 1,
+            Some(_) => return Err(MigrationError::Mismatch { version, name }),
+            None => {
+                return Err(MigrationError::Ahead {
+                    database: version,
+                    binary: migrations.last().map_or(0, |m| m.version),
+                })
+            }
+        }
+    }
+
+    Ok(applied)
 }
 
-/// Creates a new [`libsql::Database`] object using the environment variables
-/// to configure it.
-async fn create_db_object() -> libsql::Database {
-    let database_file = expect_env("RPDS_DATABASE_FILE");
+#[cfg(test)]
+#[test]
+fn migrations_match_directory() {
+    for (dir, migrations) in [("service", SERVICE_MIGRATIONS), ("actor", ACTOR_MIGRATIONS)] {
+        let dir = format!("{}/migrations/{dir}", env!("CARGO_MANIFEST_DIR"));
+        let mut names = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        assert_eq!(names.len(), migrations.len());
+        for (i, (name, m)) in names.iter().zip(migrations).enumerate() {
+            assert_eq!(name.strip_suffix(".sql"), Some(m.name));
+            assert_eq!(m.version as usize, i);
+            assert_eq!(m.name.split('-').next().unwrap().parse(), Ok(m.version));
+        }
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn migrate_database() {
+    async fn open() -> libsql::Connection {
+        let db = libsql::Builder::new_local(":memory:")
+            .build()
+            .await
+            .unwrap();
+        db.connect().unwrap()
+    }
+
+    let conn = open().await;
+    assert_eq!(
+        migrate(&conn, SERVICE_MIGRATIONS).await.unwrap(),
+        SERVICE_MIGRATIONS.len()
+    );
+    assert_eq!(migrate(&conn, SERVICE_MIGRATIONS).await.unwrap(), 0);
 
-    libsql::Builder::new_local(database_file.as_str())
-        .build()
+    conn.execute(
+        "INSERT INTO schema_migrations (version, name) VALUES (?1, 'from-the-future')",
+        [SERVICE_MIGRATIONS.len() as u32],
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        migrate(&conn, SERVICE_MIGRATIONS).await,
+        Err(MigrationError::Ahead { .. })
+    ));
+
+    // The service and actor histories are not interchangeable.
+    assert!(matches!(
+        migrate(&conn, ACTOR_MIGRATIONS).await,
+        Err(MigrationError::Mismatch { version: 0, .. })
+    ));
+
+    let conn = open().await;
+    conn.execute("CREATE TABLE accounts (did TEXT PRIMARY KEY)", ())
+        .await
+        .unwrap();
+    assert!(matches!(
+        migrate(&conn, SERVICE_MIGRATIONS).await,
+        Err(MigrationError::Unversioned)
+    ));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn connections() {
+    let db = TemporaryDatabase::new(SERVICE_MIGRATIONS).await;
+    let conn = db.connect().await.unwrap();
+
+    let pragmas = conn
+        .query_opt::<(String, u32, bool)>(
+            "SELECT * FROM pragma_journal_mode, pragma_busy_timeout, pragma_foreign_keys",
+            (),
+        )
+        .await
+        .unwrap();
+    assert_eq!(pragmas, Some(("wal".into(), 5000, true)));
+
+    let did = "did:plc:abcdefghijklmnopqrstuvwx";
+    let tx = conn.transaction().await.unwrap();
+    tx.execute(
+        "INSERT INTO accounts (did, email) VALUES (?1, 'alice@example.com')",
+        [did],
+    )
+    .await
+    .unwrap();
+
+    // The write lock is held until the transaction ends.
+    let other = db.connect().await.unwrap();
+    other
+        .execute_batch("PRAGMA busy_timeout = 0")
+        .await
+        .unwrap();
+    let err = other
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .err()
+        .unwrap();
+    assert!(is_busy(&err));
+
+    tx.commit().await.unwrap();
+
+    let accounts = other
+        .query_all::<(Did, Option<String>, bool)>(
+            "SELECT did, password_hash, email_verified FROM accounts",
+            (),
+        )
+        .await
+        .unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].0.as_str(), did);
+    assert_eq!(accounts[0].1, None);
+    assert!(!accounts[0].2);
+
+    assert!(matches!(
+        conn.query_opt::<(i64,)>("SELECT email FROM accounts", ())
+            .await,
+        Err(libsql::Error::InvalidColumnType)
+    ));
+
+    // Foreign keys are enforced.
+    let actor = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    assert!(actor
+        .connect()
+        .await
+        .unwrap()
+        .execute(
+            "INSERT INTO record_blob (blob_cid, collection, rkey) VALUES ('', 'app.bsky.feed.post', 'a')",
+            (),
+        )
         .await
-        .unwrap_or_else(|err| {
-            panic!("Failed to create a database object at `{database_file}`: {err}")
-        })
+        .is_err());
 }
@@ -0,0 +1,261 @@
+use {
+    super::{Connection, Database, MigrationError, ACTOR_MIGRATIONS},
+    crate::api::xrpc::model::Did,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    },
+};
+
+/// An error that might occur when accessing an actor store.
+#[derive(Debug)]
+pub enum ActorStoreError {
+    /// The file of the store could not be created or removed.
+    Io(std::io::Error),
+    /// The store could not be opened or migrated.
+    Open(MigrationError),
+    /// The database failed.
+    Database(libsql::Error),
+}
+
+impl std::fmt::Display for ActorStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "actor store I/O error: {err}"),
+            Self::Open(err) => write!(f, "can't open actor store: {err}"),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ActorStoreError {}
+
+impl From<std::io::Error> for ActorStoreError {
+    #[inline]
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<MigrationError> for ActorStoreError {
+    #[inline]
+    fn from(value: MigrationError) -> Self {
+        Self::Open(value)
+    }
+}
+
+impl From<libsql::Error> for ActorStoreError {
+    #[inline]
+    fn from(value: libsql::Error) -> Self {
+        Self::Database(value)
+    }
+}
+
+/// The per-account databases of the server, or "actor stores".
+///
+/// Each account stores its repository and private data in its own SQLite
+/// file, named after its DID. This keeps writes to different repositories
+/// from contending on a single lock, and makes exporting or deleting an
+/// account a matter of copying or removing a file.
+///
+/// Stores are opened lazily, and the most recently used ones are kept open.
+pub struct ActorStores {
+    /// The directory holding the stores.
+    dir: PathBuf,
+    /// The maximum number of stores kept open.
+    capacity: usize,
+    /// The stores currently open.
+    cache: Mutex<Cache>,
+}
+
+/// The stores kept open by [`ActorStores`].
+#[derive(Default)]
+struct Cache {
+    /// The open stores, with the last time they were used.
+    stores: HashMap<Did, (Arc<Database>, u64)>,
+    /// Incremented each time a store is used.
+    clock: u64,
+}
+
+impl Cache {
+    /// Returns the store of the provided account, marking it as used.
+    fn get(&mut self, did: &Did) -> Option<Arc<Database>> {
+        self.clock += 1;
+        let (db, last_used) = self.stores.get_mut(did)?;
+        *last_used = self.clock;
+        Some(db.clone())
+    }
+
+    /// Inserts a store, evicting the least recently used ones to make room
+    /// for it.
+    ///
+    /// If the store of the account was opened concurrently, that one is kept
+    /// and returned instead.
+    fn insert(&mut self, did: &Did, db: Arc<Database>, capacity: usize) -> Arc<Database> {
+        if let Some(db) = self.get(did) {
+            return db;
+        }
+
+        while self.stores.len() >= capacity.max(1) {
+            let lru = self
+                .stores
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(did, _)| did.clone())
+                .unwrap();
+            self.stores.remove(&lru);
+        }
+
+        self.stores.insert(did.clone(), (db.clone(), self.clock));
+        db
+    }
+}
+
+impl ActorStores {
+    /// Creates a new [`ActorStores`] instance, storing its databases in the
+    /// provided directory and keeping at most `capacity` of them open.
+    ///
+    /// The directory is created along with the first store.
+    pub fn new(dir: PathBuf, capacity: usize) -> Self {
+        Self {
+            dir,
+            capacity,
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Returns the path of the file holding the store of the provided
+    /// account.
+    ///
+    /// The file may not exist.
+    pub fn path(&self, did: &Did) -> PathBuf {
+        self.dir.join(format!("{}.sqlite", did.as_str()))
+    }
+
+    /// Returns the store of the provided account, or `None` if the account
+    /// has no store.
+    pub async fn open(&self, did: &Did) -> Result<Option<Arc<Database>>, ActorStoreError> {
+        if let Some(db) = self.cache.lock().unwrap().get(did) {
+            return Ok(Some(db));
+        }
+
+        let path = self.path(did);
+        if !tokio::fs::try_exists(&path).await? {
+            return Ok(None);
+        }
+
+        self.load(did, &path).await.map(Some)
+    }
+
+    /// Opens a connection to the store of the provided account, or returns
+    /// `None` if the account has no store.
+    pub async fn connect(&self, did: &Did) -> Result<Option<Connection>, ActorStoreError> {
+        match self.open(did).await? {
+            Some(db) => Ok(Some(db.connect().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the store of the provided account, creating it if it does not
+    /// exist.
+    pub async fn create(&self, did: &Did) -> Result<Arc<Database>, ActorStoreError> {
+        if let Some(db) = self.cache.lock().unwrap().get(did) {
+            return Ok(db);
+        }
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        self.load(did, &self.path(did)).await
+    }
+
+    /// Removes the store of the provided account.
+    ///
+    /// Connections to the store that are still open keep working, but their
+    /// writes are lost.
+    ///
+    /// Returns whether the account had a store.
+    pub async fn delete(&self, did: &Did) -> Result<bool, ActorStoreError> {
+        self.cache.lock().unwrap().stores.remove(did);
+
+        let path = self.path(did);
+        let existed = tokio::fs::try_exists(&path).await?;
+        for suffix in ["-wal", "-shm", ""] {
+            let mut path = path.clone().into_os_string();
+            path.push(suffix);
+            match tokio::fs::remove_file(path).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                _ => (),
+            }
+        }
+
+        Ok(existed)
+    }
+
+    /// Opens the store at `path`, creating it if needed, and caches it.
+    async fn load(&self, did: &Did, path: &Path) -> Result<Arc<Database>, ActorStoreError> {
+        let path = path.to_str().ok_or(libsql::Error::InvalidUTF8Path)?;
+        let db = Arc::new(Database::open(path, ACTOR_MIGRATIONS).await?);
+        Ok(self.cache.lock().unwrap().insert(did, db, self.capacity))
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn actor_stores() {
+    use super::Queries;
+
+    let dir = std::env::temp_dir().join(format!("rpds-test-actors-{}", std::process::id()));
+    let stores = ActorStores::new(dir.clone(), 2);
+    let did = |s: &str| Did::try_from(Box::<str>::from(s)).unwrap();
+    let (alice, bob, carol) = (
+        did("did:plc:alice"),
+        did("did:plc:bob"),
+        did("did:plc:carol"),
+    );
+
+    assert!(stores.open(&alice).await.unwrap().is_none());
+    assert!(!stores.path(&alice).exists());
+
+    let store = stores.create(&alice).await.unwrap();
+    assert!(Arc::ptr_eq(
+        &store,
+        &stores.open(&alice).await.unwrap().unwrap()
+    ));
+    store
+        .connect()
+        .await
+        .unwrap()
+        .execute(
+            "INSERT INTO repo_root (id, cid, rev) VALUES (0, 'cid', 'rev')",
+            (),
+        )
+        .await
+        .unwrap();
+
+    // Opening a third store evicts the least recently used one.
+    stores.create(&bob).await.unwrap();
+    stores.open(&alice).await.unwrap();
+    stores.create(&carol).await.unwrap();
+    {
+        let cache = stores.cache.lock().unwrap();
+        assert!(cache.stores.contains_key(&alice));
+        assert!(!cache.stores.contains_key(&bob));
+    }
+
+    // Evicted stores are opened again from their file.
+    let conn = stores.connect(&bob).await.unwrap().unwrap();
+    drop(conn);
+    let conn = stores.connect(&alice).await.unwrap().unwrap();
+    let count = conn
+        .query_opt::<(i64,)>("SELECT COUNT(*) FROM repo_root", ())
+        .await
+        .unwrap();
+    assert_eq!(count, Some((1,)));
+
+    assert!(stores.delete(&alice).await.unwrap());
+    assert!(!stores.delete(&alice).await.unwrap());
+    assert!(stores.open(&alice).await.unwrap().is_none());
+
+    drop(stores);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -1,11 +1,20 @@
 //! Defines the global state of the application.
 
 use {
-    self::{database::Database, password::PasswordHasher},
-    crate::expect_env,
+    self::{
+        auth::TokenKey,
+        database::{ActorStores, Database},
+        password::PasswordHasher,
+    },
+    crate::{
+        blob::FsBlobStore, expect_env, identity::IdentityResolver, lexicon::LexiconRegistry,
+        try_get_and_parse_env, try_get_env,
+    },
     std::sync::OnceLock,
+    tracing::info,
 };
 
+pub mod auth;
 pub mod database;
 pub mod password;
 
@@ -17,8 +26,20 @@ pub mod password;
 pub struct GlobalState {
     /// The password hasher responsible for hashing and verifying passwords.
     pub password_hasher: PasswordHasher,
-    /// The database instance.
+    /// The key used to sign and verify session tokens.
+    pub token_key: TokenKey,
+    /// The service database, holding the data shared by all accounts.
     pub database: Database,
+    /// The per-account databases.
+    pub actor_stores: ActorStores,
+    /// The storage of the blobs uploaded by accounts.
+    pub blob_store: FsBlobStore,
+    /// The maximum size of an uploaded blob, in bytes.
+    pub max_blob_size: u64,
+    /// The Lexicon documents known to the server.
+    pub lexicons: LexiconRegistry,
+    /// Resolves the DIDs and handles of accounts.
+    pub identity: IdentityResolver,
 }
 
 /// The global state of the application.
@@ -27,16 +48,63 @@ static STATE: OnceLock<GlobalState> = OnceLock::new();
 /// Initializes the global state of the application.
 pub async fn initialize() {
     let database = Database::new().await;
+    let actor_stores = load_actor_stores();
+    let blob_store = load_blob_store();
+    let max_blob_size = try_get_and_parse_env("RPDS_MAX_BLOB_SIZE").unwrap_or(5 * 1024 * 1024);
     let password_hasher = PasswordHasher::new();
+    let token_key = TokenKey::new();
+    let lexicons = load_lexicons();
+    let identity = IdentityResolver::new();
 
     STATE
         .set(GlobalState {
             database,
+            actor_stores,
+            blob_store,
+            max_blob_size,
             password_hasher,
+            token_key,
+            lexicons,
+            identity,
         })
         .unwrap_or_else(|_| panic!("the global state was already initialized"));
 }
 
+/// Creates the [`ActorStores`] instance, storing the databases in the
+/// directory pointed to by `RPDS_ACTOR_STORE_DIR`.
+///
+/// At most `RPDS_ACTOR_STORE_CACHE_SIZE` stores are kept open (128 by
+/// default).
+fn load_actor_stores() -> ActorStores {
+    let dir = expect_env("RPDS_ACTOR_STORE_DIR");
+    let capacity = try_get_and_parse_env("RPDS_ACTOR_STORE_CACHE_SIZE").unwrap_or(128);
+
+    ActorStores::new(dir.into(), capacity)
+}
+
+/// Creates the blob store, storing the blobs in the directory pointed to by
+/// `RPDS_BLOB_DIR`.
+fn load_blob_store() -> FsBlobStore {
+    let dir = expect_env("RPDS_BLOB_DIR");
+    FsBlobStore::new(dir.as_ref())
+        .unwrap_or_else(|err| panic!("Failed to create the blob directory `{dir}`: {err}"))
+}
+
+/// Loads the bundled Lexicon documents, as well as those found in the
+/// directory pointed to by `RPDS_LEXICON_DIR`, if any.
+fn load_lexicons() -> LexiconRegistry {
+    let mut lexicons = LexiconRegistry::with_bundled();
+
+    if let Some(dir) = try_get_env("RPDS_LEXICON_DIR") {
+        let count = lexicons
+            .load_dir(dir.as_ref())
+            .unwrap_or_else(|err| panic!("Failed to load the Lexicons of `{dir}`: {err}"));
+        info!("Loaded {count} Lexicon documents from `{dir}`");
+    }
+
+    lexicons
+}
+
 /// Returns a reference to the global state of the application.
 #[track_caller]
 pub fn get() -> &'static GlobalState {
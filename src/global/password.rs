@@ -160,13 +160,12 @@ impl PasswordHasher {
                 let argon2 =
                     make_argon2_engine(algorithm, version, m_cost, t_cost, p_cost, self.secret);
 
-                let mut decoded_salt = [0u8; argon2::MAX_SALT_LEN];
-                let salt = B64::decode(salt, &mut decoded_salt).unwrap();
+                let salt = B64::decode_vec(salt).unwrap();
 
                 let expected_hash = B64::decode_vec(hash).unwrap();
                 let mut computed_hash = vec![0u8; expected_hash.len()];
 
-                if let Err(err) = argon2.hash_password_into(password, salt, &mut computed_hash) {
+                if let Err(err) = argon2.hash_password_into(password, &salt, &mut computed_hash) {
                     error!("Failed to hash the password: {err}");
                     return false;
                 };
@@ -189,7 +188,7 @@ fn make_argon2_engine(
     time_cost: u32,
     parallelism: u32,
     secret: Option<&[u8]>,
-) -> argon2::Argon2 {
+) -> argon2::Argon2<'_> {
     #[inline(never)]
     #[cold]
     #[track_caller]
@@ -0,0 +1,97 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The maximum number of entries of a [`TtlCache`].
+///
+/// When it is reached, expired entries are dropped, and the whole cache is
+/// cleared if none had expired.
+const MAX_ENTRIES: usize = 10_000;
+
+/// The outcome of a resolution, as kept by a [`TtlCache`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cached<T> {
+    /// The resolution succeeded, finding the value or that it does not
+    /// exist.
+    Resolved(Option<T>),
+    /// The resolution failed.
+    Failed,
+}
+
+/// A cache of resolutions, whose entries expire after a while.
+///
+/// Failures are kept for a shorter time than successful resolutions, so
+/// that an unreachable server is not queried on every request, but is
+/// queried again soon.
+#[derive(Debug)]
+pub struct TtlCache<K, T> {
+    /// The cached entries, with the time they were inserted.
+    entries: Mutex<HashMap<K, (Instant, Cached<T>)>>,
+    /// How long successful resolutions are kept.
+    ttl: Duration,
+    /// How long failures are kept.
+    failure_ttl: Duration,
+}
+
+impl<K: Eq + Hash, T: Clone> TtlCache<K, T> {
+    /// Creates a new empty [`TtlCache`].
+    pub fn new(ttl: Duration, failure_ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+            failure_ttl,
+        }
+    }
+
+    /// Returns whether an entry inserted at `inserted_at` has not expired.
+    fn is_fresh(&self, inserted_at: Instant, value: &Cached<T>) -> bool {
+        let ttl = match value {
+            Cached::Resolved(_) => self.ttl,
+            Cached::Failed => self.failure_ttl,
+        };
+        inserted_at.elapsed() < ttl
+    }
+
+    /// Returns the cached outcome of the resolution of `key`, unless it
+    /// expired.
+    pub fn get(&self, key: &K) -> Option<Cached<T>> {
+        let entries = self.entries.lock().unwrap();
+        let (inserted_at, value) = entries.get(key)?;
+        self.is_fresh(*inserted_at, value).then(|| value.clone())
+    }
+
+    /// Caches the outcome of the resolution of `key`.
+    pub fn insert(&self, key: K, value: Cached<T>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, (inserted_at, value)| self.is_fresh(*inserted_at, value));
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(key, (Instant::now(), value));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn expiration() {
+    let cache = TtlCache::new(Duration::from_secs(3600), Duration::ZERO);
+    cache.insert("found", Cached::Resolved(Some(1)));
+    cache.insert("missing", Cached::Resolved(None));
+    cache.insert("failed", Cached::Failed);
+
+    assert_eq!(cache.get(&"found"), Some(Cached::Resolved(Some(1))));
+    assert_eq!(cache.get(&"missing"), Some(Cached::Resolved(None)));
+    assert_eq!(cache.get(&"failed"), None);
+    assert_eq!(cache.get(&"unknown"), None);
+
+    let cache = TtlCache::new(Duration::ZERO, Duration::from_secs(3600));
+    cache.insert("found", Cached::Resolved(Some(1)));
+    cache.insert("failed", Cached::Failed);
+    assert_eq!(cache.get(&"found"), None);
+    assert_eq!(cache.get(&"failed"), Some(Cached::<i32>::Failed));
+}
@@ -0,0 +1,341 @@
+//! Resolution of the identities (DIDs and handles) of accounts.
+//!
+//! DIDs resolve to DID documents, which list the handles of the account in
+//! their `alsoKnownAs` field. Handles resolve to DIDs, either through the
+//! `TXT` record of the `_atproto` subdomain or through the
+//! `/.well-known/atproto-did` HTTPS endpoint. A handle is only valid if both
+//! directions agree. See the [handle specification] for more information.
+//!
+//! Resolutions are cached for a while, so that serving a request does not
+//! always query other servers.
+//!
+//! [handle specification]: https://atproto.com/specs/handle
+
+mod cache;
+pub use self::cache::*;
+
+use {
+    crate::{
+        api::xrpc::model::{Did, Handle},
+        dag_cbor::Value,
+        try_get_env,
+    },
+    hickory_resolver::{
+        config::{ResolverConfig, ResolverOpts},
+        error::ResolveErrorKind,
+        TokioAsyncResolver,
+    },
+    http_body_util::{BodyExt, Empty, Limited},
+    hyper::{body::Bytes, StatusCode},
+    hyper_rustls::HttpsConnector,
+    hyper_util::{
+        client::legacy::{connect::HttpConnector, Client},
+        rt::TokioExecutor,
+    },
+    std::time::Duration,
+    tracing::{debug, warn},
+};
+
+/// The maximum size of a DID document, or of any other response used to
+/// resolve an identity.
+const MAX_RESPONSE_LEN: usize = 64 * 1024;
+/// How long to wait for a response.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long DID documents and handle resolutions are cached.
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// How long failed resolutions are cached.
+const FAILURE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// An error that might occur when resolving an identity.
+#[derive(Debug)]
+pub enum IdentityError {
+    /// The DID method is not supported, or the DID can't be resolved.
+    UnsupportedDid,
+    /// The request failed, or timed out.
+    Http(Box<dyn std::error::Error + Send + Sync>),
+    /// The server responded with an error status.
+    Status(StatusCode),
+    /// The DID document is malformed, or describes another DID.
+    InvalidDocument,
+    /// The same resolution failed recently, and is not attempted again yet.
+    RecentFailure,
+}
+
+impl std::fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedDid => f.write_str("unsupported DID"),
+            Self::Http(err) => write!(f, "request failed: {err}"),
+            Self::Status(status) => write!(f, "the server responded with status {status}"),
+            Self::InvalidDocument => f.write_str("invalid DID document"),
+            Self::RecentFailure => f.write_str("the resolution failed recently"),
+        }
+    }
+}
+
+impl std::error::Error for IdentityError {}
+
+/// Resolves DIDs and handles.
+pub struct IdentityResolver {
+    client: Client<HttpsConnector<HttpConnector>, Empty<Bytes>>,
+    /// The DNS resolver used to look up the `TXT` records of handles.
+    dns: TokioAsyncResolver,
+    /// The DID documents, by DID.
+    documents: TtlCache<Did, Value>,
+    /// The DIDs that handles point to, by lowercase handle.
+    handles: TtlCache<String, Did>,
+    /// The base URL of the PLC directory, without trailing slash.
+    plc_url: String,
+}
+
+impl IdentityResolver {
+    /// Creates a new [`IdentityResolver`].
+    ///
+    /// `did:plc` DIDs are resolved using the PLC directory at
+    /// `RPDS_PLC_URL` (`https://plc.directory` by default). DNS lookups use
+    /// the name servers configured on the system.
+    pub fn new() -> Self {
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .build();
+
+        let plc_url = try_get_env("RPDS_PLC_URL")
+            .unwrap_or_else(|| "https://plc.directory".to_owned())
+            .trim_end_matches('/')
+            .to_owned();
+
+        let dns = TokioAsyncResolver::tokio_from_system_conf().unwrap_or_else(|err| {
+            warn!("Failed to read the DNS configuration of the system, using the defaults: {err}");
+            TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+        });
+
+        Self {
+            client: Client::builder(TokioExecutor::new()).build(connector),
+            dns,
+            documents: TtlCache::new(CACHE_TTL, FAILURE_CACHE_TTL),
+            handles: TtlCache::new(CACHE_TTL, FAILURE_CACHE_TTL),
+            plc_url,
+        }
+    }
+
+    /// Fetches the provided URL, returning `None` if it does not exist.
+    async fn fetch(&self, url: &str) -> Result<Option<Bytes>, IdentityError> {
+        let request = hyper::Request::get(url)
+            .body(Empty::new())
+            .map_err(|err| IdentityError::Http(err.into()))?;
+
+        let response = tokio::time::timeout(TIMEOUT, async {
+            let response = self
+                .client
+                .request(request)
+                .await
+                .map_err(|err| IdentityError::Http(err.into()))?;
+            match response.status() {
+                StatusCode::NOT_FOUND | StatusCode::GONE => return Ok(None),
+                status if !status.is_success() => return Err(IdentityError::Status(status)),
+                _ => (),
+            }
+
+            let body = Limited::new(response.into_body(), MAX_RESPONSE_LEN)
+                .collect()
+                .await
+                .map_err(IdentityError::Http)?;
+            Ok(Some(body.to_bytes()))
+        });
+
+        match response.await {
+            Ok(ret) => ret,
+            Err(_) => Err(IdentityError::Http("timed out".into())),
+        }
+    }
+
+    /// Resolves the DID document of the provided DID.
+    ///
+    /// Returns `None` if the DID does not exist.
+    pub async fn resolve_did(&self, did: &Did) -> Result<Option<Value>, IdentityError> {
+        cached(&self.documents, did.clone(), self.fetch_document(did)).await
+    }
+
+    /// Fetches the DID document of the provided DID, bypassing the cache.
+    async fn fetch_document(&self, did: &Did) -> Result<Option<Value>, IdentityError> {
+        let url = document_url(did, &self.plc_url).ok_or(IdentityError::UnsupportedDid)?;
+        let Some(body) = self.fetch(&url).await? else {
+            return Ok(None);
+        };
+
+        let doc: Value =
+            serde_json::from_slice(&body).map_err(|_| IdentityError::InvalidDocument)?;
+        if doc.get("id").and_then(Value::as_str) != Some(did.as_str()) {
+            return Err(IdentityError::InvalidDocument);
+        }
+        Ok(Some(doc))
+    }
+
+    /// Resolves the DID that the provided handle points to.
+    ///
+    /// The `TXT` record of the `_atproto` subdomain is checked first, then
+    /// the `/.well-known/atproto-did` HTTPS endpoint.
+    pub async fn resolve_handle(&self, handle: &Handle) -> Result<Option<Did>, IdentityError> {
+        let handle = handle.as_str().to_ascii_lowercase();
+        cached(&self.handles, handle.clone(), self.lookup_handle(&handle)).await
+    }
+
+    /// Resolves the provided lowercase handle, bypassing the cache.
+    async fn lookup_handle(&self, handle: &str) -> Result<Option<Did>, IdentityError> {
+        // The name is fully qualified, so that no search domain is appended.
+        match self.dns.txt_lookup(format!("_atproto.{handle}.")).await {
+            Ok(records) => {
+                let mut dids = records.iter().filter_map(|record| {
+                    // A record may be split into several character strings.
+                    let record: Vec<u8> = record.iter().flat_map(|s| s.iter().copied()).collect();
+                    let did = std::str::from_utf8(&record).ok()?.strip_prefix("did=")?;
+                    Did::try_from(Box::<str>::from(did)).ok()
+                });
+                // Ambiguous records are ignored.
+                if let (Some(did), None) = (dids.next(), dids.next()) {
+                    return Ok(Some(did));
+                }
+            }
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => (),
+            Err(err) => debug!("Failed to look up the DNS records of `{handle}`: {err}"),
+        }
+
+        let url = format!("https://{handle}/.well-known/atproto-did");
+        Ok(self.fetch(&url).await?.and_then(|body| {
+            let did = std::str::from_utf8(&body).ok()?.trim();
+            Did::try_from(Box::<str>::from(did)).ok()
+        }))
+    }
+
+    /// Returns whether `handle` is a valid handle of the account whose DID
+    /// document is provided: the document must claim the handle, and the
+    /// handle must resolve back to the DID of the document.
+    pub async fn verify_handle(&self, handle: &Handle, did: &Did, doc: &Value) -> bool {
+        let claimed = handles(doc).any(|claimed| claimed.eq_ignore_ascii_case(handle.as_str()));
+        if !claimed {
+            return false;
+        }
+
+        match self.resolve_handle(handle).await {
+            Ok(resolved) => resolved.as_ref() == Some(did),
+            Err(err) => {
+                debug!("Failed to resolve the handle `{}`: {err}", handle.as_str());
+                false
+            }
+        }
+    }
+}
+
+/// Returns the cached resolution of `key`, or runs `resolve` and caches its
+/// outcome.
+async fn cached<K, T>(
+    cache: &TtlCache<K, T>,
+    key: K,
+    resolve: impl std::future::Future<Output = Result<Option<T>, IdentityError>>,
+) -> Result<Option<T>, IdentityError>
+where
+    K: Eq + std::hash::Hash,
+    T: Clone,
+{
+    match cache.get(&key) {
+        Some(Cached::Resolved(value)) => return Ok(value),
+        Some(Cached::Failed) => return Err(IdentityError::RecentFailure),
+        None => (),
+    }
+
+    let ret = resolve.await;
+    let outcome = match &ret {
+        Ok(value) => Cached::Resolved(value.clone()),
+        Err(_) => Cached::Failed,
+    };
+    cache.insert(key, outcome);
+    ret
+}
+
+/// Returns the URL of the DID document of `did`, or `None` if its method is
+/// not supported.
+fn document_url(did: &Did, plc_url: &str) -> Option<String> {
+    let did = did.as_str();
+    if did.starts_with("did:plc:") {
+        Some(format!("{plc_url}/{did}"))
+    } else if let Some(host) = did.strip_prefix("did:web:") {
+        // Only hostname-level `did:web` DIDs are supported, as documents
+        // under a path can't be used by accounts.
+        if host.contains(':') {
+            return None;
+        }
+        let host = host.replace("%3A", ":").replace("%3a", ":");
+        Some(format!("https://{host}/.well-known/did.json"))
+    } else {
+        None
+    }
+}
+
+/// Returns the handles listed in the `alsoKnownAs` field of a DID document.
+fn handles(doc: &Value) -> impl '_ + Iterator<Item = &str> {
+    let aliases = match doc.get("alsoKnownAs") {
+        Some(Value::List(aliases)) => &aliases[..],
+        _ => &[],
+    };
+    aliases
+        .iter()
+        .filter_map(|alias| alias.as_str()?.strip_prefix("at://"))
+}
+
+/// Returns the public key that signs the commits of the repository described
+/// by a DID document, in the multibase `Multikey` format.
+pub fn signing_key(doc: &Value) -> Option<&str> {
+    let Some(Value::List(methods)) = doc.get("verificationMethod") else {
+        return None;
+    };
+    methods
+        .iter()
+        .find(|method| {
+            method
+                .get("id")
+                .and_then(Value::as_str)
+                .is_some_and(|id| id.ends_with("#atproto"))
+        })?
+        .get("publicKeyMultibase")?
+        .as_str()
+}
+
+#[cfg(test)]
+#[test]
+fn did_documents() {
+    let did = |did: &str| Did::try_from(Box::<str>::from(did)).unwrap();
+    let plc = "https://plc.directory";
+
+    assert_eq!(
+        document_url(&did("did:plc:abcdefghijklmnopqrstuvwx"), plc).as_deref(),
+        Some("https://plc.directory/did:plc:abcdefghijklmnopqrstuvwx")
+    );
+    assert_eq!(
+        document_url(&did("did:web:localhost%3A8080"), plc).as_deref(),
+        Some("https://localhost:8080/.well-known/did.json")
+    );
+    assert_eq!(document_url(&did("did:web:example.com:user"), plc), None);
+    assert_eq!(document_url(&did("did:key:zQ3shokFTS3brHcD"), plc), None);
+
+    let doc: Value = serde_json::from_str(
+        r#"{
+            "id": "did:plc:abcdefghijklmnopqrstuvwx",
+            "alsoKnownAs": ["at://alice.example.com", "https://example.com"],
+            "verificationMethod": [{
+                "id": "did:plc:abcdefghijklmnopqrstuvwx#atproto",
+                "type": "Multikey",
+                "controller": "did:plc:abcdefghijklmnopqrstuvwx",
+                "publicKeyMultibase": "zQ3shXjHeiBuRCKmM36cuYnm7YEMzhGnCmCyW92sRJ9pribSF"
+            }]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(handles(&doc).collect::<Vec<_>>(), ["alice.example.com"]);
+    assert_eq!(
+        signing_key(&doc),
+        Some("zQ3shXjHeiBuRCKmM36cuYnm7YEMzhGnCmCyW92sRJ9pribSF")
+    );
+}
@@ -0,0 +1,8 @@
+//! The Lexicon documents bundled with the server.
+//!
+//! The `com.atproto.*` documents are always included, since the other
+//! namespaces build on them. The others depend on their `ns-*` feature. The
+//! list is generated by `build.rs` from the `lexicons` directory.
+
+/// The JSON source of the bundled documents.
+pub const BUNDLED: &[&str] = include!(concat!(env!("OUT_DIR"), "/bundled_lexicons.rs"));
@@ -0,0 +1,18 @@
+//! Support for [Lexicon](https://atproto.com/specs/lexicon), the schema
+//! language of the AT Protocol.
+//!
+//! Lexicon documents are loaded into a [`LexiconRegistry`], which can then
+//! validate the records written to repositories. The documents of the
+//! namespaces supported by the server are bundled with it, and more can be
+//! loaded from a directory at startup.
+
+mod bundled;
+
+mod schema;
+pub use self::schema::*;
+
+mod registry;
+pub use self::registry::*;
+
+mod validate;
+pub use self::validate::*;
@@ -0,0 +1,200 @@
+use {
+    super::{LexRecord, LexType, LexiconDoc},
+    crate::api::xrpc::model::validate_nsid,
+    std::{collections::HashMap, path::Path},
+};
+
+/// An error that might occur when loading a Lexicon document.
+#[derive(Debug)]
+pub enum LexiconError {
+    /// A file could not be read.
+    Io(Box<Path>, std::io::Error),
+    /// A document is not valid JSON, or does not follow the Lexicon schema.
+    Json(serde_json::Error),
+    /// The `id` of a document is not a valid NSID.
+    InvalidId(String),
+}
+
+impl std::fmt::Display for LexiconError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "can't read `{}`: {err}", path.display()),
+            Self::Json(err) => write!(f, "invalid Lexicon document: {err}"),
+            Self::InvalidId(id) => write!(f, "invalid Lexicon document id: `{id}`"),
+        }
+    }
+}
+
+impl std::error::Error for LexiconError {}
+
+/// A set of Lexicon documents, indexed by NSID.
+#[derive(Debug, Default)]
+pub struct LexiconRegistry {
+    docs: HashMap<String, LexiconDoc>,
+}
+
+impl LexiconRegistry {
+    /// Creates a new empty [`LexiconRegistry`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`LexiconRegistry`] holding the Lexicon documents
+    /// bundled with the server.
+    pub fn with_bundled() -> Self {
+        let mut registry = Self::new();
+        for json in super::bundled::BUNDLED {
+            registry
+                .load_json(json)
+                .unwrap_or_else(|err| panic!("bundled Lexicon: {err}"));
+        }
+        registry
+    }
+
+    /// Adds a document to the registry.
+    ///
+    /// If a document with the same NSID was already present, it is replaced
+    /// and returned.
+    pub fn add(&mut self, doc: LexiconDoc) -> Result<Option<LexiconDoc>, LexiconError> {
+        if !validate_nsid(doc.id.as_bytes()) {
+            return Err(LexiconError::InvalidId(doc.id));
+        }
+
+        Ok(self.docs.insert(doc.id.clone(), doc))
+    }
+
+    /// Parses a JSON Lexicon document and adds it to the registry.
+    pub fn load_json(&mut self, json: &str) -> Result<(), LexiconError> {
+        let doc = serde_json::from_str(json).map_err(LexiconError::Json)?;
+        self.add(doc)?;
+        Ok(())
+    }
+
+    /// Adds all the `.json` files found in the provided directory (and its
+    /// sub-directories) to the registry.
+    ///
+    /// Returns the number of documents that were loaded.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<usize, LexiconError> {
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).map_err(|err| LexiconError::Io(dir.into(), err))? {
+            let path = entry
+                .map_err(|err| LexiconError::Io(dir.into(), err))?
+                .path();
+
+            if path.is_dir() {
+                count += self.load_dir(&path)?;
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                let json = std::fs::read_to_string(&path)
+                    .map_err(|err| LexiconError::Io(path.as_path().into(), err))?;
+                self.load_json(&json)?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Returns the document with the provided NSID.
+    pub fn get(&self, nsid: &str) -> Option<&LexiconDoc> {
+        self.docs.get(nsid)
+    }
+
+    /// Returns the record type defined by the provided collection NSID.
+    pub fn record(&self, collection: &str) -> Option<&LexRecord> {
+        match self.get(collection)?.defs.get("main")? {
+            LexType::Record(record) => Some(record),
+            _ => None,
+        }
+    }
+
+    /// Resolves a reference found in the document `context`.
+    ///
+    /// References take the form `#name` (a definition of the same document),
+    /// `nsid#name`, or `nsid` (the `main` definition of another document).
+    ///
+    /// On success, the NSID of the document holding the definition is
+    /// returned alongside the definition itself.
+    pub fn resolve<'a>(&'a self, context: &'a str, reference: &'a str) -> Option<Resolved<'a>> {
+        let (nsid, name) = split_ref(context, reference);
+        let def = self.get(nsid)?.defs.get(name)?;
+        Some(Resolved { nsid, name, def })
+    }
+}
+
+/// A definition resolved by [`LexiconRegistry::resolve`].
+#[derive(Debug, Clone, Copy)]
+pub struct Resolved<'a> {
+    /// The NSID of the document holding the definition.
+    pub nsid: &'a str,
+    /// The name of the definition within its document.
+    pub name: &'a str,
+    /// The definition.
+    pub def: &'a LexType,
+}
+
+/// Splits a reference into the NSID of its document and the name of the
+/// definition.
+fn split_ref<'a>(context: &'a str, reference: &'a str) -> (&'a str, &'a str) {
+    match reference.split_once('#') {
+        Some(("", name)) => (context, name),
+        Some((nsid, name)) => (nsid, name),
+        None => (reference, "main"),
+    }
+}
+
+/// Returns the fully-qualified form of a reference found in the document
+/// `context`, as it would appear in the `$type` field of an object.
+///
+/// References to `main` definitions are written without their fragment.
+pub fn qualify_ref(context: &str, reference: &str) -> String {
+    match split_ref(context, reference) {
+        (nsid, "main") => nsid.to_owned(),
+        (nsid, name) => format!("{nsid}#{name}"),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn bundled_refs_resolve() {
+    use super::LexObject;
+
+    fn check(registry: &LexiconRegistry, nsid: &str, ty: &LexType) {
+        let check_object = |object: &LexObject| {
+            for ty in object.properties.values() {
+                check(registry, nsid, ty);
+            }
+        };
+
+        match ty {
+            LexType::Record(record) => check_object(&record.record),
+            LexType::Object(object) => check_object(object),
+            LexType::Array(array) => check(registry, nsid, &array.items),
+            LexType::Ref(r) => assert!(registry.resolve(nsid, &r.ref_).is_some(), "{}", r.ref_),
+            LexType::Union(u) => {
+                for r in &u.refs {
+                    assert!(registry.resolve(nsid, r).is_some(), "{r}");
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let registry = LexiconRegistry::with_bundled();
+    assert!(registry.record("app.bsky.feed.post").is_some());
+    assert!(registry.record("com.atproto.repo.strongRef").is_none());
+
+    for doc in registry.docs.values() {
+        for def in doc.defs.values() {
+            check(&registry, &doc.id, def);
+        }
+    }
+
+    assert_eq!(
+        qualify_ref("app.bsky.feed.post", "#replyRef"),
+        "app.bsky.feed.post#replyRef"
+    );
+    assert_eq!(
+        qualify_ref("app.bsky.feed.post", "app.bsky.embed.images#main"),
+        "app.bsky.embed.images"
+    );
+}
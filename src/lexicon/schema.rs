@@ -0,0 +1,359 @@
+//! The types describing a Lexicon document, as defined in the
+//! [Lexicon specification](https://atproto.com/specs/lexicon).
+//!
+//! Those types are only meant to be deserialized from the JSON
+//! representation of Lexicon documents. Fields that are not relevant to the
+//! server are ignored.
+
+use {serde::Deserialize, std::collections::BTreeMap};
+
+/// A Lexicon document.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexiconDoc {
+    /// The version of the Lexicon language used by the document.
+    pub lexicon: u32,
+    /// The NSID of the document.
+    pub id: String,
+    /// A description of the document.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The definitions of the document, indexed by name.
+    ///
+    /// The `main` definition is the one referred to by the NSID of the
+    /// document alone.
+    pub defs: BTreeMap<String, LexType>,
+}
+
+/// A type definition.
+///
+/// Primary types ([`Record`](LexType::Record), [`Query`](LexType::Query),
+/// [`Procedure`](LexType::Procedure) and
+/// [`Subscription`](LexType::Subscription)) may only appear as the `main`
+/// definition of a document.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum LexType {
+    /// A record type, stored in repositories.
+    Record(LexRecord),
+    /// An XRPC query (HTTP GET).
+    Query(LexXrpc),
+    /// An XRPC procedure (HTTP POST).
+    Procedure(LexXrpc),
+    /// An event stream (WebSocket).
+    Subscription(LexSubscription),
+    /// An object with named properties.
+    Object(LexObject),
+    /// A list of values.
+    Array(LexArray),
+    /// A UTF-8 string.
+    String(LexString),
+    /// A signed integer.
+    Integer(LexInteger),
+    /// A boolean.
+    Boolean(LexBoolean),
+    /// A byte string.
+    Bytes(LexBytes),
+    /// A CID link.
+    CidLink(LexCidLink),
+    /// A reference to a blob.
+    Blob(LexBlob),
+    /// A reference to another definition.
+    Ref(LexRef),
+    /// One of several definitions, told apart by their `$type` field.
+    Union(LexUnion),
+    /// Any object.
+    Unknown(LexUnknown),
+    /// A symbolic value that has no data representation of its own.
+    Token(LexToken),
+}
+
+/// A record type.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexRecord {
+    /// A description of the record type.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The kind of record key expected for this record type (`tid`, `nsid`,
+    /// `any` or `literal:<value>`).
+    #[serde(default)]
+    pub key: Option<String>,
+    /// The schema of the record.
+    pub record: LexObject,
+}
+
+/// An XRPC query or procedure.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexXrpc {
+    /// A description of the method.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The query parameters accepted by the method.
+    #[serde(default)]
+    pub parameters: Option<LexParams>,
+    /// The body expected by the method (procedures only).
+    #[serde(default)]
+    pub input: Option<LexBody>,
+    /// The body returned by the method.
+    #[serde(default)]
+    pub output: Option<LexBody>,
+    /// The errors that the method might return.
+    #[serde(default)]
+    pub errors: Vec<LexXrpcError>,
+}
+
+/// An XRPC subscription.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexSubscription {
+    /// A description of the subscription.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The query parameters accepted by the subscription.
+    #[serde(default)]
+    pub parameters: Option<LexParams>,
+    /// The messages sent by the subscription.
+    #[serde(default)]
+    pub message: Option<LexSubscriptionMessage>,
+    /// The errors that the subscription might return.
+    #[serde(default)]
+    pub errors: Vec<LexXrpcError>,
+}
+
+/// The messages sent by an XRPC subscription.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexSubscriptionMessage {
+    /// A description of the messages.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The schema of the messages.
+    #[serde(default)]
+    pub schema: Option<Box<LexType>>,
+}
+
+/// The query parameters of an XRPC method.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexParams {
+    /// The names of the parameters that must be provided.
+    #[serde(default)]
+    pub required: Vec<String>,
+    /// The parameters, indexed by name.
+    ///
+    /// Parameters are either booleans, integers, strings, or arrays of
+    /// those.
+    #[serde(default)]
+    pub properties: BTreeMap<String, LexType>,
+}
+
+/// The body of an XRPC request or response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexBody {
+    /// A description of the body.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The MIME type of the body.
+    pub encoding: String,
+    /// The schema of the body, when it is JSON.
+    #[serde(default)]
+    pub schema: Option<Box<LexType>>,
+}
+
+/// An error that an XRPC method might return.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexXrpcError {
+    /// The name of the error.
+    pub name: String,
+    /// A description of the error.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// An object type.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexObject {
+    /// A description of the object.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The names of the properties that must be present.
+    #[serde(default)]
+    pub required: Vec<String>,
+    /// The names of the properties that may be `null`.
+    #[serde(default)]
+    pub nullable: Vec<String>,
+    /// The properties of the object, indexed by name.
+    #[serde(default)]
+    pub properties: BTreeMap<String, LexType>,
+}
+
+/// An array type.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LexArray {
+    /// A description of the array.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The type of the elements of the array.
+    pub items: Box<LexType>,
+    /// The minimum number of elements.
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    /// The maximum number of elements.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+/// A string type.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LexString {
+    /// A description of the string.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The format of the string, such as `did` or `datetime`.
+    #[serde(default)]
+    pub format: Option<String>,
+    /// The minimum length of the string, in UTF-8 bytes.
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    /// The maximum length of the string, in UTF-8 bytes.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// The minimum length of the string, in grapheme clusters.
+    #[serde(default)]
+    pub min_graphemes: Option<usize>,
+    /// The maximum length of the string, in grapheme clusters.
+    #[serde(default)]
+    pub max_graphemes: Option<usize>,
+    /// The values that the string is expected to take, without this being
+    /// enforced.
+    #[serde(default)]
+    pub known_values: Vec<String>,
+    /// The only values that the string may take.
+    #[serde(default, rename = "enum")]
+    pub enum_: Option<Vec<String>>,
+    /// The only value that the string may take.
+    #[serde(default, rename = "const")]
+    pub const_: Option<String>,
+    /// The default value of the string.
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// An integer type.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexInteger {
+    /// A description of the integer.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The minimum value of the integer (inclusive).
+    #[serde(default)]
+    pub minimum: Option<i64>,
+    /// The maximum value of the integer (inclusive).
+    #[serde(default)]
+    pub maximum: Option<i64>,
+    /// The only values that the integer may take.
+    #[serde(default, rename = "enum")]
+    pub enum_: Option<Vec<i64>>,
+    /// The only value that the integer may take.
+    #[serde(default, rename = "const")]
+    pub const_: Option<i64>,
+    /// The default value of the integer.
+    #[serde(default)]
+    pub default: Option<i64>,
+}
+
+/// A boolean type.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexBoolean {
+    /// A description of the boolean.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The only value that the boolean may take.
+    #[serde(default, rename = "const")]
+    pub const_: Option<bool>,
+    /// The default value of the boolean.
+    #[serde(default)]
+    pub default: Option<bool>,
+}
+
+/// A byte string type.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LexBytes {
+    /// A description of the byte string.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The minimum length of the byte string.
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    /// The maximum length of the byte string.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+/// A CID link type.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexCidLink {
+    /// A description of the link.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A blob type.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LexBlob {
+    /// A description of the blob.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The MIME types accepted for the blob, such as `image/png` or
+    /// `image/*`.
+    ///
+    /// When `None`, any MIME type is accepted.
+    #[serde(default)]
+    pub accept: Option<Vec<String>>,
+    /// The maximum size of the blob, in bytes.
+    #[serde(default)]
+    pub max_size: Option<u64>,
+}
+
+/// A reference to another definition.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexRef {
+    /// A description of the reference.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The referenced definition, as `nsid`, `nsid#name` or `#name`.
+    #[serde(rename = "ref")]
+    pub ref_: String,
+}
+
+/// A union of several definitions.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexUnion {
+    /// A description of the union.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The definitions that are part of the union, as `nsid`, `nsid#name`
+    /// or `#name`.
+    pub refs: Vec<String>,
+    /// Whether the union only accepts the listed definitions.
+    ///
+    /// Open unions also accept objects with any other `$type`.
+    #[serde(default)]
+    pub closed: bool,
+}
+
+/// A type accepting any object.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexUnknown {
+    /// A description of the value.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A token type.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LexToken {
+    /// A description of the token.
+    #[serde(default)]
+    pub description: Option<String>,
+}
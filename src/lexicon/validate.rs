@@ -0,0 +1,674 @@
+use {
+    super::{
+        qualify_ref, LexArray, LexBlob, LexBytes, LexInteger, LexObject, LexString, LexType,
+        LexUnion, LexiconRegistry,
+    },
+    crate::{
+        api::xrpc::model::{validate_nsid, validate_tid, StringFormat},
+        dag_cbor::Value,
+    },
+    serde::Serialize,
+    unicode_segmentation::UnicodeSegmentation,
+};
+
+/// The maximum number of nested definitions that are followed while
+/// validating a value.
+///
+/// This protects against reference cycles in the schemas.
+const MAX_DEPTH: usize = 128;
+
+/// Whether a record was validated against its Lexicon schema.
+///
+/// This is returned as the `validationStatus` of the methods writing records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationStatus {
+    /// The record was found valid.
+    Valid,
+    /// The record was not validated, either because validation was skipped
+    /// or because its Lexicon is not known by the server.
+    Unknown,
+}
+
+impl ValidationStatus {
+    /// Returns the status as it appears in responses.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Valid => "valid",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// An error that might occur when validating a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// Validation was required, but the Lexicon of the collection is not
+    /// known by the server.
+    UnknownLexicon(String),
+    /// The record key is not of the kind expected by the schema.
+    InvalidRecordKey {
+        /// The record key.
+        rkey: String,
+        /// The kind of record key expected (`tid`, `nsid` or
+        /// `literal:<value>`).
+        expected: String,
+    },
+    /// The record does not match its schema.
+    Invalid {
+        /// The path to the faulty value, such as `Record/embed/images/0`.
+        path: String,
+        /// What is wrong with the value.
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownLexicon(nsid) => write!(f, "Lexicon not found: `{nsid}`"),
+            Self::InvalidRecordKey { rkey, expected } => match expected.as_str() {
+                "tid" => write!(f, "Record key `{rkey}` must be a TID"),
+                "nsid" => write!(f, "Record key `{rkey}` must be an NSID"),
+                _ => match expected.strip_prefix("literal:") {
+                    Some(literal) => write!(f, "Record key `{rkey}` must be `{literal}`"),
+                    None => write!(f, "Record key `{rkey}` must be of kind `{expected}`"),
+                },
+            },
+            Self::Invalid { path, message } => write!(f, "{path} {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl LexiconRegistry {
+    /// Validates a record meant to be stored in the provided collection,
+    /// under the record key `rkey`.
+    ///
+    /// `validate` is the `validate` parameter of the methods writing
+    /// records:
+    ///
+    /// - `Some(true)` requires the record to be validated, failing if the
+    ///   Lexicon of the collection is not known.
+    ///
+    /// - `None` validates the record only if its Lexicon is known.
+    ///
+    /// - `Some(false)` skips validation.
+    ///
+    /// In all cases, the record must be an object whose `$type` is the
+    /// collection. When the record is validated, its key must also be of the
+    /// kind declared by the schema.
+    pub fn validate_record(
+        &self,
+        collection: &str,
+        rkey: &str,
+        record: &Value,
+        validate: Option<bool>,
+    ) -> Result<ValidationStatus, ValidationError> {
+        let mut path = String::from("Record");
+
+        if record.as_map().is_none() {
+            return Err(invalid(&path, "must be an object"));
+        }
+        match record.get("$type").and_then(Value::as_str) {
+            Some(ty) if ty == collection => (),
+            Some(ty) => {
+                path.push_str("/$type");
+                return Err(invalid(
+                    &path,
+                    format!("must be `{collection}`, got `{ty}`"),
+                ));
+            }
+            None => return Err(invalid(&path, "must have a `$type` property")),
+        }
+
+        if validate == Some(false) {
+            return Ok(ValidationStatus::Unknown);
+        }
+
+        let Some(schema) = self.record(collection) else {
+            return match validate {
+                Some(true) => Err(ValidationError::UnknownLexicon(collection.into())),
+                _ => Ok(ValidationStatus::Unknown),
+            };
+        };
+
+        if let Some(expected) = &schema.key {
+            if !record_key_matches(expected, rkey) {
+                return Err(ValidationError::InvalidRecordKey {
+                    rkey: rkey.into(),
+                    expected: expected.clone(),
+                });
+            }
+        }
+
+        let validator = Validator { registry: self };
+        validator.object(collection, &schema.record, record, &mut path, 0)?;
+
+        Ok(ValidationStatus::Valid)
+    }
+}
+
+/// Creates a [`ValidationError::Invalid`] error.
+fn invalid(path: &str, message: impl Into<String>) -> ValidationError {
+    ValidationError::Invalid {
+        path: path.into(),
+        message: message.into(),
+    }
+}
+
+/// Validates values against the definitions of a [`LexiconRegistry`].
+///
+/// The `context` argument of the methods is the NSID of the document the
+/// definition belongs to, used to resolve local references.
+struct Validator<'a> {
+    registry: &'a LexiconRegistry,
+}
+
+impl Validator<'_> {
+    /// Validates a value against any field type.
+    fn value(
+        &self,
+        context: &str,
+        ty: &LexType,
+        value: &Value,
+        path: &mut String,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        if depth > MAX_DEPTH {
+            return Err(invalid(path, "is too deeply nested"));
+        }
+
+        match ty {
+            LexType::Object(object) => self.object(context, object, value, path, depth),
+            LexType::Array(array) => self.array(context, array, value, path, depth),
+            LexType::String(string) => validate_string(string, value, path),
+            LexType::Integer(integer) => validate_integer(integer, value, path),
+            LexType::Boolean(boolean) => match value {
+                Value::Bool(b) if boolean.const_.is_some_and(|c| c != *b) => {
+                    Err(invalid(path, format!("must be {}", !b)))
+                }
+                Value::Bool(_) => Ok(()),
+                _ => Err(invalid(path, "must be a boolean")),
+            },
+            LexType::Bytes(bytes) => validate_bytes(bytes, value, path),
+            LexType::CidLink(_) => match value {
+                Value::Link(_) => Ok(()),
+                _ => Err(invalid(path, "must be a CID link")),
+            },
+            LexType::Blob(blob) => validate_blob(blob, value, path),
+            LexType::Ref(r) => {
+                let Some(resolved) = self.registry.resolve(context, &r.ref_) else {
+                    return Err(invalid(
+                        path,
+                        format!("refers to unknown definition `{}`", r.ref_),
+                    ));
+                };
+                self.definition(resolved.nsid, resolved.def, value, path, depth + 1)
+            }
+            LexType::Union(union) => self.union(context, union, value, path, depth),
+            LexType::Unknown(_) => match value {
+                Value::Map(_) => Ok(()),
+                _ => Err(invalid(path, "must be an object")),
+            },
+            LexType::Record(_)
+            | LexType::Query(_)
+            | LexType::Procedure(_)
+            | LexType::Subscription(_)
+            | LexType::Token(_) => Err(invalid(path, "has an invalid schema")),
+        }
+    }
+
+    /// Validates a value against a definition that was referred to.
+    ///
+    /// Unlike fields, references may point to records and tokens.
+    fn definition(
+        &self,
+        context: &str,
+        def: &LexType,
+        value: &Value,
+        path: &mut String,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        match def {
+            LexType::Record(record) => self.object(context, &record.record, value, path, depth),
+            LexType::Token(_) => match value {
+                Value::String(_) => Ok(()),
+                _ => Err(invalid(path, "must be a string")),
+            },
+            _ => self.value(context, def, value, path, depth),
+        }
+    }
+
+    /// Validates an object.
+    fn object(
+        &self,
+        context: &str,
+        object: &LexObject,
+        value: &Value,
+        path: &mut String,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        let Value::Map(map) = value else {
+            return Err(invalid(path, "must be an object"));
+        };
+
+        for name in &object.required {
+            if !map.contains_key(name) {
+                return Err(invalid(path, format!("must have the property `{name}`")));
+            }
+        }
+
+        for (name, ty) in &object.properties {
+            let Some(value) = map.get(name) else {
+                continue;
+            };
+
+            let len = path.len();
+            path.push('/');
+            path.push_str(name);
+
+            if *value != Value::Null || !object.nullable.contains(name) {
+                self.value(context, ty, value, path, depth + 1)?;
+            }
+
+            path.truncate(len);
+        }
+
+        Ok(())
+    }
+
+    /// Validates an array.
+    fn array(
+        &self,
+        context: &str,
+        array: &LexArray,
+        value: &Value,
+        path: &mut String,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        let Value::List(list) = value else {
+            return Err(invalid(path, "must be an array"));
+        };
+
+        if let Some(min) = array.min_length.filter(|&min| list.len() < min) {
+            return Err(invalid(
+                path,
+                format!("must not have fewer than {min} elements"),
+            ));
+        }
+        if let Some(max) = array.max_length.filter(|&max| list.len() > max) {
+            return Err(invalid(
+                path,
+                format!("must not have more than {max} elements"),
+            ));
+        }
+
+        for (i, item) in list.iter().enumerate() {
+            let len = path.len();
+            path.push('/');
+            path.push_str(&i.to_string());
+            self.value(context, &array.items, item, path, depth + 1)?;
+            path.truncate(len);
+        }
+
+        Ok(())
+    }
+
+    /// Validates an object against one of the definitions of a union.
+    fn union(
+        &self,
+        context: &str,
+        union: &LexUnion,
+        value: &Value,
+        path: &mut String,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        if value.as_map().is_none() {
+            return Err(invalid(path, "must be an object"));
+        }
+        let Some(ty) = value.get("$type").and_then(Value::as_str) else {
+            return Err(invalid(path, "must have a `$type` property"));
+        };
+
+        // `$type` may spell out the `#main` fragment.
+        let ty = ty.strip_suffix("#main").unwrap_or(ty);
+
+        let Some(r) = union.refs.iter().find(|r| qualify_ref(context, r) == ty) else {
+            if union.closed {
+                return Err(invalid(path, format!("has an unexpected `$type`: `{ty}`")));
+            }
+            return Ok(());
+        };
+
+        let Some(resolved) = self.registry.resolve(context, r) else {
+            return Err(invalid(path, format!("refers to unknown definition `{r}`")));
+        };
+        self.definition(resolved.nsid, resolved.def, value, path, depth + 1)
+    }
+}
+
+/// Validates a string.
+fn validate_string(string: &LexString, value: &Value, path: &str) -> Result<(), ValidationError> {
+    let Value::String(s) = value else {
+        return Err(invalid(path, "must be a string"));
+    };
+
+    if let Some(c) = string.const_.as_ref().filter(|&c| c != s) {
+        return Err(invalid(path, format!("must be `{c}`")));
+    }
+    if let Some(values) = string.enum_.as_ref().filter(|values| !values.contains(s)) {
+        return Err(invalid(path, format!("must be one of {values:?}")));
+    }
+
+    if let Some(min) = string.min_length.filter(|&min| s.len() < min) {
+        return Err(invalid(
+            path,
+            format!("must not be shorter than {min} bytes"),
+        ));
+    }
+    if let Some(max) = string.max_length.filter(|&max| s.len() > max) {
+        return Err(invalid(
+            path,
+            format!("must not be longer than {max} bytes"),
+        ));
+    }
+
+    // A string never has more graphemes than bytes, which avoids counting
+    // them in most cases.
+    if let Some(min) = string.min_graphemes {
+        if s.graphemes(true).count() < min {
+            return Err(invalid(
+                path,
+                format!("must not be shorter than {min} graphemes"),
+            ));
+        }
+    }
+    if let Some(max) = string.max_graphemes {
+        if s.len() > max && s.graphemes(true).count() > max {
+            return Err(invalid(
+                path,
+                format!("must not be longer than {max} graphemes"),
+            ));
+        }
+    }
+
+    // Unknown formats are not enforced, so that documents using newer
+    // formats can still be loaded.
+    if let Some(format) = string.format.as_deref().and_then(StringFormat::from_name) {
+        if !format.validate(s) {
+            return Err(invalid(path, format!("must be a valid {}", format.name())));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates a byte string.
+fn validate_bytes(bytes: &LexBytes, value: &Value, path: &str) -> Result<(), ValidationError> {
+    let Value::Bytes(b) = value else {
+        return Err(invalid(path, "must be a byte string"));
+    };
+
+    if let Some(min) = bytes.min_length.filter(|&min| b.len() < min) {
+        return Err(invalid(
+            path,
+            format!("must not be smaller than {min} bytes"),
+        ));
+    }
+    if let Some(max) = bytes.max_length.filter(|&max| b.len() > max) {
+        return Err(invalid(
+            path,
+            format!("must not be larger than {max} bytes"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates an integer.
+fn validate_integer(
+    integer: &LexInteger,
+    value: &Value,
+    path: &str,
+) -> Result<(), ValidationError> {
+    let Value::Integer(n) = *value else {
+        return Err(invalid(path, "must be an integer"));
+    };
+
+    if let Some(c) = integer.const_.filter(|&c| c != n) {
+        return Err(invalid(path, format!("must be {c}")));
+    }
+    if let Some(values) = integer.enum_.as_ref().filter(|values| !values.contains(&n)) {
+        return Err(invalid(path, format!("must be one of {values:?}")));
+    }
+    if let Some(min) = integer.minimum.filter(|&min| n < min) {
+        return Err(invalid(path, format!("can not be less than {min}")));
+    }
+    if let Some(max) = integer.maximum.filter(|&max| n > max) {
+        return Err(invalid(path, format!("can not be greater than {max}")));
+    }
+
+    Ok(())
+}
+
+/// Validates a blob reference.
+///
+/// Only the current format (`{"$type": "blob", "ref": ..., "mimeType": ...,
+/// "size": ...}`) is accepted. The legacy format lacks the size of the blob
+/// and must not be used by new records.
+fn validate_blob(blob: &LexBlob, value: &Value, path: &str) -> Result<(), ValidationError> {
+    if value.as_map().is_none() || value.get("$type").and_then(Value::as_str) != Some("blob") {
+        return Err(invalid(path, "must be a blob reference"));
+    }
+    let Some(Value::Link(_)) = value.get("ref") else {
+        return Err(invalid(path, "must have a `ref` link"));
+    };
+    let Some(mime_type) = value.get("mimeType").and_then(Value::as_str) else {
+        return Err(invalid(path, "must have a `mimeType` string"));
+    };
+    let Some(&Value::Integer(size)) = value.get("size") else {
+        return Err(invalid(path, "must have a `size` integer"));
+    };
+
+    if let Some(accept) = &blob.accept {
+        if !accept
+            .iter()
+            .any(|pattern| mime_type_matches(pattern, mime_type))
+        {
+            return Err(invalid(
+                path,
+                format!("has an unaccepted MIME type `{mime_type}`, expected one of {accept:?}"),
+            ));
+        }
+    }
+
+    if size < 0 {
+        return Err(invalid(path, "has a negative size"));
+    }
+    if let Some(max) = blob.max_size.filter(|&max| size as u64 > max) {
+        return Err(invalid(
+            path,
+            format!("must not be larger than {max} bytes"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns whether `mime_type` matches the provided `accept` pattern, such
+/// as `image/png`, `image/*` or `*/*`.
+fn mime_type_matches(pattern: &str, mime_type: &str) -> bool {
+    if pattern == "*/*" {
+        return true;
+    }
+
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime_type
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/')),
+        None => pattern == mime_type,
+    }
+}
+
+/// Returns whether `rkey` is of the kind of record key declared by a record
+/// schema.
+///
+/// Kinds that are not known are not checked: the general syntax of record
+/// keys is already enforced by [`RecordKey`](crate::api::xrpc::model::RecordKey).
+fn record_key_matches(expected: &str, rkey: &str) -> bool {
+    match expected {
+        "tid" => validate_tid(rkey.as_bytes()),
+        "nsid" => validate_nsid(rkey.as_bytes()),
+        _ => match expected.strip_prefix("literal:") {
+            Some(literal) => rkey == literal,
+            None => true,
+        },
+    }
+}
+
+#[cfg(test)]
+fn json(s: &str) -> Value {
+    serde_json::from_str(s).unwrap()
+}
+
+#[cfg(test)]
+#[test]
+fn validate_posts() {
+    let registry = LexiconRegistry::with_bundled();
+    let check = |record: &str| {
+        registry.validate_record("app.bsky.feed.post", "3jui7kd54zh2y", &json(record), None)
+    };
+
+    assert_eq!(
+        check(
+            r#"{"$type":"app.bsky.feed.post","text":"hello","createdAt":"2024-12-14T12:00:00Z"}"#
+        ),
+        Ok(ValidationStatus::Valid)
+    );
+    assert_eq!(
+        check(
+            r#"{"$type":"app.bsky.feed.post","text":"hi","createdAt":"2024-12-14T12:00:00Z",
+            "langs":["en"],
+            "embed":{"$type":"app.bsky.embed.images","images":[{"alt":"",
+                "image":{"$type":"blob","ref":{"$link":"bafkreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm"},
+                "mimeType":"image/png","size":1000}}]},
+            "labels":{"$type":"com.atproto.label.defs#selfLabels","values":[{"val":"nudity"}]}}"#
+        ),
+        Ok(ValidationStatus::Valid)
+    );
+
+    let err = check(r#"{"$type":"app.bsky.feed.post","createdAt":"2024-12-14T12:00:00Z"}"#);
+    assert!(matches!(err, Err(ValidationError::Invalid { path, .. }) if path == "Record"));
+
+    let long_text = format!(
+        r#"{{"$type":"app.bsky.feed.post","text":"{}","createdAt":"2024-12-14T12:00:00Z"}}"#,
+        "é".repeat(301)
+    );
+    let err = check(&long_text);
+    assert!(matches!(err, Err(ValidationError::Invalid { path, .. }) if path == "Record/text"));
+
+    let err = check(r#"{"$type":"app.bsky.feed.post","text":"","createdAt":"yesterday"}"#);
+    assert!(
+        matches!(err, Err(ValidationError::Invalid { path, .. }) if path == "Record/createdAt")
+    );
+
+    let err = check(
+        r#"{"$type":"app.bsky.feed.post","text":"","createdAt":"2024-12-14T12:00:00Z",
+        "embed":{"$type":"app.bsky.embed.images","images":[{"alt":"",
+            "image":{"$type":"blob","ref":{"$link":"bafkreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm"},
+            "mimeType":"text/plain","size":1000}}]}}"#,
+    );
+    assert!(
+        matches!(err, Err(ValidationError::Invalid { path, .. }) if path == "Record/embed/images/0/image")
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn validate_modes() {
+    let registry = LexiconRegistry::with_bundled();
+    let unknown = json(r#"{"$type":"com.example.thing","anything":1}"#);
+    let invalid_like = json(r#"{"$type":"app.bsky.feed.like"}"#);
+
+    assert_eq!(
+        registry.validate_record("com.example.thing", "3jui7kd54zh2y", &unknown, None),
+        Ok(ValidationStatus::Unknown)
+    );
+    assert_eq!(
+        registry.validate_record("com.example.thing", "3jui7kd54zh2y", &unknown, Some(false)),
+        Ok(ValidationStatus::Unknown)
+    );
+    assert_eq!(
+        registry.validate_record("com.example.thing", "3jui7kd54zh2y", &unknown, Some(true)),
+        Err(ValidationError::UnknownLexicon("com.example.thing".into()))
+    );
+
+    assert!(registry
+        .validate_record("app.bsky.feed.like", "3jui7kd54zh2y", &invalid_like, None)
+        .is_err());
+    assert_eq!(
+        registry.validate_record(
+            "app.bsky.feed.like",
+            "3jui7kd54zh2y",
+            &invalid_like,
+            Some(false)
+        ),
+        Ok(ValidationStatus::Unknown)
+    );
+
+    // The `$type` is always checked.
+    assert!(registry
+        .validate_record(
+            "app.bsky.feed.post",
+            "3jui7kd54zh2y",
+            &invalid_like,
+            Some(false)
+        )
+        .is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn validate_record_keys() {
+    let registry = LexiconRegistry::with_bundled();
+    let post =
+        json(r#"{"$type":"app.bsky.feed.post","text":"","createdAt":"2024-12-14T12:00:00Z"}"#);
+    let profile = json(r#"{"$type":"app.bsky.actor.profile"}"#);
+
+    assert!(registry
+        .validate_record("app.bsky.feed.post", "3jui7kd54zh2y", &post, None)
+        .is_ok());
+    assert!(matches!(
+        registry.validate_record("app.bsky.feed.post", "foo", &post, None),
+        Err(ValidationError::InvalidRecordKey { expected, .. }) if expected == "tid"
+    ));
+    assert!(registry
+        .validate_record("app.bsky.actor.profile", "self", &profile, Some(true))
+        .is_ok());
+    assert!(matches!(
+        registry.validate_record("app.bsky.actor.profile", "foo", &profile, Some(true)),
+        Err(ValidationError::InvalidRecordKey { expected, .. }) if expected == "literal:self"
+    ));
+
+    // Skipping validation skips the check of the record key.
+    assert_eq!(
+        registry.validate_record("app.bsky.feed.post", "foo", &post, Some(false)),
+        Ok(ValidationStatus::Unknown)
+    );
+
+    assert!(record_key_matches("nsid", "app.bsky.feed.post"));
+    assert!(!record_key_matches("nsid", "3jui7kd54zh2y"));
+    assert!(record_key_matches("any", "foo"));
+}
+
+#[cfg(test)]
+#[test]
+fn mime_types() {
+    assert!(mime_type_matches("*/*", "video/mp4"));
+    assert!(mime_type_matches("image/*", "image/png"));
+    assert!(mime_type_matches("image/png", "image/png"));
+    assert!(!mime_type_matches("image/*", "imagery/png"));
+    assert!(!mime_type_matches("image/png", "image/jpeg"));
+}
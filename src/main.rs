@@ -1,14 +1,21 @@
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports))]
 
 use {
+    http_body_util::combinators::BoxBody,
+    hyper::body::Incoming,
     std::{convert::Infallible, ffi::OsString, net::SocketAddr, str::FromStr, time::Duration},
     tokio::net::TcpStream,
     tracing::{error, info, trace, warn},
 };
 
 mod api;
+mod blob;
+mod dag_cbor;
 mod global;
+mod identity;
+mod lexicon;
 mod panic;
+mod repo;
 
 /// The glorious entry point.
 fn main() {
@@ -43,6 +50,7 @@ fn main() {
 async fn main_async() {
     tokio::select!(
         _ = run_server() => (),
+        _ = self::blob::run_gc() => (),
         _ = wait_for_shutdown_signal() => (),
     );
 }
@@ -133,7 +141,8 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr) {
     let executor = hyper_util::rt::TokioExecutor::new();
     let stream = hyper_util::rt::TokioIo::new(stream);
 
-    let service = hyper::service::service_fn(move |mut req| async move {
+    let service = hyper::service::service_fn(move |req: hyper::Request<Incoming>| async move {
+        let mut req = req.map(BoxBody::new);
         req.extensions_mut().insert(addr);
         Ok::<_, Infallible>(self::api::handle_request(&mut req).await)
     });
@@ -0,0 +1,112 @@
+use {
+    crate::{
+        api::xrpc::model::{Cid, Codec},
+        dag_cbor::Value,
+    },
+    std::{collections::BTreeMap, future::Future},
+};
+
+/// An error that might occur when accessing a [`BlockStore`].
+#[derive(Debug)]
+pub struct BlockStoreError(pub Box<dyn std::error::Error + Send + Sync>);
+
+impl std::fmt::Display for BlockStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to access the block store: {}", self.0)
+    }
+}
+
+impl std::error::Error for BlockStoreError {}
+
+/// A storage backend for the blocks of a repository, indexed by their CID.
+pub trait BlockStore: Sync {
+    /// Returns the content of the block with the provided CID, or `None` if
+    /// the block is not present in the store.
+    fn get_block(
+        &self,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<Option<Vec<u8>>, BlockStoreError>>;
+}
+
+/// An in-memory collection of blocks.
+///
+/// This is typically used to collect the blocks created by an operation
+/// before they are written to persistent storage.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BlockMap(BTreeMap<Cid, Vec<u8>>);
+
+impl BlockMap {
+    /// Creates a new empty [`BlockMap`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a block with a precomputed CID.
+    #[inline]
+    pub fn insert(&mut self, cid: Cid, bytes: Vec<u8>) {
+        self.0.insert(cid, bytes);
+    }
+
+    /// Encodes the provided value as DAG-CBOR, inserts it, and returns its
+    /// CID.
+    pub fn insert_value(&mut self, value: &Value) -> Cid {
+        let bytes = value.encode();
+        let cid = Cid::compute(Codec::DagCbor, &bytes);
+        self.0.insert(cid, bytes);
+        cid
+    }
+
+    /// Returns the content of the block with the provided CID.
+    #[inline]
+    pub fn get(&self, cid: &Cid) -> Option<&[u8]> {
+        self.0.get(cid).map(Vec::as_slice)
+    }
+
+    /// Returns whether the map contains a block with the provided CID.
+    #[inline]
+    pub fn contains(&self, cid: &Cid) -> bool {
+        self.0.contains_key(cid)
+    }
+
+    /// Returns the number of blocks in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the map is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Moves all the blocks of `other` into this map.
+    pub fn extend(&mut self, other: BlockMap) {
+        self.0.extend(other.0);
+    }
+
+    /// Returns an iterator over the blocks of the map.
+    pub fn iter(&self) -> impl Iterator<Item = (&Cid, &[u8])> {
+        self.0.iter().map(|(cid, bytes)| (cid, bytes.as_slice()))
+    }
+}
+
+impl IntoIterator for BlockMap {
+    type Item = (Cid, Vec<u8>);
+    type IntoIter = std::collections::btree_map::IntoIter<Cid, Vec<u8>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl BlockStore for BlockMap {
+    fn get_block(
+        &self,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<Option<Vec<u8>>, BlockStoreError>> {
+        std::future::ready(Ok(self.0.get(cid).cloned()))
+    }
+}
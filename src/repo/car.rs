@@ -0,0 +1,446 @@
+//! Streaming support for CAR v1 files.
+//!
+//! A CAR file is a DAG-CBOR header listing the root CIDs of the archive,
+//! followed by a sequence of blocks. Both the header and each block are
+//! prefixed by their length, encoded as an unsigned LEB128 varint.
+//!
+//! See the [CAR specification](https://ipld.io/specs/transport/car/carv1/)
+//! for more information.
+
+use {
+    crate::{api::xrpc::model::Cid, dag_cbor},
+    futures::{channel::mpsc, SinkExt},
+    http_body_util::{BodyExt, StreamBody},
+    hyper::body::{Body, Bytes, Frame},
+    serde::{Deserialize, Serialize},
+    std::convert::Infallible,
+};
+
+/// The maximum number of bytes in a LEB128-encoded `u64`.
+const MAX_VARINT_LEN: usize = 10;
+
+/// The number of chunks that a [`CarWriter`] may buffer before waiting for
+/// the body to be polled.
+const WRITER_BUFFER: usize = 16;
+
+/// An error that might occur when reading a CAR file.
+#[derive(Debug)]
+pub enum CarError {
+    /// The body ended in the middle of the file.
+    UnexpectedEof,
+    /// A length prefix is not a valid varint.
+    InvalidVarint,
+    /// The header is malformed.
+    InvalidHeader,
+    /// The file uses a version other than 1.
+    UnsupportedVersion(u64),
+    /// A block has an unsupported or malformed CID.
+    InvalidCid,
+    /// The content of a block does not match its CID.
+    CidMismatch(Cid),
+    /// The file is larger than [`CarLimits::max_size`].
+    TooLarge,
+    /// A block is larger than [`CarLimits::max_block_size`].
+    BlockTooLarge,
+    /// The file contains more than [`CarLimits::max_blocks`] blocks.
+    TooManyBlocks,
+    /// The underlying body failed.
+    Body(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl std::fmt::Display for CarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEof => f.write_str("unexpected end of CAR file"),
+            Self::InvalidVarint => f.write_str("invalid length prefix in CAR file"),
+            Self::InvalidHeader => f.write_str("invalid CAR header"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported CAR version {v}"),
+            Self::InvalidCid => f.write_str("invalid block CID in CAR file"),
+            Self::CidMismatch(cid) => write!(f, "block `{cid}` does not match its CID"),
+            Self::TooLarge => f.write_str("CAR file is too large"),
+            Self::BlockTooLarge => f.write_str("CAR block is too large"),
+            Self::TooManyBlocks => f.write_str("CAR file contains too many blocks"),
+            Self::Body(err) => write!(f, "failed to read CAR file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CarError {}
+
+/// Limits enforced by a [`CarReader`].
+#[derive(Debug, Clone, Copy)]
+pub struct CarLimits {
+    /// The maximum total size of the file, in bytes.
+    pub max_size: u64,
+    /// The maximum size of the header or of a single block (including its
+    /// CID), in bytes.
+    pub max_block_size: usize,
+    /// The maximum number of blocks in the file.
+    pub max_blocks: usize,
+}
+
+impl Default for CarLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 100 * 1024 * 1024,
+            max_block_size: 2 * 1024 * 1024,
+            max_blocks: 1_000_000,
+        }
+    }
+}
+
+/// The header of a CAR file.
+#[derive(Serialize, Deserialize)]
+struct CarHeader {
+    version: u64,
+    roots: Vec<Cid>,
+}
+
+/// Writes `n` as an unsigned LEB128 varint.
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Encodes the header of a CAR file with the provided roots, including its
+/// length prefix.
+pub fn encode_header(roots: &[Cid]) -> Vec<u8> {
+    let header = dag_cbor::to_vec(&CarHeader {
+        version: 1,
+        roots: roots.to_vec(),
+    })
+    .expect("CAR headers are always serializable");
+
+    let mut out = Vec::with_capacity(header.len() + MAX_VARINT_LEN);
+    write_varint(&mut out, header.len() as u64);
+    out.extend_from_slice(&header);
+    out
+}
+
+/// Encodes a block of a CAR file, including its length prefix.
+pub fn encode_block(cid: &Cid, data: &[u8]) -> Vec<u8> {
+    let len = Cid::BYTE_LEN + data.len();
+    let mut out = Vec::with_capacity(len + MAX_VARINT_LEN);
+    write_varint(&mut out, len as u64);
+    out.extend_from_slice(&cid.to_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// The body produced by a [`CarWriter`].
+pub type CarBody = StreamBody<mpsc::Receiver<Result<Frame<Bytes>, Infallible>>>;
+
+/// The error returned when the body of a [`CarWriter`] has been dropped,
+/// usually because the client disconnected.
+#[derive(Debug)]
+pub struct CarWriterClosed;
+
+impl std::fmt::Display for CarWriterClosed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the CAR body has been dropped")
+    }
+}
+
+impl std::error::Error for CarWriterClosed {}
+
+/// Streams a CAR file into a [`CarBody`].
+///
+/// Blocks are sent to the body as they are written, and writing waits when
+/// the body is not consumed fast enough.
+pub struct CarWriter {
+    sender: mpsc::Sender<Result<Frame<Bytes>, Infallible>>,
+}
+
+impl CarWriter {
+    /// Creates a new [`CarWriter`] with the provided roots, and the body it
+    /// writes to.
+    ///
+    /// The header is written when the body is first polled. The file is
+    /// complete once the writer is dropped.
+    pub fn new(roots: &[Cid]) -> (Self, CarBody) {
+        let (mut sender, receiver) = mpsc::channel(WRITER_BUFFER);
+
+        // A new channel always has room for at least one message.
+        sender
+            .try_send(Ok(Frame::data(Bytes::from(encode_header(roots)))))
+            .expect("failed to send the CAR header");
+
+        (Self { sender }, StreamBody::new(receiver))
+    }
+
+    /// Writes a block to the file.
+    pub async fn write_block(&mut self, cid: &Cid, data: &[u8]) -> Result<(), CarWriterClosed> {
+        let chunk = Bytes::from(encode_block(cid, data));
+        self.sender
+            .send(Ok(Frame::data(chunk)))
+            .await
+            .map_err(|_| CarWriterClosed)
+    }
+}
+
+/// Incrementally reads a CAR file from a request body.
+///
+/// The CID of every block is checked against its content.
+pub struct CarReader<B> {
+    body: B,
+    limits: CarLimits,
+    /// The bytes received from the body but not consumed yet start at
+    /// `buf[pos..]`.
+    buf: Vec<u8>,
+    pos: usize,
+    /// The total number of bytes received from the body.
+    received: u64,
+    /// The number of blocks read so far.
+    blocks: usize,
+    roots: Vec<Cid>,
+}
+
+impl<B> CarReader<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    /// Creates a new [`CarReader`], reading the header of the file.
+    pub async fn new(body: B, limits: CarLimits) -> Result<Self, CarError> {
+        let mut reader = Self {
+            body,
+            limits,
+            buf: Vec::new(),
+            pos: 0,
+            received: 0,
+            blocks: 0,
+            roots: Vec::new(),
+        };
+
+        let len = reader.read_length().await?.ok_or(CarError::UnexpectedEof)?;
+        reader.fill(len).await?;
+
+        let header: CarHeader = dag_cbor::from_slice(&reader.buf[reader.pos..reader.pos + len])
+            .map_err(|_| CarError::InvalidHeader)?;
+        reader.pos += len;
+
+        if header.version != 1 {
+            return Err(CarError::UnsupportedVersion(header.version));
+        }
+
+        reader.roots = header.roots;
+        Ok(reader)
+    }
+
+    /// Returns the roots listed in the header of the file.
+    #[inline]
+    pub fn roots(&self) -> &[Cid] {
+        &self.roots
+    }
+
+    /// Reads the next block of the file, or returns `None` if the end of the
+    /// file has been reached.
+    pub async fn next_block(&mut self) -> Result<Option<(Cid, Bytes)>, CarError> {
+        let Some(len) = self.read_length().await? else {
+            return Ok(None);
+        };
+
+        if self.blocks >= self.limits.max_blocks {
+            return Err(CarError::TooManyBlocks);
+        }
+
+        self.fill(len).await?;
+        let section = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        self.blocks += 1;
+
+        let (cid, data) = Cid::read_bytes(section).map_err(|_| CarError::InvalidCid)?;
+        if Cid::compute(cid.codec(), data) != cid {
+            return Err(CarError::CidMismatch(cid));
+        }
+
+        Ok(Some((cid, Bytes::copy_from_slice(data))))
+    }
+
+    /// Reads a length prefix, or returns `None` if the body ended cleanly
+    /// before it.
+    async fn read_length(&mut self) -> Result<Option<usize>, CarError> {
+        let mut n = 0u64;
+        let mut i = 0;
+
+        loop {
+            if self.pos + i >= self.buf.len() && !self.read_chunk().await? {
+                return match i {
+                    0 => Ok(None),
+                    _ => Err(CarError::UnexpectedEof),
+                };
+            }
+
+            let byte = self.buf[self.pos + i];
+            n |= u64::from(byte & 0x7F) << (7 * i);
+            i += 1;
+
+            if byte & 0x80 == 0 {
+                // Varints must be minimally encoded.
+                if byte == 0 && i > 1 {
+                    return Err(CarError::InvalidVarint);
+                }
+                break;
+            }
+            if i >= MAX_VARINT_LEN - 1 {
+                return Err(CarError::InvalidVarint);
+            }
+        }
+
+        self.pos += i;
+
+        match usize::try_from(n) {
+            Ok(0) => Err(CarError::InvalidVarint),
+            Ok(len) if len <= self.limits.max_block_size => Ok(Some(len)),
+            _ => Err(CarError::BlockTooLarge),
+        }
+    }
+
+    /// Makes sure that at least `len` unconsumed bytes are buffered.
+    async fn fill(&mut self, len: usize) -> Result<(), CarError> {
+        while self.buf.len() - self.pos < len {
+            if !self.read_chunk().await? {
+                return Err(CarError::UnexpectedEof);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the next chunk of data from the body into the buffer, returning
+    /// `false` if the body has ended.
+    async fn read_chunk(&mut self) -> Result<bool, CarError> {
+        // Drop the consumed bytes before growing the buffer.
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        loop {
+            let Some(frame) = self.body.frame().await else {
+                return Ok(false);
+            };
+
+            let frame = frame.map_err(|err| CarError::Body(err.into()))?;
+            let Ok(data) = frame.into_data() else {
+                // Trailers are ignored.
+                continue;
+            };
+
+            self.received += data.len() as u64;
+            if self.received > self.limits.max_size {
+                return Err(CarError::TooLarge);
+            }
+
+            if data.is_empty() {
+                continue;
+            }
+
+            self.buf.extend_from_slice(&data);
+            return Ok(true);
+        }
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn car_round_trip() {
+    use {crate::api::xrpc::model::Codec, futures::StreamExt};
+
+    let blocks: Vec<(Cid, Vec<u8>)> = (0..50u32)
+        .map(|i| {
+            let data = vec![i as u8; i as usize * 10];
+            (Cid::compute(Codec::Raw, &data), data)
+        })
+        .collect();
+
+    let (mut writer, body) = CarWriter::new(&[blocks[0].0]);
+    let produce = async move {
+        for (cid, data) in &blocks {
+            writer.write_block(cid, data).await.unwrap();
+        }
+        blocks
+    };
+    let (blocks, chunks) = futures::join!(produce, body.into_data_stream().collect::<Vec<_>>());
+
+    // Feed the file to the reader in small, irregular chunks.
+    let file: Vec<u8> = chunks.into_iter().flat_map(|c| c.unwrap()).collect();
+    let frames: Vec<Result<Frame<Bytes>, Infallible>> = file
+        .chunks(7)
+        .map(|c| Ok(Frame::data(Bytes::copy_from_slice(c))))
+        .collect();
+    let body = StreamBody::new(futures::stream::iter(frames));
+
+    let mut reader = CarReader::new(body, CarLimits::default()).await.unwrap();
+    assert_eq!(reader.roots(), [blocks[0].0]);
+
+    for (cid, data) in &blocks {
+        let (read_cid, read_data) = reader.next_block().await.unwrap().unwrap();
+        assert_eq!(read_cid, *cid);
+        assert_eq!(read_data, *data);
+    }
+
+    assert!(reader.next_block().await.unwrap().is_none());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn car_limits() {
+    use {crate::api::xrpc::model::Codec, http_body_util::Full};
+
+    let data = [1u8; 100];
+    let cid = Cid::compute(Codec::Raw, &data);
+
+    let mut file = encode_header(&[cid]);
+    for _ in 0..3 {
+        file.extend(encode_block(&cid, &data));
+    }
+
+    let read_all = |limits: CarLimits| {
+        let file = Bytes::from(file.clone());
+        async move {
+            let mut reader = CarReader::new(Full::new(file), limits).await?;
+            while reader.next_block().await?.is_some() {}
+            Ok::<_, CarError>(())
+        }
+    };
+
+    assert!(read_all(CarLimits::default()).await.is_ok());
+
+    let limits = CarLimits {
+        max_blocks: 2,
+        ..CarLimits::default()
+    };
+    assert!(matches!(
+        read_all(limits).await,
+        Err(CarError::TooManyBlocks)
+    ));
+
+    let limits = CarLimits {
+        max_block_size: 64,
+        ..CarLimits::default()
+    };
+    assert!(matches!(
+        read_all(limits).await,
+        Err(CarError::BlockTooLarge)
+    ));
+
+    let limits = CarLimits {
+        max_size: 200,
+        ..CarLimits::default()
+    };
+    assert!(matches!(read_all(limits).await, Err(CarError::TooLarge)));
+
+    // A block whose content does not match its CID.
+    let mut file = encode_header(&[cid]);
+    file.extend(encode_block(&cid, &[2u8; 100]));
+    let mut reader = CarReader::new(Full::new(Bytes::from(file)), CarLimits::default())
+        .await
+        .unwrap();
+    assert!(matches!(
+        reader.next_block().await,
+        Err(CarError::CidMismatch(_))
+    ));
+}
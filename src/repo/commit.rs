@@ -0,0 +1,192 @@
+//! Signed repository commits.
+//!
+//! A commit is the root object of a repository. It points to the root of the
+//! [`Mst`](super::mst::Mst) holding the records of the repository, and is
+//! signed by the repository's [`SigningKey`].
+
+use {
+    super::{verify_signature, BlockMap, SigningKey, SIGNATURE_LEN},
+    crate::{
+        api::xrpc::model::{Cid, Did, Tid},
+        dag_cbor::{self, Value},
+    },
+    k256::ecdsa::VerifyingKey,
+    std::collections::BTreeMap,
+};
+
+/// The version of the commit format produced by this module.
+pub const COMMIT_VERSION: i64 = 3;
+
+/// An error that might occur when decoding a [`Commit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitDecodeError(pub &'static str);
+
+impl std::fmt::Display for CommitDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid commit: {}", self.0)
+    }
+}
+
+impl std::error::Error for CommitDecodeError {}
+
+/// A signed repository commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    /// The DID of the account that owns the repository.
+    pub did: Did,
+    /// The CID of the root node of the repository's MST.
+    pub data: Cid,
+    /// The revision of the repository.
+    ///
+    /// Revisions always increase from one commit to the next.
+    pub rev: Tid,
+    /// The CID of the previous commit.
+    ///
+    /// This is always `None` for v3 commits, but the field must still be
+    /// present in the encoded object.
+    pub prev: Option<Cid>,
+    /// The signature of the commit.
+    pub sig: [u8; SIGNATURE_LEN],
+}
+
+impl Commit {
+    /// Creates a new commit and signs it with the provided key.
+    pub fn sign(did: Did, data: Cid, rev: Tid, key: &SigningKey) -> Self {
+        let mut commit = Self {
+            did,
+            data,
+            rev,
+            prev: None,
+            sig: [0; SIGNATURE_LEN],
+        };
+
+        commit.sig = key.sign(&commit.unsigned_value().encode());
+        commit
+    }
+
+    /// Returns whether the signature of the commit was produced by the
+    /// provided key.
+    pub fn verify(&self, key: &VerifyingKey) -> bool {
+        verify_signature(key, &self.unsigned_value().encode(), &self.sig)
+    }
+
+    /// Returns the commit as a [`Value`], without its signature.
+    fn unsigned_value(&self) -> Value {
+        let mut map = BTreeMap::new();
+        map.insert("did".into(), Value::String(self.did.as_str().into()));
+        map.insert("version".into(), Value::Integer(COMMIT_VERSION));
+        map.insert("data".into(), Value::Link(self.data));
+        map.insert("rev".into(), Value::String(self.rev.to_string()));
+        map.insert("prev".into(), self.prev.map_or(Value::Null, Value::Link));
+        Value::Map(map)
+    }
+
+    /// Returns the commit as a [`Value`].
+    pub fn to_value(&self) -> Value {
+        let mut value = self.unsigned_value();
+        if let Value::Map(map) = &mut value {
+            map.insert("sig".into(), Value::Bytes(self.sig.to_vec()));
+        }
+        value
+    }
+
+    /// Decodes a commit from its DAG-CBOR representation.
+    pub fn decode(bytes: &[u8]) -> Result<Self, CommitDecodeError> {
+        let value: Value =
+            dag_cbor::from_slice(bytes).map_err(|_| CommitDecodeError("not DAG-CBOR"))?;
+        let map = value.as_map().ok_or(CommitDecodeError("not a map"))?;
+
+        match map.get("version") {
+            Some(Value::Integer(COMMIT_VERSION)) => (),
+            _ => return Err(CommitDecodeError("unsupported version")),
+        }
+
+        let did = match map.get("did") {
+            Some(Value::String(did)) => Did::try_from(did.clone().into_boxed_str())
+                .map_err(|_| CommitDecodeError("invalid `did` field"))?,
+            _ => return Err(CommitDecodeError("invalid `did` field")),
+        };
+
+        let Some(Value::Link(data)) = map.get("data") else {
+            return Err(CommitDecodeError("invalid `data` field"));
+        };
+
+        let rev = match map.get("rev") {
+            Some(Value::String(rev)) => rev
+                .parse()
+                .map_err(|_| CommitDecodeError("invalid `rev` field"))?,
+            _ => return Err(CommitDecodeError("invalid `rev` field")),
+        };
+
+        let prev = match map.get("prev") {
+            Some(Value::Null) => None,
+            Some(Value::Link(cid)) => Some(*cid),
+            _ => return Err(CommitDecodeError("invalid `prev` field")),
+        };
+
+        let sig = match map.get("sig") {
+            Some(Value::Bytes(sig)) => sig
+                .as_slice()
+                .try_into()
+                .map_err(|_| CommitDecodeError("invalid `sig` field"))?,
+            _ => return Err(CommitDecodeError("invalid `sig` field")),
+        };
+
+        Ok(Self {
+            did,
+            data: *data,
+            rev,
+            prev,
+            sig,
+        })
+    }
+}
+
+/// The result of a commit, ready to be persisted.
+#[derive(Debug, Clone)]
+pub struct CommitData {
+    /// The CID of the new commit.
+    pub cid: Cid,
+    /// The revision of the new commit.
+    pub rev: Tid,
+    /// The CID of the commit that was current before this one, or `None` if
+    /// this is the first commit of the repository.
+    pub since: Option<Cid>,
+    /// The blocks created by the commit, including the commit itself.
+    pub blocks: BlockMap,
+    /// The records written by the commit, in the order they were written.
+    pub writes: Vec<RecordWrite>,
+}
+
+/// A record created, updated or deleted by a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordWrite {
+    /// The path of the record, in the `<collection>/<rkey>` form.
+    pub path: Box<str>,
+    /// The CID of the new version of the record, or `None` if the record was
+    /// deleted.
+    pub cid: Option<Cid>,
+    /// The blobs referenced by the new version of the record.
+    pub blobs: Vec<Cid>,
+}
+
+#[cfg(test)]
+#[test]
+fn commit_round_trip() {
+    use crate::api::xrpc::model::Codec;
+
+    let key = SigningKey::generate();
+    let did = Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap();
+    let data = Cid::compute(Codec::DagCbor, b"data");
+
+    let commit = Commit::sign(did, data, Tid::next(), &key);
+    assert!(commit.verify(key.verifying_key()));
+    assert!(!commit.verify(SigningKey::generate().verifying_key()));
+
+    let decoded = Commit::decode(&commit.to_value().encode()).unwrap();
+    assert_eq!(decoded, commit);
+
+    let mut tampered = commit.clone();
+    tampered.rev = Tid::next();
+    assert!(!tampered.verify(key.verifying_key()));
+}
@@ -0,0 +1,339 @@
+use {
+    super::{
+        car::{CarError, CarReader},
+        mst::{Mst, MstError},
+        BlockMap, Commit, CommitDecodeError, RecordWrite,
+    },
+    crate::{
+        api::xrpc::model::{validate_nsid, validate_record_key, Cid, Did, Tid},
+        blob::BlobRef,
+        dag_cbor::{self, Value},
+    },
+    hyper::body::{Body, Bytes},
+    k256::ecdsa::VerifyingKey,
+    std::ops::Bound,
+};
+
+/// An error that might occur when reading an imported repository.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The CAR file could not be read.
+    Car(CarError),
+    /// The CAR file does not have exactly one root.
+    InvalidRoots,
+    /// A block of the repository is missing from the CAR file.
+    MissingBlock(Cid),
+    /// The commit is malformed.
+    InvalidCommit(CommitDecodeError),
+    /// The commit belongs to another account.
+    WrongDid(Did),
+    /// The commit was not signed by the key of the account.
+    InvalidSignature,
+    /// The MST is malformed.
+    Mst(MstError),
+    /// A key of the MST is not of the form `<collection>/<rkey>`.
+    InvalidPath(String),
+    /// The MST does not have the shape implied by its keys.
+    NonCanonicalMst,
+    /// A record is not a DAG-CBOR map.
+    InvalidRecord(Cid),
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Car(err) => std::fmt::Display::fmt(err, f),
+            Self::InvalidRoots => f.write_str("the CAR file must have exactly one root"),
+            Self::MissingBlock(cid) => write!(f, "missing block `{cid}`"),
+            Self::InvalidCommit(err) => std::fmt::Display::fmt(err, f),
+            Self::WrongDid(did) => write!(f, "the commit belongs to `{did}`"),
+            Self::InvalidSignature => f.write_str("invalid commit signature"),
+            Self::Mst(err) => std::fmt::Display::fmt(err, f),
+            Self::InvalidPath(path) => write!(f, "invalid record path `{path}`"),
+            Self::NonCanonicalMst => f.write_str("the MST is not in its canonical shape"),
+            Self::InvalidRecord(cid) => write!(f, "invalid record `{cid}`"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<CarError> for ImportError {
+    #[inline]
+    fn from(value: CarError) -> Self {
+        Self::Car(value)
+    }
+}
+
+impl From<MstError> for ImportError {
+    #[inline]
+    fn from(value: MstError) -> Self {
+        Self::Mst(value)
+    }
+}
+
+/// A repository read from a CAR file, whose signature and structure have
+/// been verified.
+#[derive(Debug)]
+pub struct ImportedRepo {
+    /// The account the repository belongs to.
+    pub did: Did,
+    /// The CID of the commit.
+    pub cid: Cid,
+    /// The revision of the commit.
+    pub rev: Tid,
+    /// The blocks of the repository: the commit, the nodes of the MST and
+    /// the records. Blocks of the CAR file that are not part of the
+    /// repository are dropped.
+    pub blocks: BlockMap,
+    /// The records of the repository, sorted by path.
+    pub records: Vec<RecordWrite>,
+}
+
+impl ImportedRepo {
+    /// Reads a repository from a CAR file whose root is a commit.
+    ///
+    /// The commit must belong to `did` and be signed by `key`. Every node
+    /// of the MST must be present and well-formed, the tree must have the
+    /// canonical shape for its keys, every key must be made of a valid
+    /// collection NSID and record key, and every record must be a DAG-CBOR
+    /// map.
+    pub async fn read<B>(
+        mut car: CarReader<B>,
+        did: &Did,
+        key: &VerifyingKey,
+    ) -> Result<Self, ImportError>
+    where
+        B: Body<Data = Bytes> + Unpin,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let &[root] = car.roots() else {
+            return Err(ImportError::InvalidRoots);
+        };
+
+        let mut blocks = BlockMap::new();
+        while let Some((cid, data)) = car.next_block().await? {
+            blocks.insert(cid, data.to_vec());
+        }
+
+        let commit_bytes = blocks.get(&root).ok_or(ImportError::MissingBlock(root))?;
+        let commit = Commit::decode(commit_bytes).map_err(ImportError::InvalidCommit)?;
+        if commit.did != *did {
+            return Err(ImportError::WrongDid(commit.did));
+        }
+        if !commit.verify(key) {
+            return Err(ImportError::InvalidSignature);
+        }
+
+        let entries = Mst::load(commit.data)
+            .range(
+                &blocks,
+                Bound::Unbounded,
+                Bound::Unbounded,
+                false,
+                usize::MAX,
+            )
+            .await?;
+
+        // The tree is rebuilt from its entries: it only has the canonical
+        // shape if the rebuilt tree has the same root.
+        let mut mst = Mst::new();
+        let mut out = BlockMap::new();
+        let mut records = Vec::with_capacity(entries.len());
+        for (path, cid) in entries {
+            let path = std::str::from_utf8(&path)
+                .ok()
+                .filter(|path| is_record_path(path))
+                .ok_or_else(|| ImportError::InvalidPath(String::from_utf8_lossy(&path).into()))?;
+
+            let bytes = blocks.get(&cid).ok_or(ImportError::MissingBlock(cid))?;
+            let record: Value =
+                dag_cbor::from_slice(bytes).map_err(|_| ImportError::InvalidRecord(cid))?;
+            if record.as_map().is_none() {
+                return Err(ImportError::InvalidRecord(cid));
+            }
+
+            mst.insert(&out, path.as_bytes(), cid).await?;
+            out.insert(cid, bytes.to_vec());
+
+            records.push(RecordWrite {
+                path: path.into(),
+                cid: Some(cid),
+                blobs: BlobRef::find_all(&record)
+                    .into_iter()
+                    .map(|blob| blob.cid)
+                    .collect(),
+            });
+        }
+
+        if mst.write(&mut out) != commit.data {
+            return Err(ImportError::NonCanonicalMst);
+        }
+        out.insert(root, commit_bytes.to_vec());
+
+        Ok(Self {
+            did: commit.did,
+            cid: root,
+            rev: commit.rev,
+            blocks: out,
+            records,
+        })
+    }
+}
+
+/// Returns whether `path` is made of a valid collection NSID and a valid
+/// record key, separated by a slash.
+fn is_record_path(path: &str) -> bool {
+    path.split_once('/').is_some_and(|(collection, rkey)| {
+        validate_nsid(collection.as_bytes()) && validate_record_key(rkey.as_bytes())
+    })
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn import_repo() {
+    use {
+        super::{car, SigningKey},
+        http_body_util::Full,
+    };
+
+    let did = Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap();
+    let key = SigningKey::generate();
+
+    // Builds a CAR file holding a repository with the provided records,
+    // along with an unrelated block. If `pad` is set, an empty subtree is
+    // added to the left of the root of the MST, which keeps its keys but
+    // makes its shape non-canonical.
+    async fn export(
+        did: &Did,
+        records: &[(&str, Value)],
+        key: &SigningKey,
+        tamper: bool,
+        pad: bool,
+    ) -> CarReader<Full<Bytes>> {
+        let mut blocks = BlockMap::new();
+        let mut mst = Mst::new();
+        for (path, record) in records {
+            let cid = blocks.insert_value(record);
+            mst.insert(&blocks, path.as_bytes(), cid).await.unwrap();
+        }
+        let mut data = mst.write(&mut blocks);
+        if pad {
+            let empty = blocks.insert_value(&Value::Map(
+                [
+                    ("l".into(), Value::Null),
+                    ("e".into(), Value::List(Vec::new())),
+                ]
+                .into(),
+            ));
+            let mut root: Value = dag_cbor::from_slice(blocks.get(&data).unwrap()).unwrap();
+            let Value::Map(map) = &mut root else {
+                unreachable!();
+            };
+            map.insert("l".into(), Value::Link(empty));
+            data = blocks.insert_value(&root);
+        }
+        let mut commit = Commit::sign(did.clone(), data, Tid::next(), key);
+        if tamper {
+            commit.rev = Tid::next();
+        }
+        let root = blocks.insert_value(&commit.to_value());
+        blocks.insert_value(&Value::String("unrelated".into()));
+
+        let mut bytes = car::encode_header(&[root]);
+        for (cid, data) in blocks.iter() {
+            bytes.extend_from_slice(&car::encode_block(cid, data));
+        }
+        CarReader::new(Full::new(Bytes::from(bytes)), Default::default())
+            .await
+            .unwrap()
+    }
+
+    let post = |text: &str| Value::Map([("text".into(), Value::String(text.into()))].into());
+    let records = [
+        ("app.bsky.feed.post/a", post("first")),
+        ("app.bsky.feed.post/b", post("second")),
+        ("app.bsky.feed.like/c", post("third")),
+    ];
+
+    let repo = ImportedRepo::read(
+        export(&did, &records, &key, false, false).await,
+        &did,
+        key.verifying_key(),
+    )
+    .await
+    .unwrap();
+    let paths: Vec<&str> = repo.records.iter().map(|r| &*r.path).collect();
+    assert_eq!(
+        paths,
+        [
+            "app.bsky.feed.like/c",
+            "app.bsky.feed.post/a",
+            "app.bsky.feed.post/b"
+        ]
+    );
+    // The unrelated block is dropped.
+    let unrelated = BlockMap::new().insert_value(&Value::String("unrelated".into()));
+    assert!(repo.blocks.contains(&repo.cid));
+    assert!(!repo.blocks.contains(&unrelated));
+
+    let other = SigningKey::generate();
+    assert!(matches!(
+        ImportedRepo::read(
+            export(&did, &records, &other, false, false).await,
+            &did,
+            key.verifying_key()
+        )
+        .await,
+        Err(ImportError::InvalidSignature)
+    ));
+    assert!(matches!(
+        ImportedRepo::read(
+            export(&did, &records, &key, true, false).await,
+            &did,
+            key.verifying_key()
+        )
+        .await,
+        Err(ImportError::InvalidSignature)
+    ));
+
+    let invalid = [("app.bsky.feed.post/a", Value::String("not a map".into()))];
+    assert!(matches!(
+        ImportedRepo::read(
+            export(&did, &invalid, &key, false, false).await,
+            &did,
+            key.verifying_key()
+        )
+        .await,
+        Err(ImportError::InvalidRecord(_))
+    ));
+
+    // An empty subtree is only allowed above the bottom layer.
+    let high = (0..)
+        .map(|i| format!("app.bsky.feed.post/{i}"))
+        .find(|path| super::mst::key_layer(path.as_bytes()) > 0)
+        .unwrap();
+    let padded = [(&*high, post("first"))];
+    assert!(matches!(
+        ImportedRepo::read(
+            export(&did, &padded, &key, false, true).await,
+            &did,
+            key.verifying_key()
+        )
+        .await,
+        Err(ImportError::NonCanonicalMst)
+    ));
+
+    for path in ["thing/a", "app.bsky.feed.post/.."] {
+        let invalid = [(path, post("first"))];
+        assert!(matches!(
+            ImportedRepo::read(
+                export(&did, &invalid, &key, false, false).await,
+                &did,
+                key.verifying_key()
+            )
+            .await,
+            Err(ImportError::InvalidPath(p)) if p == path
+        ));
+    }
+}
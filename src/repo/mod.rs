@@ -0,0 +1,30 @@
+//! Storage structures for account repositories.
+//!
+//! A repository is a content-addressed collection of records, indexed by a
+//! Merkle Search Tree and signed through a chain of commits. See the
+//! [repository specification](https://atproto.com/specs/repository) for more
+//! information.
+
+mod block_store;
+pub use self::block_store::*;
+
+mod commit;
+pub use self::commit::*;
+
+mod import;
+pub use self::import::*;
+
+mod signing_key;
+pub use self::signing_key::*;
+
+mod storage;
+pub use self::storage::*;
+
+mod transaction;
+pub use self::transaction::*;
+
+mod writer;
+pub use self::writer::*;
+
+pub mod car;
+pub mod mst;
@@ -0,0 +1,1028 @@
+//! An implementation of the Merkle Search Tree (MST) used to store the
+//! records of a repository.
+//!
+//! Keys are repository paths of the form `<collection>/<record-key>`, and
+//! values are the CIDs of the records. The shape of the tree only depends on
+//! the set of keys it contains: the layer of a key is the number of leading
+//! zero bits of its SHA-256 hash, divided by two. Each node contains the keys
+//! of a single layer, and the gaps between those keys are filled by subtrees
+//! of the layer below.
+//!
+//! See the [repository specification](https://atproto.com/specs/repository)
+//! for more information.
+
+use {
+    super::{BlockMap, BlockStore, BlockStoreError},
+    crate::{
+        api::xrpc::model::{Cid, Codec},
+        dag_cbor::{self, Value},
+    },
+    futures::{future::BoxFuture, FutureExt},
+    sha2::{Digest, Sha256},
+    std::{collections::BTreeMap, ops::Bound},
+};
+
+/// The maximum length of a key, in bytes.
+pub const MAX_KEY_LEN: usize = 1024;
+
+/// An error that might occur when manipulating a [`Mst`].
+#[derive(Debug)]
+pub enum MstError {
+    /// A node referenced by the tree is not present in the block store.
+    MissingBlock(Cid),
+    /// A node of the tree is malformed.
+    InvalidNode(Cid, &'static str),
+    /// The provided key is not a valid repository path.
+    InvalidKey,
+    /// The block store failed.
+    Store(BlockStoreError),
+}
+
+impl std::fmt::Display for MstError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingBlock(cid) => write!(f, "missing MST node `{cid}`"),
+            Self::InvalidNode(cid, reason) => write!(f, "invalid MST node `{cid}`: {reason}"),
+            Self::InvalidKey => f.write_str("invalid MST key"),
+            Self::Store(err) => std::fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for MstError {}
+
+impl From<BlockStoreError> for MstError {
+    #[inline]
+    fn from(value: BlockStoreError) -> Self {
+        Self::Store(value)
+    }
+}
+
+/// Validates the provided key as a repository path (`<collection>/<rkey>`).
+pub fn validate_key(key: &[u8]) -> bool {
+    #[inline]
+    fn is_key_char(c: &u8) -> bool {
+        matches!(c, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' | b':')
+    }
+
+    if key.len() > MAX_KEY_LEN {
+        return false;
+    }
+
+    let Some(slash) = memchr::memchr(b'/', key) else {
+        return false;
+    };
+
+    let (collection, rkey) = (&key[..slash], &key[slash + 1..]);
+
+    !collection.is_empty()
+        && !rkey.is_empty()
+        && collection.iter().all(is_key_char)
+        && rkey.iter().all(is_key_char)
+}
+
+/// Computes the layer of the provided key.
+///
+/// This is the number of leading zero bits of the SHA-256 hash of the key,
+/// divided by two (rounding down).
+pub fn key_layer(key: &[u8]) -> u32 {
+    let hash = Sha256::digest(key);
+
+    let mut zeros = 0;
+    for byte in hash {
+        zeros += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+
+    zeros / 2
+}
+
+/// A reference to a child node.
+enum Link {
+    /// The node has not been loaded from the block store yet.
+    Stored(Cid),
+    /// The node is loaded in memory.
+    Loaded(Box<Node>),
+}
+
+/// An entry of a node.
+struct Entry {
+    /// The full key of the entry.
+    key: Box<[u8]>,
+    /// The value associated with the key.
+    value: Cid,
+    /// The subtree containing the keys between this entry and the next one.
+    right: Option<Link>,
+}
+
+/// A node of the tree.
+#[derive(Default)]
+struct Node {
+    /// The subtree containing the keys before the first entry.
+    left: Option<Link>,
+    /// The entries of the node, sorted by key.
+    entries: Vec<Entry>,
+    /// The CID of the node, if it has not been modified since it was last
+    /// loaded or written.
+    cid: Option<Cid>,
+}
+
+impl Node {
+    /// Creates a chain of nodes starting at `layer` and going down to
+    /// `key_layer`, where the last node contains the provided key.
+    fn chain(layer: u32, key: Box<[u8]>, key_layer: u32, value: Cid) -> Self {
+        if layer == key_layer {
+            Self {
+                left: None,
+                entries: vec![Entry {
+                    key,
+                    value,
+                    right: None,
+                }],
+                cid: None,
+            }
+        } else {
+            Self {
+                left: Some(Link::Loaded(Box::new(Self::chain(
+                    layer - 1,
+                    key,
+                    key_layer,
+                    value,
+                )))),
+                entries: Vec::new(),
+                cid: None,
+            }
+        }
+    }
+
+    /// Returns whether the node contains nothing at all.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.left.is_none()
+    }
+
+    /// Returns the index of the first entry whose key is not less than `key`.
+    #[inline]
+    fn position(&self, key: &[u8]) -> usize {
+        self.entries.partition_point(|e| &*e.key < key)
+    }
+
+    /// Returns the subtree located right before the entry at index `i` (or at
+    /// the end of the node if `i` is the number of entries).
+    fn gap_mut(&mut self, i: usize) -> &mut Option<Link> {
+        match i.checked_sub(1) {
+            Some(prev) => &mut self.entries[prev].right,
+            None => &mut self.left,
+        }
+    }
+
+    /// Converts the node into a link, or `None` if the node is empty.
+    fn into_link(self) -> Option<Link> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Link::Loaded(Box::new(self)))
+        }
+    }
+
+    /// Decodes a node from its DAG-CBOR representation.
+    ///
+    /// If `layer` is provided, all the keys of the node must belong to that
+    /// layer.
+    fn decode(cid: Cid, bytes: &[u8], layer: Option<u32>) -> Result<Self, MstError> {
+        let invalid = |reason| MstError::InvalidNode(cid, reason);
+
+        let value: Value = dag_cbor::from_slice(bytes).map_err(|_| invalid("not DAG-CBOR"))?;
+        let map = value.as_map().ok_or(invalid("not a map"))?;
+        if map.len() != 2 {
+            return Err(invalid("unexpected fields"));
+        }
+
+        let left = match map.get("l") {
+            Some(Value::Null) => None,
+            Some(Value::Link(cid)) => Some(Link::Stored(*cid)),
+            _ => return Err(invalid("invalid `l` field")),
+        };
+
+        let Some(Value::List(raw_entries)) = map.get("e") else {
+            return Err(invalid("invalid `e` field"));
+        };
+
+        let mut entries: Vec<Entry> = Vec::with_capacity(raw_entries.len());
+        let mut expected_layer = layer;
+
+        for raw in raw_entries {
+            let raw = raw.as_map().ok_or(invalid("entry is not a map"))?;
+            if raw.len() != 4 {
+                return Err(invalid("unexpected entry fields"));
+            }
+
+            let (
+                Some(Value::Integer(prefix_len)),
+                Some(Value::Bytes(suffix)),
+                Some(Value::Link(value)),
+            ) = (raw.get("p"), raw.get("k"), raw.get("v"))
+            else {
+                return Err(invalid("invalid entry fields"));
+            };
+
+            let right = match raw.get("t") {
+                Some(Value::Null) => None,
+                Some(Value::Link(cid)) => Some(Link::Stored(*cid)),
+                _ => return Err(invalid("invalid `t` field")),
+            };
+
+            let prev_key = entries.last().map(|e| &*e.key).unwrap_or_default();
+            let prefix = usize::try_from(*prefix_len)
+                .ok()
+                .and_then(|len| prev_key.get(..len))
+                .ok_or(invalid("invalid key prefix length"))?;
+
+            let mut key = Vec::with_capacity(prefix.len() + suffix.len());
+            key.extend_from_slice(prefix);
+            key.extend_from_slice(suffix);
+
+            if !validate_key(&key) {
+                return Err(invalid("invalid key"));
+            }
+            if !entries.is_empty() && &*key <= prev_key {
+                return Err(invalid("keys are not sorted"));
+            }
+
+            let this_layer = key_layer(&key);
+            match expected_layer {
+                Some(expected) if expected != this_layer => {
+                    return Err(invalid("key is in the wrong layer"))
+                }
+                _ => expected_layer = Some(this_layer),
+            }
+
+            entries.push(Entry {
+                key: key.into_boxed_slice(),
+                value: *value,
+                right,
+            });
+        }
+
+        // The nodes of the bottom layer have no subtrees.
+        let has_subtrees = left.is_some() || entries.iter().any(|e| e.right.is_some());
+        if expected_layer == Some(0) && has_subtrees {
+            return Err(invalid("subtree below layer 0"));
+        }
+
+        Ok(Self {
+            left,
+            entries,
+            cid: Some(cid),
+        })
+    }
+
+    /// Encodes the node into its DAG-CBOR representation.
+    ///
+    /// All the children of the node must have been written beforehand.
+    fn encode(&self) -> Value {
+        fn link_value(link: &Option<Link>) -> Value {
+            match link {
+                None => Value::Null,
+                Some(Link::Stored(cid)) => Value::Link(*cid),
+                Some(Link::Loaded(node)) => {
+                    Value::Link(node.cid.expect("child node has not been written"))
+                }
+            }
+        }
+
+        let mut prev_key: &[u8] = &[];
+        let mut entries = Vec::with_capacity(self.entries.len());
+
+        for entry in &self.entries {
+            let prefix_len = prev_key
+                .iter()
+                .zip(entry.key.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            let mut map = BTreeMap::new();
+            map.insert("p".into(), Value::Integer(prefix_len as i64));
+            map.insert("k".into(), Value::Bytes(entry.key[prefix_len..].to_vec()));
+            map.insert("v".into(), Value::Link(entry.value));
+            map.insert("t".into(), link_value(&entry.right));
+            entries.push(Value::Map(map));
+
+            prev_key = &entry.key;
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert("l".into(), link_value(&self.left));
+        map.insert("e".into(), Value::List(entries));
+        Value::Map(map)
+    }
+
+    /// Writes the node and all its modified descendants to `out`, returning
+    /// the CID of the node.
+    fn write(&mut self, out: &mut BlockMap) -> Cid {
+        if let Some(cid) = self.cid {
+            return cid;
+        }
+
+        let children =
+            std::iter::once(&mut self.left).chain(self.entries.iter_mut().map(|e| &mut e.right));
+        for child in children {
+            if let Some(Link::Loaded(node)) = child {
+                node.write(out);
+            }
+        }
+
+        let cid = out.insert_value(&self.encode());
+        self.cid = Some(cid);
+        cid
+    }
+}
+
+/// Loads the node referenced by `link` if needed, and returns it.
+async fn load<'a, S: BlockStore>(
+    link: &'a mut Link,
+    store: &S,
+    layer: u32,
+) -> Result<&'a mut Node, MstError> {
+    if let Link::Stored(cid) = *link {
+        let bytes = store
+            .get_block(&cid)
+            .await?
+            .ok_or(MstError::MissingBlock(cid))?;
+        let node = Node::decode(cid, &bytes, Some(layer))?;
+        *link = Link::Loaded(Box::new(node));
+    }
+
+    match link {
+        Link::Loaded(node) => Ok(node),
+        Link::Stored(_) => unreachable!(),
+    }
+}
+
+/// Loads the node referenced by `link` if needed, and takes ownership of it.
+async fn take<S: BlockStore>(link: Link, store: &S, layer: u32) -> Result<Box<Node>, MstError> {
+    match link {
+        Link::Loaded(node) => Ok(node),
+        Link::Stored(cid) => {
+            let bytes = store
+                .get_block(&cid)
+                .await?
+                .ok_or(MstError::MissingBlock(cid))?;
+            Ok(Box::new(Node::decode(cid, &bytes, Some(layer))?))
+        }
+    }
+}
+
+/// Inserts a key in the subtree rooted at `node`, which belongs to `layer`.
+///
+/// The layer of the key must not be greater than `layer`.
+fn insert<'a, S: BlockStore>(
+    node: &'a mut Node,
+    layer: u32,
+    store: &'a S,
+    key: Box<[u8]>,
+    key_layer: u32,
+    value: Cid,
+) -> BoxFuture<'a, Result<Option<Cid>, MstError>> {
+    async move {
+        let i = node.position(&key);
+
+        if key_layer == layer {
+            if let Some(entry) = node.entries.get_mut(i).filter(|e| e.key == key) {
+                let prev = std::mem::replace(&mut entry.value, value);
+                if prev != value {
+                    node.cid = None;
+                }
+                return Ok(Some(prev));
+            }
+
+            // The subtree where the key would have been must be split in two
+            // around the new key.
+            let (left, right) = match node.gap_mut(i).take() {
+                Some(gap) => split(gap, layer - 1, store, &key).await?,
+                None => (None, None),
+            };
+
+            *node.gap_mut(i) = left;
+            node.entries.insert(i, Entry { key, value, right });
+            node.cid = None;
+            return Ok(None);
+        }
+
+        let prev = match node.gap_mut(i) {
+            Some(gap) => {
+                let child = load(gap, store, layer - 1).await?;
+                insert(child, layer - 1, store, key, key_layer, value).await?
+            }
+            gap @ None => {
+                let chain = Node::chain(layer - 1, key, key_layer, value);
+                *gap = Some(Link::Loaded(Box::new(chain)));
+                None
+            }
+        };
+
+        if prev != Some(value) {
+            node.cid = None;
+        }
+
+        Ok(prev)
+    }
+    .boxed()
+}
+
+/// The two halves of a subtree that has been split around a key.
+type SplitHalves = (Option<Link>, Option<Link>);
+
+/// Splits the subtree referenced by `link` into two subtrees: one containing
+/// the keys less than `key`, and one containing the keys greater than `key`.
+fn split<'a, S: BlockStore>(
+    link: Link,
+    layer: u32,
+    store: &'a S,
+    key: &'a [u8],
+) -> BoxFuture<'a, Result<SplitHalves, MstError>> {
+    async move {
+        let mut node = take(link, store, layer).await?;
+        let i = node.position(key);
+
+        let mut right = Node {
+            left: None,
+            entries: node.entries.split_off(i),
+            cid: None,
+        };
+
+        let (mid_left, mid_right) = match node.gap_mut(i).take() {
+            Some(gap) => split(gap, layer - 1, store, key).await?,
+            None => (None, None),
+        };
+
+        *node.gap_mut(i) = mid_left;
+        node.cid = None;
+        right.left = mid_right;
+
+        Ok((node.into_link(), right.into_link()))
+    }
+    .boxed()
+}
+
+/// Removes a key from the subtree rooted at `node`, which belongs to `layer`.
+fn remove<'a, S: BlockStore>(
+    node: &'a mut Node,
+    layer: u32,
+    store: &'a S,
+    key: &'a [u8],
+    key_layer: u32,
+) -> BoxFuture<'a, Result<Option<Cid>, MstError>> {
+    async move {
+        let i = node.position(key);
+
+        if key_layer == layer {
+            if node.entries.get(i).is_none_or(|e| &*e.key != key) {
+                return Ok(None);
+            }
+
+            // The subtrees on both sides of the removed entry must be merged.
+            let entry = node.entries.remove(i);
+            let left = node.gap_mut(i).take();
+            *node.gap_mut(i) = merge(left, entry.right, layer.saturating_sub(1), store).await?;
+            node.cid = None;
+            return Ok(Some(entry.value));
+        }
+
+        let Some(gap) = node.gap_mut(i) else {
+            return Ok(None);
+        };
+
+        let child = load(gap, store, layer - 1).await?;
+        let prev = remove(child, layer - 1, store, key, key_layer).await?;
+        let child_is_empty = child.is_empty();
+
+        if prev.is_some() {
+            node.cid = None;
+            if child_is_empty {
+                *node.gap_mut(i) = None;
+            }
+        }
+
+        Ok(prev)
+    }
+    .boxed()
+}
+
+/// Merges two adjacent subtrees of the same layer.
+///
+/// All the keys of `left` must be less than the keys of `right`.
+fn merge<'a, S: BlockStore>(
+    left: Option<Link>,
+    right: Option<Link>,
+    layer: u32,
+    store: &'a S,
+) -> BoxFuture<'a, Result<Option<Link>, MstError>> {
+    async move {
+        let (left, right) = match (left, right) {
+            (None, other) | (other, None) => return Ok(other),
+            (Some(left), Some(right)) => (left, right),
+        };
+
+        let mut left = take(left, store, layer).await?;
+        let mut right = take(right, store, layer).await?;
+
+        // The last subtree of `left` and the first subtree of `right` are now
+        // adjacent, and must be merged as well.
+        let last = left.entries.len();
+        let a = left.gap_mut(last).take();
+        let b = right.left.take();
+        *left.gap_mut(last) = merge(a, b, layer.saturating_sub(1), store).await?;
+
+        left.entries.append(&mut right.entries);
+        left.cid = None;
+
+        Ok(left.into_link())
+    }
+    .boxed()
+}
+
+/// Collects the entries of the subtree rooted at `node` that fall within
+/// the provided bounds, stopping once `limit` entries have been collected.
+fn collect_range<'a, S: BlockStore>(
+    node: &'a mut Node,
+    layer: u32,
+    store: &'a S,
+    bounds: (Bound<&'a [u8]>, Bound<&'a [u8]>),
+    reverse: bool,
+    limit: usize,
+    out: &'a mut Vec<(Box<[u8]>, Cid)>,
+) -> BoxFuture<'a, Result<(), MstError>> {
+    async move {
+        let (lower, upper) = bounds;
+
+        let above_lower = |key: &[u8]| match lower {
+            Bound::Included(bound) => key >= bound,
+            Bound::Excluded(bound) => key > bound,
+            Bound::Unbounded => true,
+        };
+        let below_upper = |key: &[u8]| match upper {
+            Bound::Included(bound) => key <= bound,
+            Bound::Excluded(bound) => key < bound,
+            Bound::Unbounded => true,
+        };
+
+        let len = node.entries.len();
+
+        // Visit the gaps and entries in order: gap 0, entry 0, gap 1, entry 1,
+        // ..., gap N. Position `2 * i` is gap `i` and `2 * i + 1` is entry `i`.
+        let positions: Box<dyn Iterator<Item = usize> + Send> = if reverse {
+            Box::new((0..=2 * len).rev())
+        } else {
+            Box::new(0..=2 * len)
+        };
+
+        for pos in positions {
+            if out.len() >= limit {
+                break;
+            }
+
+            let i = pos / 2;
+
+            if pos % 2 == 1 {
+                let entry = &node.entries[i];
+                if above_lower(&entry.key) && below_upper(&entry.key) {
+                    out.push((entry.key.clone(), entry.value));
+                }
+                continue;
+            }
+
+            // Skip the gaps that are entirely outside of the bounds. All the keys
+            // of gap `i` are between the keys of entries `i - 1` and `i`.
+            if i < len && !above_lower(&node.entries[i].key) {
+                continue;
+            }
+            if i > 0 && !below_upper(&node.entries[i - 1].key) {
+                continue;
+            }
+
+            if let Some(gap) = node.gap_mut(i) {
+                let child = load(gap, store, layer - 1).await?;
+                collect_range(child, layer - 1, store, bounds, reverse, limit, out).await?;
+            }
+        }
+
+        Ok(())
+    }
+    .boxed()
+}
+
+/// A Merkle Search Tree.
+///
+/// Nodes are loaded lazily from a [`BlockStore`] when they are needed, and
+/// modified nodes are kept in memory until [`Mst::write`] is called.
+pub struct Mst {
+    /// The root node of the tree.
+    root: Link,
+    /// The layer of the root node, or `None` if the root has not been loaded
+    /// yet.
+    layer: Option<u32>,
+}
+
+impl Mst {
+    /// Creates a new empty tree.
+    pub fn new() -> Self {
+        Self {
+            root: Link::Loaded(Box::default()),
+            layer: Some(0),
+        }
+    }
+
+    /// Creates a tree whose root node is stored in a block store.
+    pub fn load(root: Cid) -> Self {
+        Self {
+            root: Link::Stored(root),
+            layer: None,
+        }
+    }
+
+    /// Loads the root node of the tree if needed, and returns it along with
+    /// its layer.
+    async fn root<S: BlockStore>(&mut self, store: &S) -> Result<(&mut Node, u32), MstError> {
+        if let Link::Stored(cid) = self.root {
+            let bytes = store
+                .get_block(&cid)
+                .await?
+                .ok_or(MstError::MissingBlock(cid))?;
+            let node = Node::decode(cid, &bytes, None)?;
+
+            let layer = match node.entries.first() {
+                Some(entry) => key_layer(&entry.key),
+                None if node.left.is_none() => 0,
+                None => return Err(MstError::InvalidNode(cid, "root node has no entries")),
+            };
+
+            self.root = Link::Loaded(Box::new(node));
+            self.layer = Some(layer);
+        }
+
+        match (&mut self.root, self.layer) {
+            (Link::Loaded(node), Some(layer)) => Ok((node, layer)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the value associated with the provided key.
+    pub async fn get<S: BlockStore>(
+        &mut self,
+        store: &S,
+        key: &[u8],
+    ) -> Result<Option<Cid>, MstError> {
+        let (mut node, mut layer) = self.root(store).await?;
+
+        loop {
+            let i = node.position(key);
+
+            if let Some(entry) = node.entries.get(i).filter(|e| &*e.key == key) {
+                return Ok(Some(entry.value));
+            }
+
+            match node.gap_mut(i) {
+                Some(gap) if layer > 0 => {
+                    node = load(gap, store, layer - 1).await?;
+                    layer -= 1;
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    /// Inserts or updates a key, returning its previous value.
+    pub async fn insert<S: BlockStore>(
+        &mut self,
+        store: &S,
+        key: &[u8],
+        value: Cid,
+    ) -> Result<Option<Cid>, MstError> {
+        if !validate_key(key) {
+            return Err(MstError::InvalidKey);
+        }
+
+        let key_layer = key_layer(key);
+        let (_, layer) = self.root(store).await?;
+
+        // The tree must grow until its root is at least as high as the key.
+        if key_layer > layer {
+            let Link::Loaded(root) = &mut self.root else {
+                unreachable!();
+            };
+
+            if root.is_empty() {
+                self.layer = Some(key_layer);
+            } else {
+                for _ in layer..key_layer {
+                    let old_root = std::mem::take(root);
+                    root.left = Some(Link::Loaded(old_root));
+                }
+                self.layer = Some(key_layer);
+            }
+        }
+
+        let (root, layer) = self.root(store).await?;
+        insert(root, layer, store, key.into(), key_layer, value).await
+    }
+
+    /// Removes a key, returning its previous value.
+    pub async fn remove<S: BlockStore>(
+        &mut self,
+        store: &S,
+        key: &[u8],
+    ) -> Result<Option<Cid>, MstError> {
+        if !validate_key(key) {
+            return Err(MstError::InvalidKey);
+        }
+
+        let key_layer = key_layer(key);
+        let (root, layer) = self.root(store).await?;
+        if key_layer > layer {
+            return Ok(None);
+        }
+
+        let prev = remove(root, layer, store, key, key_layer).await?;
+
+        // Remove the top layers of the tree that only contain a single
+        // subtree.
+        loop {
+            let (root, layer) = self.root(store).await?;
+            if !root.entries.is_empty() {
+                break;
+            }
+
+            match root.left.take() {
+                Some(left) => {
+                    self.root = Link::Loaded(take(left, store, layer - 1).await?);
+                    self.layer = Some(layer - 1);
+                }
+                None => {
+                    self.layer = Some(0);
+                    break;
+                }
+            }
+        }
+
+        Ok(prev)
+    }
+
+    /// Returns the entries whose key is within the provided bounds, in
+    /// ascending order (or descending order if `reverse` is set).
+    ///
+    /// At most `limit` entries are returned.
+    pub async fn range<S: BlockStore>(
+        &mut self,
+        store: &S,
+        lower: Bound<&[u8]>,
+        upper: Bound<&[u8]>,
+        reverse: bool,
+        limit: usize,
+    ) -> Result<Vec<(Box<[u8]>, Cid)>, MstError> {
+        let (root, layer) = self.root(store).await?;
+        let mut out = Vec::new();
+        collect_range(root, layer, store, (lower, upper), reverse, limit, &mut out).await?;
+        Ok(out)
+    }
+
+    /// Writes all the nodes that have been modified since the last call to
+    /// `out`, and returns the CID of the root node.
+    pub fn write(&mut self, out: &mut BlockMap) -> Cid {
+        match &mut self.root {
+            Link::Stored(cid) => *cid,
+            Link::Loaded(node) => node.write(out),
+        }
+    }
+
+    /// Computes the CID of the root node, writing modified nodes to a
+    /// throwaway block map.
+    pub fn root_cid(&mut self) -> Cid {
+        self.write(&mut BlockMap::new())
+    }
+}
+
+impl Default for Mst {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the CID of a record value with the provided content, as used in
+/// the tests.
+#[cfg(test)]
+fn test_value(n: u32) -> Cid {
+    Cid::compute(Codec::DagCbor, &n.to_be_bytes())
+}
+
+#[cfg(test)]
+#[test]
+fn layers() {
+    assert_eq!(key_layer(b""), 0);
+    assert_eq!(key_layer(b"asdf"), 0);
+    assert_eq!(key_layer(b"blue"), 1);
+    assert_eq!(key_layer(b"2653ae71"), 0);
+    assert_eq!(key_layer(b"88bfafc7"), 2);
+    assert_eq!(key_layer(b"2a92d355"), 4);
+    assert_eq!(key_layer(b"884976f5"), 6);
+    assert_eq!(key_layer(b"app.bsky.feed.post/454397e440ec"), 4);
+    assert_eq!(key_layer(b"app.bsky.feed.post/9adeb165882c"), 8);
+}
+
+#[cfg(test)]
+#[test]
+fn empty_tree_root() {
+    let mut mst = Mst::new();
+    assert_eq!(
+        mst.root_cid().to_string(),
+        "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn single_entry_root() {
+    let store = BlockMap::new();
+    let value: Cid = "bafyreie5cvv4h45feadgeuwhbcutmh6t2ceseocckahdoe6uat64zmz454"
+        .parse()
+        .unwrap();
+
+    let mut mst = Mst::new();
+    mst.insert(&store, b"com.example.record/3jqfcqzm3fo2j", value)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        mst.root_cid().to_string(),
+        "bafyreibj4lsc3aqnrvphp5xmrnfoorvru4wynt6lwidqbm2623a6tatzdu",
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn insertion_order_does_not_matter() {
+    let keys: Vec<String> = (0..300u32)
+        .map(|i| format!("com.example.record/{:08x}", i.wrapping_mul(2654435761)))
+        .collect();
+
+    let store = BlockMap::new();
+
+    let mut forward = Mst::new();
+    for (i, key) in keys.iter().enumerate() {
+        forward
+            .insert(&store, key.as_bytes(), test_value(i as u32))
+            .await
+            .unwrap();
+    }
+
+    let mut backward = Mst::new();
+    for (i, key) in keys.iter().enumerate().rev() {
+        backward
+            .insert(&store, key.as_bytes(), test_value(i as u32))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(forward.root_cid(), backward.root_cid());
+
+    // Removing half of the keys should yield the same tree as never inserting
+    // them.
+    let mut half = Mst::new();
+    for (i, key) in keys.iter().enumerate() {
+        if i % 2 == 0 {
+            half.insert(&store, key.as_bytes(), test_value(i as u32))
+                .await
+                .unwrap();
+        } else {
+            assert_eq!(
+                forward.remove(&store, key.as_bytes()).await.unwrap(),
+                Some(test_value(i as u32)),
+            );
+        }
+    }
+
+    assert_eq!(forward.root_cid(), half.root_cid());
+
+    // Removing everything should yield the empty tree.
+    for (i, key) in keys.iter().enumerate().filter(|(i, _)| i % 2 == 0) {
+        assert_eq!(
+            half.remove(&store, key.as_bytes()).await.unwrap(),
+            Some(test_value(i as u32)),
+        );
+    }
+
+    assert_eq!(half.root_cid(), Mst::new().root_cid());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn reload_from_store() {
+    let mut store = BlockMap::new();
+
+    let mut mst = Mst::new();
+    for i in 0..100u32 {
+        let key = format!("com.example.record/{i:04}");
+        mst.insert(&store, key.as_bytes(), test_value(i))
+            .await
+            .unwrap();
+    }
+
+    let root = mst.write(&mut store);
+
+    let mut reloaded = Mst::load(root);
+    assert_eq!(
+        reloaded
+            .get(&store, b"com.example.record/0042")
+            .await
+            .unwrap(),
+        Some(test_value(42)),
+    );
+    assert_eq!(
+        reloaded
+            .get(&store, b"com.example.record/0100")
+            .await
+            .unwrap(),
+        None,
+    );
+
+    reloaded
+        .insert(&store, b"com.example.record/0100", test_value(100))
+        .await
+        .unwrap();
+    mst.insert(&store, b"com.example.record/0100", test_value(100))
+        .await
+        .unwrap();
+    assert_eq!(reloaded.root_cid(), mst.root_cid());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn range_iteration() {
+    let store = BlockMap::new();
+
+    let mut mst = Mst::new();
+    for i in 0..200u32 {
+        let key = format!("com.example.record/{i:04}");
+        mst.insert(&store, key.as_bytes(), test_value(i))
+            .await
+            .unwrap();
+    }
+
+    let page = mst
+        .range(
+            &store,
+            Bound::Excluded(b"com.example.record/0010"),
+            Bound::Unbounded,
+            false,
+            5,
+        )
+        .await
+        .unwrap();
+    let keys: Vec<&[u8]> = page.iter().map(|(k, _)| &**k).collect();
+    assert_eq!(
+        keys,
+        [
+            b"com.example.record/0011",
+            b"com.example.record/0012",
+            b"com.example.record/0013",
+            b"com.example.record/0014",
+            b"com.example.record/0015",
+        ]
+    );
+
+    let page = mst
+        .range(
+            &store,
+            Bound::Unbounded,
+            Bound::Excluded(b"com.example.record/0100"),
+            true,
+            3,
+        )
+        .await
+        .unwrap();
+    let keys: Vec<&[u8]> = page.iter().map(|(k, _)| &**k).collect();
+    assert_eq!(
+        keys,
+        [
+            b"com.example.record/0099",
+            b"com.example.record/0098",
+            b"com.example.record/0097",
+        ]
+    );
+
+    let all = mst
+        .range(
+            &store,
+            Bound::Unbounded,
+            Bound::Unbounded,
+            false,
+            usize::MAX,
+        )
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 200);
+    assert!(all.windows(2).all(|w| w[0].0 < w[1].0));
+}
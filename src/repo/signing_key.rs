@@ -0,0 +1,178 @@
+use k256::ecdsa::{signature::Signer, Signature, VerifyingKey};
+
+/// The length of a serialized [`SigningKey`], in bytes.
+pub const SIGNING_KEY_LEN: usize = 32;
+
+/// The length of a signature produced by a [`SigningKey`], in bytes.
+pub const SIGNATURE_LEN: usize = 64;
+
+/// The multicodec prefix of compressed `secp256k1` public keys.
+const SECP256K1_PUB_PREFIX: [u8; 2] = [0xE7, 0x01];
+
+/// The alphabet of the base58btc encoding.
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// A `secp256k1` private key used to sign the commits of a repository.
+#[derive(Clone)]
+pub struct SigningKey(k256::ecdsa::SigningKey);
+
+impl SigningKey {
+    /// Generates a new random signing key.
+    pub fn generate() -> Self {
+        Self(k256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng))
+    }
+
+    /// Creates a signing key from its serialized form.
+    ///
+    /// Returns `None` if the provided bytes are not a valid private key.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        k256::ecdsa::SigningKey::from_slice(bytes).ok().map(Self)
+    }
+
+    /// Serializes the signing key.
+    pub fn to_bytes(&self) -> [u8; SIGNING_KEY_LEN] {
+        self.0.to_bytes().into()
+    }
+
+    /// Returns the public key associated with this signing key.
+    #[inline]
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        self.0.verifying_key()
+    }
+
+    /// Signs the SHA-256 hash of the provided message.
+    ///
+    /// The signature is returned in its compact `r || s` form, and is always
+    /// normalized to the lower half of the curve order, as required by the
+    /// AT Protocol.
+    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_LEN] {
+        let signature: Signature = self.0.sign(message);
+        let signature = signature.normalize_s().unwrap_or(signature);
+        signature.to_bytes().into()
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the private key.
+        f.debug_struct("SigningKey").finish_non_exhaustive()
+    }
+}
+
+/// Verifies a signature produced by [`SigningKey::sign`].
+///
+/// Signatures that are not normalized to the lower half of the curve order
+/// are rejected.
+pub fn verify_signature(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> bool {
+    use k256::ecdsa::signature::Verifier;
+
+    let Ok(signature) = Signature::from_slice(signature) else {
+        return false;
+    };
+
+    signature.normalize_s().is_none() && key.verify(message, &signature).is_ok()
+}
+
+/// Encodes a public key in the multibase `Multikey` format used by DID
+/// documents and `did:key` DIDs.
+pub fn encode_multikey(key: &VerifyingKey) -> String {
+    let mut bytes = SECP256K1_PUB_PREFIX.to_vec();
+    bytes.extend_from_slice(key.to_encoded_point(true).as_bytes());
+
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &bytes {
+        let mut carry = u32::from(byte);
+        for digit in &mut digits {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let mut out = String::with_capacity(1 + zeros + digits.len());
+    out.push('z');
+    out.extend(std::iter::repeat_n('1', zeros));
+    out.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&d| BASE58_ALPHABET[d as usize] as char),
+    );
+    out
+}
+
+/// Decodes a public key encoded in the multibase `Multikey` format.
+///
+/// Returns `None` if the key is malformed, or is not a `secp256k1` key.
+pub fn decode_multikey(multikey: &str) -> Option<VerifyingKey> {
+    let encoded = multikey.strip_prefix('z')?;
+
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in encoded.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in &mut bytes {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = encoded.bytes().take_while(|&c| c == b'1').count();
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+
+    let key = bytes.strip_prefix(&SECP256K1_PUB_PREFIX)?;
+    VerifyingKey::from_sec1_bytes(key).ok()
+}
+
+#[cfg(test)]
+#[test]
+fn sign_and_verify() {
+    let key = SigningKey::generate();
+    let key = SigningKey::from_bytes(&key.to_bytes()).unwrap();
+
+    let signature = key.sign(b"hello world");
+    assert!(verify_signature(
+        key.verifying_key(),
+        b"hello world",
+        &signature
+    ));
+    assert!(!verify_signature(
+        key.verifying_key(),
+        b"hello world!",
+        &signature
+    ));
+    assert!(!verify_signature(
+        SigningKey::generate().verifying_key(),
+        b"hello world",
+        &signature,
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn multikeys() {
+    let key = SigningKey::generate();
+    let multikey = encode_multikey(key.verifying_key());
+    assert!(multikey.starts_with("zQ3s"));
+    assert_eq!(
+        decode_multikey(&multikey).as_ref(),
+        Some(key.verifying_key())
+    );
+
+    assert_eq!(decode_multikey(&multikey[1..]), None);
+    assert_eq!(decode_multikey("z0OIl"), None);
+    // A P-256 key.
+    assert_eq!(
+        decode_multikey("zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169"),
+        None
+    );
+}
@@ -0,0 +1,775 @@
+use {
+    super::{
+        mst::MstError, BlockStore, BlockStoreError, CommitData, CommitDecodeError, ImportedRepo,
+        RecordWrite, RepoTransaction, SigningKey,
+    },
+    crate::{
+        api::xrpc::model::{Cid, Did, Nsid, RecordKey, Tid},
+        blob::{BlobStore, BlobStoreError},
+        dag_cbor::{self, Value},
+        global::database::{Connection, Queries},
+    },
+    libsql::params,
+    std::future::Future,
+};
+
+/// An error that might occur when reading or updating a repository.
+#[derive(Debug)]
+pub enum RepoError {
+    /// The repository does not exist.
+    NotFound,
+    /// The repository was modified by another commit in the meantime.
+    Conflict,
+    /// A compare-and-swap check failed: the commit or record was not the
+    /// expected one.
+    InvalidSwap {
+        /// What was checked (`Commit` or `Record`).
+        object: &'static str,
+        /// The current version of the checked object.
+        current: Option<Cid>,
+    },
+    /// The repository has no signing key.
+    MissingSigningKey,
+    /// An imported repository belongs to another account.
+    WrongDid(Did),
+    /// An imported repository is not newer than the current one, at the
+    /// provided revision.
+    OutdatedImport(Tid),
+    /// The current commit of the repository is malformed.
+    InvalidCommit(CommitDecodeError),
+    /// A record of the repository is missing from the store, or malformed.
+    InvalidRecord(Cid),
+    /// The MST of the repository could not be read or updated.
+    Mst(MstError),
+    /// A blob referenced by a record could not be made permanent.
+    Blob(BlobStoreError),
+    /// The database failed.
+    Database(libsql::Error),
+}
+
+impl std::fmt::Display for RepoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("repository not found"),
+            Self::Conflict => f.write_str("the repository was modified concurrently"),
+            Self::InvalidSwap {
+                object,
+                current: Some(cid),
+            } => write!(f, "{object} was at {cid}"),
+            Self::InvalidSwap {
+                object,
+                current: None,
+            } => write!(f, "{object} does not exist"),
+            Self::MissingSigningKey => f.write_str("the repository has no signing key"),
+            Self::WrongDid(did) => write!(f, "the repository belongs to `{did}`"),
+            Self::OutdatedImport(rev) => {
+                write!(f, "the repository is already at revision `{rev}`")
+            }
+            Self::InvalidCommit(err) => std::fmt::Display::fmt(err, f),
+            Self::InvalidRecord(cid) => write!(f, "missing or malformed record `{cid}`"),
+            Self::Mst(err) => std::fmt::Display::fmt(err, f),
+            Self::Blob(err) => std::fmt::Display::fmt(err, f),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RepoError {}
+
+impl From<MstError> for RepoError {
+    #[inline]
+    fn from(value: MstError) -> Self {
+        Self::Mst(value)
+    }
+}
+
+impl From<BlockStoreError> for RepoError {
+    #[inline]
+    fn from(value: BlockStoreError) -> Self {
+        Self::Mst(MstError::Store(value))
+    }
+}
+
+impl From<BlobStoreError> for RepoError {
+    #[inline]
+    fn from(value: BlobStoreError) -> Self {
+        Self::Blob(value)
+    }
+}
+
+impl From<libsql::Error> for RepoError {
+    #[inline]
+    fn from(value: libsql::Error) -> Self {
+        Self::Database(value)
+    }
+}
+
+/// A reference to the current commit of a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoRoot {
+    /// The CID of the commit.
+    pub cid: Cid,
+    /// The revision of the commit.
+    pub rev: Tid,
+}
+
+/// Provides access to the stored data of a single repository.
+///
+/// The connection must be one to the actor store of the account (see
+/// [`ActorStores`](crate::global::database::ActorStores)).
+pub struct RepoStorage {
+    conn: Connection,
+    did: Did,
+}
+
+impl RepoStorage {
+    /// Creates a new [`RepoStorage`] for the repository of the provided
+    /// account.
+    pub fn new(conn: Connection, did: Did) -> Self {
+        Self { conn, did }
+    }
+
+    /// Returns the DID of the account that owns the repository.
+    #[inline]
+    pub fn did(&self) -> &Did {
+        &self.did
+    }
+
+    /// Returns the current commit of the repository, or `None` if the
+    /// repository has no commit yet.
+    pub async fn root(&self) -> Result<Option<RepoRoot>, RepoError> {
+        let mut rows = self
+            .conn
+            .query("SELECT cid, rev FROM repo_root", ())
+            .await?;
+
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+
+        let cid = row
+            .get_str(0)?
+            .parse()
+            .map_err(|_| RepoError::InvalidCommit(CommitDecodeError("invalid root CID")))?;
+        let rev = row
+            .get_str(1)?
+            .parse()
+            .map_err(|_| RepoError::InvalidCommit(CommitDecodeError("invalid root revision")))?;
+
+        Ok(Some(RepoRoot { cid, rev }))
+    }
+
+    /// Returns the content of the record with the provided CID.
+    pub async fn read_record(&self, cid: &Cid) -> Result<Value, RepoError> {
+        let bytes = self
+            .get_block(cid)
+            .await?
+            .ok_or(RepoError::InvalidRecord(*cid))?;
+        dag_cbor::from_slice(&bytes).map_err(|_| RepoError::InvalidRecord(*cid))
+    }
+
+    /// Returns the collections that hold at least one record, in
+    /// lexicographic order.
+    ///
+    /// The collections are read from the `records` index, jumping from one
+    /// collection to the next instead of visiting every record.
+    pub async fn collections(&self) -> Result<Vec<Nsid>, RepoError> {
+        let rows = self
+            .conn
+            .query_all::<(Nsid,)>(
+                "WITH RECURSIVE c (name) AS ( \
+                     SELECT min(collection) FROM records \
+                     UNION ALL \
+                     SELECT (SELECT min(collection) FROM records WHERE collection > c.name) \
+                     FROM c WHERE c.name IS NOT NULL \
+                 ) \
+                 SELECT name FROM c WHERE name IS NOT NULL",
+                (),
+            )
+            .await?;
+        Ok(rows.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Returns the key used to sign the commits of the repository.
+    pub async fn signing_key(&self) -> Result<SigningKey, RepoError> {
+        let mut rows = self
+            .conn
+            .query("SELECT private_key FROM repo_signing_key", ())
+            .await?;
+
+        let row = rows.next().await?.ok_or(RepoError::MissingSigningKey)?;
+        let bytes: Vec<u8> = row.get(0)?;
+        SigningKey::from_bytes(&bytes).ok_or(RepoError::MissingSigningKey)
+    }
+
+    /// Sets the key used to sign the commits of the repository.
+    pub async fn set_signing_key(&self, key: &SigningKey) -> Result<(), RepoError> {
+        self.conn
+            .execute(
+                "INSERT INTO repo_signing_key (id, private_key) VALUES (0, ?1) \
+                 ON CONFLICT (id) DO UPDATE SET private_key = excluded.private_key",
+                params![key.to_bytes().to_vec()],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Creates the repository if it has no commit yet: a signing key is
+    /// generated, unless one was already set, and an empty genesis commit is
+    /// made.
+    ///
+    /// Concurrent calls are safe: only one genesis commit is kept, signed
+    /// with the key of the repository.
+    pub async fn init(&self, blobs: &impl BlobStore) -> Result<(), RepoError> {
+        if self.root().await?.is_some() {
+            return Ok(());
+        }
+
+        self.conn
+            .execute(
+                "INSERT INTO repo_signing_key (id, private_key) VALUES (0, ?1) \
+                 ON CONFLICT (id) DO NOTHING",
+                params![SigningKey::generate().to_bytes().to_vec()],
+            )
+            .await?;
+        let key = self.signing_key().await?;
+
+        match RepoTransaction::genesis(self).commit(&key, blobs).await {
+            Ok(_) | Err(RepoError::Conflict) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Persists the provided commit and makes it the current commit of the
+    /// repository.
+    ///
+    /// The `records` and `record_blob` indexes are updated, and the blobs
+    /// referenced by the written records are made permanent in `blobs`. This
+    /// happens while the write lock of the store is held, so that the blob
+    /// garbage collector never sees a blob that is about to be referenced.
+    ///
+    /// # Errors
+    ///
+    /// If the current commit of the repository is not [`CommitData::since`],
+    /// [`RepoError::Conflict`] is returned and nothing is written.
+    pub async fn apply_commit(
+        &self,
+        commit: &CommitData,
+        blobs: &impl BlobStore,
+    ) -> Result<(), RepoError> {
+        let tx = self.conn.transaction().await?;
+
+        let mut rows = tx.query("SELECT cid FROM repo_root", ()).await?;
+        let current = match rows.next().await? {
+            Some(row) => Some(row.get::<String>(0)?),
+            None => None,
+        };
+        drop(rows);
+
+        if current != commit.since.map(|cid| cid.to_string()) {
+            return Err(RepoError::Conflict);
+        }
+
+        for (cid, content) in commit.blocks.iter() {
+            tx.execute(
+                "INSERT OR IGNORE INTO repo_blocks (cid, repo_rev, content) VALUES (?1, ?2, ?3)",
+                params![cid.to_string(), commit.rev.to_string(), content.to_vec()],
+            )
+            .await?;
+        }
+
+        let rev = commit.rev.to_string();
+        for write in &commit.writes {
+            let (collection, rkey) = write.path.split_once('/').unwrap_or((&write.path, ""));
+
+            tx.execute(
+                "DELETE FROM record_blob WHERE collection = ?1 AND rkey = ?2",
+                params![collection, rkey],
+            )
+            .await?;
+
+            let Some(cid) = write.cid else {
+                tx.execute(
+                    "DELETE FROM records WHERE collection = ?1 AND rkey = ?2",
+                    params![collection, rkey],
+                )
+                .await?;
+                continue;
+            };
+
+            tx.execute(
+                "INSERT INTO records (collection, rkey, cid, repo_rev) VALUES (?1, ?2, ?3, ?4) \
+                 ON CONFLICT (collection, rkey) DO UPDATE \
+                 SET cid = excluded.cid, repo_rev = excluded.repo_rev",
+                params![collection, rkey, cid.to_string(), rev.as_str()],
+            )
+            .await?;
+
+            for blob in &write.blobs {
+                // Blobs are made permanent before the transaction commits,
+                // failing the commit if one is missing. Their temporary copy
+                // is left in place: if the transaction fails, the next sweep
+                // removes the unreferenced permanent blob, and the commit can
+                // still be retried until the temporary copy expires.
+                blobs.make_permanent(&self.did, blob).await?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO record_blob (blob_cid, collection, rkey) \
+                     VALUES (?1, ?2, ?3)",
+                    params![blob.to_string(), collection, rkey],
+                )
+                .await?;
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO repo_root (id, cid, rev) VALUES (0, ?1, ?2) \
+             ON CONFLICT (id) DO UPDATE SET cid = excluded.cid, rev = excluded.rev",
+            params![commit.cid.to_string(), commit.rev.to_string()],
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Replaces the whole content of the repository with an imported
+    /// repository.
+    ///
+    /// The `records` and `record_blob` indexes are rebuilt. The blobs
+    /// referenced by the imported records are not made permanent: they are
+    /// missing until they are uploaded again (see [`Self::claim_blob`]).
+    ///
+    /// # Errors
+    ///
+    /// The imported repository must belong to the account. Unless the
+    /// current repository has no records, the imported commit must also
+    /// have a newer revision than the current one.
+    pub async fn import(&self, repo: &ImportedRepo) -> Result<(), RepoError> {
+        if repo.did != self.did {
+            return Err(RepoError::WrongDid(repo.did.clone()));
+        }
+
+        let tx = self.conn.transaction().await?;
+
+        let current = tx
+            .query_opt::<(Tid, bool)>(
+                "SELECT rev, EXISTS (SELECT 1 FROM records) FROM repo_root",
+                (),
+            )
+            .await?;
+        if let Some((rev, true)) = current {
+            if rev >= repo.rev {
+                return Err(RepoError::OutdatedImport(rev));
+            }
+        }
+
+        for table in ["record_blob", "records", "repo_blocks"] {
+            tx.execute(&format!("DELETE FROM {table}"), ()).await?;
+        }
+
+        let rev = repo.rev.to_string();
+        for (cid, content) in repo.blocks.iter() {
+            tx.execute(
+                "INSERT INTO repo_blocks (cid, repo_rev, content) VALUES (?1, ?2, ?3)",
+                params![cid.to_string(), rev.as_str(), content.to_vec()],
+            )
+            .await?;
+        }
+
+        for RecordWrite { path, cid, blobs } in &repo.records {
+            // Paths were checked by `ImportedRepo::read`.
+            let (collection, rkey) = path.split_once('/').ok_or(MstError::InvalidKey)?;
+            let Some(cid) = cid else {
+                continue;
+            };
+
+            tx.execute(
+                "INSERT INTO records (collection, rkey, cid, repo_rev) VALUES (?1, ?2, ?3, ?4)",
+                params![collection, rkey, cid.to_string(), rev.as_str()],
+            )
+            .await?;
+
+            for blob in blobs {
+                tx.execute(
+                    "INSERT OR IGNORE INTO record_blob (blob_cid, collection, rkey) \
+                     VALUES (?1, ?2, ?3)",
+                    params![blob.to_string(), collection, rkey],
+                )
+                .await?;
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO repo_root (id, cid, rev) VALUES (0, ?1, ?2) \
+             ON CONFLICT (id) DO UPDATE SET cid = excluded.cid, rev = excluded.rev",
+            params![repo.cid.to_string(), rev],
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Returns the blobs referenced by the records of the repository, along
+    /// with a record referencing each of them.
+    ///
+    /// Blobs are sorted by the string form of their CID, starting after
+    /// `after`, and at most `limit` blobs are returned.
+    pub async fn blob_references(
+        &self,
+        after: &str,
+        limit: usize,
+    ) -> Result<Vec<(Cid, Nsid, RecordKey)>, RepoError> {
+        let rows = self
+            .conn
+            .query_all::<(Cid, Nsid, RecordKey)>(
+                "SELECT blob_cid, min(collection), rkey FROM record_blob \
+                 WHERE blob_cid > ?1 GROUP BY blob_cid ORDER BY blob_cid LIMIT ?2",
+                params![after, limit as i64],
+            )
+            .await?;
+        Ok(rows)
+    }
+
+    /// Returns the blobs referenced by the records of the repository that
+    /// `blobs` does not hold, along with a record referencing each of them.
+    ///
+    /// Blobs are sorted by the string form of their CID, starting after
+    /// `after`, and at most `limit` blobs are returned. The references are
+    /// scanned in batches of `limit` blobs until enough of them are found to
+    /// be missing.
+    pub async fn missing_blobs(
+        &self,
+        after: &str,
+        limit: usize,
+        blobs: &impl BlobStore,
+    ) -> Result<Vec<(Cid, Nsid, RecordKey)>, RepoError> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut missing = Vec::new();
+        let mut after = after.to_owned();
+        loop {
+            let batch = self.blob_references(&after, limit).await?;
+            let done = batch.len() < limit;
+
+            for (cid, collection, rkey) in batch {
+                after = cid.to_string();
+                if blobs.contains(&self.did, &cid).await? {
+                    continue;
+                }
+
+                missing.push((cid, collection, rkey));
+                if missing.len() == limit {
+                    return Ok(missing);
+                }
+            }
+
+            if done {
+                return Ok(missing);
+            }
+        }
+    }
+
+    /// Makes a temporary blob permanent if a record of the repository
+    /// already references it, which is the case of the blobs of imported
+    /// repositories.
+    ///
+    /// Returns whether the blob was made permanent.
+    pub async fn claim_blob(&self, cid: &Cid, blobs: &impl BlobStore) -> Result<bool, RepoError> {
+        // The write lock is held so that the blob garbage collector can't
+        // run in the meantime.
+        let tx = self.conn.transaction().await?;
+
+        let referenced = tx
+            .query_opt::<(i64,)>(
+                "SELECT 1 FROM record_blob WHERE blob_cid = ?1 LIMIT 1",
+                [cid.to_string()],
+            )
+            .await?
+            .is_some();
+        if referenced {
+            blobs.make_permanent(&self.did, cid).await?;
+        }
+
+        tx.commit().await?;
+        Ok(referenced)
+    }
+}
+
+impl BlockStore for RepoStorage {
+    fn get_block(
+        &self,
+        cid: &Cid,
+    ) -> impl Send + Future<Output = Result<Option<Vec<u8>>, BlockStoreError>> {
+        let cid = cid.to_string();
+
+        async move {
+            let mut rows = self
+                .conn
+                .query(
+                    "SELECT content FROM repo_blocks WHERE cid = ?1",
+                    params![cid],
+                )
+                .await
+                .map_err(|err| BlockStoreError(err.into()))?;
+
+            match rows.next().await {
+                Ok(Some(row)) => row
+                    .get::<Vec<u8>>(0)
+                    .map(Some)
+                    .map_err(|err| BlockStoreError(err.into())),
+                Ok(None) => Ok(None),
+                Err(err) => Err(BlockStoreError(err.into())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn import_and_claim_blobs() {
+    use {
+        super::{mst::Mst, BlockMap, Commit, RepoTransaction},
+        crate::{
+            blob::{BlobRef, MemoryBlobStore},
+            global::database::{TemporaryDatabase, ACTOR_MIGRATIONS},
+        },
+        hyper::body::Bytes,
+    };
+
+    let db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let did = Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap();
+    let storage = RepoStorage::new(db.connect().await.unwrap(), did.clone());
+    let blobs = MemoryBlobStore::new();
+    let key = SigningKey::generate();
+    storage.set_signing_key(&key).await.unwrap();
+
+    let put_temp = |did: &Did, data: &'static [u8]| {
+        let (blobs, did) = (&blobs, did.clone());
+        async move {
+            blobs
+                .put_temp(&did, futures::stream::iter([Ok(Bytes::from_static(data))]))
+                .await
+                .unwrap()
+        }
+    };
+    let other_did = Did::try_from(Box::<str>::from("did:plc:other")).unwrap();
+
+    // The current content of the repository is replaced.
+    let mut tx = RepoTransaction::genesis(&storage);
+    let text = Value::Map([("text".into(), Value::String("old".into()))].into());
+    tx.put("app.bsky.feed.like/old", &text).await.unwrap();
+    tx.commit(&key, &blobs).await.unwrap();
+
+    let image = put_temp(&other_did, b"image").await;
+    let record = Value::Map(
+        [(
+            "image".into(),
+            BlobRef {
+                cid: image.cid,
+                mime_type: "image/png".into(),
+                size: image.size,
+            }
+            .to_value(),
+        )]
+        .into(),
+    );
+    let mut blocks = BlockMap::new();
+    let record_cid = blocks.insert_value(&record);
+    let mut mst = Mst::new();
+    mst.insert(&blocks, b"app.bsky.feed.post/new", record_cid)
+        .await
+        .unwrap();
+    let data = mst.write(&mut blocks);
+    let commit = Commit::sign(did.clone(), data, Tid::next(), &key);
+    let cid = blocks.insert_value(&commit.to_value());
+    let repo = ImportedRepo {
+        did: did.clone(),
+        cid,
+        rev: commit.rev,
+        blocks,
+        records: vec![RecordWrite {
+            path: "app.bsky.feed.post/new".into(),
+            cid: Some(record_cid),
+            blobs: vec![image.cid],
+        }],
+    };
+    storage.import(&repo).await.unwrap();
+    assert_eq!(storage.root().await.unwrap().unwrap().cid, cid);
+
+    // Only newer repositories of the account replace it.
+    assert!(matches!(
+        storage.import(&repo).await,
+        Err(RepoError::OutdatedImport(rev)) if rev == repo.rev
+    ));
+
+    // Repositories without records, such as the ones made on the first
+    // write of an account, are replaced even by older revisions.
+    let fresh_db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let fresh = RepoStorage::new(fresh_db.connect().await.unwrap(), did.clone());
+    fresh.init(&blobs).await.unwrap();
+    assert!(fresh.root().await.unwrap().unwrap().rev > repo.rev);
+    fresh.import(&repo).await.unwrap();
+    assert_eq!(fresh.root().await.unwrap().unwrap().cid, cid);
+
+    let wrong = ImportedRepo {
+        did: other_did.clone(),
+        ..repo
+    };
+    assert!(matches!(
+        storage.import(&wrong).await,
+        Err(RepoError::WrongDid(did)) if did == other_did
+    ));
+
+    assert_eq!(storage.root().await.unwrap().unwrap().cid, cid);
+    let collections = storage.collections().await.unwrap();
+    assert_eq!(collections.len(), 1);
+    assert_eq!(collections[0].as_str(), "app.bsky.feed.post");
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    assert_eq!(
+        tx.get("app.bsky.feed.post/new").await.unwrap(),
+        Some(record_cid)
+    );
+    assert_eq!(tx.get("app.bsky.feed.like/old").await.unwrap(), None);
+    assert_eq!(storage.read_record(&record_cid).await.unwrap(), record);
+
+    // The blobs of the imported records are missing until the account
+    // uploads them again, and other blobs are left temporary.
+    assert!(blobs.get(&did, &image.cid).await.unwrap().is_none());
+    assert!(matches!(
+        storage.claim_blob(&image.cid, &blobs).await,
+        Err(RepoError::Blob(BlobStoreError::NotFound(_)))
+    ));
+    put_temp(&did, b"image").await;
+    assert!(storage.claim_blob(&image.cid, &blobs).await.unwrap());
+    assert!(blobs.get(&did, &image.cid).await.unwrap().is_some());
+
+    let other = put_temp(&did, b"other").await;
+    assert!(!storage.claim_blob(&other.cid, &blobs).await.unwrap());
+    assert!(blobs.get(&did, &other.cid).await.unwrap().is_none());
+}
+
+#[cfg(test)]
+async fn blob_test_repo() -> (
+    crate::global::database::TemporaryDatabase,
+    RepoStorage,
+    crate::blob::MemoryBlobStore,
+    Vec<Cid>,
+) {
+    use {
+        super::RepoTransaction,
+        crate::{
+            blob::{BlobRef, MemoryBlobStore},
+            global::database::{TemporaryDatabase, ACTOR_MIGRATIONS},
+        },
+        hyper::body::Bytes,
+    };
+
+    let db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let did = Did::try_from(Box::<str>::from("did:plc:abcdefghijklmnopqrstuvwx")).unwrap();
+    let storage = RepoStorage::new(db.connect().await.unwrap(), did);
+    let blobs = MemoryBlobStore::new();
+    let key = SigningKey::generate();
+
+    // Five blobs, each referenced by the records `a` and `b` of a
+    // collection.
+    let mut refs = Vec::new();
+    for data in [&b"one"[..], b"two", b"three", b"four", b"five"] {
+        let blob = blobs
+            .put_temp(
+                &storage.did,
+                futures::stream::iter([Ok(Bytes::copy_from_slice(data))]),
+            )
+            .await
+            .unwrap();
+        refs.push(BlobRef {
+            cid: blob.cid,
+            mime_type: "image/png".to_owned(),
+            size: blob.size,
+        });
+    }
+    let record = Value::Map(
+        [(
+            "images".to_owned(),
+            Value::List(refs.iter().map(BlobRef::to_value).collect()),
+        )]
+        .into(),
+    );
+
+    let mut tx = RepoTransaction::genesis(&storage);
+    tx.put("com.example.b/a", &record).await.unwrap();
+    tx.put("com.example.a/b", &record).await.unwrap();
+    tx.commit(&key, &blobs).await.unwrap();
+
+    let mut cids: Vec<Cid> = refs.iter().map(|blob| blob.cid).collect();
+    cids.sort_by_key(|cid| cid.to_string());
+    (db, storage, blobs, cids)
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn blob_references_pages() {
+    let (_db, storage, _, cids) = blob_test_repo().await;
+
+    // Each blob is listed once, with one of the records referencing it.
+    let page = storage.blob_references("", 2).await.unwrap();
+    assert_eq!(page.len(), 2);
+    assert_eq!(page[0].0, cids[0]);
+    assert_eq!(page[1].0, cids[1]);
+    assert_eq!(page[0].1.as_str(), "com.example.a");
+    assert_eq!(page[0].2.as_str(), "b");
+
+    let page = storage
+        .blob_references(&cids[1].to_string(), 10)
+        .await
+        .unwrap();
+    let listed: Vec<Cid> = page.iter().map(|(cid, _, _)| *cid).collect();
+    assert_eq!(listed, cids[2..]);
+
+    let last = cids[4].to_string();
+    assert!(storage.blob_references(&last, 10).await.unwrap().is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn missing_blobs_pages() {
+    let (_db, storage, blobs, cids) = blob_test_repo().await;
+
+    // Reads a page, returning its blobs and the next cursor.
+    let page = |after: String| {
+        let (storage, blobs) = (&storage, &blobs);
+        async move {
+            let missing = storage.missing_blobs(&after, 2, blobs).await.unwrap();
+            let listed: Vec<Cid> = missing.iter().map(|(cid, _, _)| *cid).collect();
+            let cursor = (listed.len() == 2).then(|| listed[1].to_string());
+            (listed, cursor)
+        }
+    };
+
+    // The blobs were made permanent by the commit.
+    assert_eq!(page(String::new()).await, (vec![], None));
+
+    // The scan goes on past batches whose blobs are all present.
+    blobs.delete(&storage.did, &cids[4]).await.unwrap();
+    assert_eq!(page(String::new()).await, (vec![cids[4]], None));
+
+    for cid in &cids[..3] {
+        blobs.delete(&storage.did, cid).await.unwrap();
+    }
+    let (listed, cursor) = page(String::new()).await;
+    assert_eq!(listed, cids[..2]);
+    let cursor = cursor.unwrap();
+    assert_eq!(cursor, cids[1].to_string());
+    let (listed, cursor) = page(cursor).await;
+    assert_eq!(listed, [cids[2], cids[4]]);
+    let (listed, cursor) = page(cursor.unwrap()).await;
+    assert_eq!((listed, cursor), (vec![], None));
+
+    assert!(storage
+        .missing_blobs("", 0, &blobs)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,446 @@
+use {
+    super::{
+        mst::{Mst, MstError},
+        BlockMap, BlockStore, Commit, CommitData, RecordWrite, RepoError, RepoRoot, RepoStorage,
+        SigningKey,
+    },
+    crate::{
+        api::xrpc::model::{Cid, Nsid, RecordKey, Tid},
+        blob::{BlobRef, BlobStore},
+        dag_cbor::Value,
+    },
+    std::ops::Bound,
+};
+
+/// A set of modifications to a repository that is turned into a single
+/// signed commit.
+///
+/// Every operation that modifies a repository must go through this type so
+/// that the current commit of the repository stays consistent.
+pub struct RepoTransaction<'a> {
+    storage: &'a RepoStorage,
+    /// The commit that was current when the transaction started.
+    since: Option<RepoRoot>,
+    /// The records of the repository.
+    mst: Mst,
+    /// The record blocks created by the transaction.
+    blocks: BlockMap,
+    /// The records written by the transaction.
+    writes: Vec<RecordWrite>,
+}
+
+impl<'a> RepoTransaction<'a> {
+    /// Starts a new transaction on top of the current commit of the
+    /// repository.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RepoError::NotFound`] if the repository has no commit yet.
+    pub async fn begin(storage: &'a RepoStorage) -> Result<Self, RepoError> {
+        let root = storage.root().await?.ok_or(RepoError::NotFound)?;
+
+        let bytes = storage
+            .get_block(&root.cid)
+            .await?
+            .ok_or(RepoError::NotFound)?;
+        let commit = Commit::decode(&bytes).map_err(RepoError::InvalidCommit)?;
+
+        Ok(Self {
+            storage,
+            since: Some(root),
+            mst: Mst::load(commit.data),
+            blocks: BlockMap::new(),
+            writes: Vec::new(),
+        })
+    }
+
+    /// Starts a transaction that creates the first commit of an empty
+    /// repository.
+    pub fn genesis(storage: &'a RepoStorage) -> Self {
+        Self {
+            storage,
+            since: None,
+            mst: Mst::new(),
+            blocks: BlockMap::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    /// Returns the commit that was current when the transaction started.
+    #[inline]
+    pub fn since(&self) -> Option<&RepoRoot> {
+        self.since.as_ref()
+    }
+
+    /// Returns whether the transaction created, updated or deleted any
+    /// record.
+    #[inline]
+    pub fn has_changes(&self) -> bool {
+        !self.writes.is_empty()
+    }
+
+    /// Returns the CID of the record stored at the provided path.
+    pub async fn get(&mut self, path: &str) -> Result<Option<Cid>, RepoError> {
+        Ok(self.mst.get(self.storage, path.as_bytes()).await?)
+    }
+
+    /// Ensures that the commit that was current when the transaction
+    /// started is `expected`, if provided.
+    ///
+    /// This implements the `swapCommit` parameter of the methods writing
+    /// records.
+    pub fn check_swap_commit(&self, expected: Option<&Cid>) -> Result<(), RepoError> {
+        let Some(expected) = expected else {
+            return Ok(());
+        };
+        let current = self.since.as_ref().map(|root| root.cid);
+        if current != Some(*expected) {
+            return Err(RepoError::InvalidSwap {
+                object: "Commit",
+                current,
+            });
+        }
+        Ok(())
+    }
+
+    /// Ensures that the record stored at the provided path is `expected`, if
+    /// provided. `Some(None)` requires that no record exists.
+    ///
+    /// This implements the `swapRecord` parameter of the methods writing
+    /// records.
+    pub async fn check_swap_record(
+        &mut self,
+        path: &str,
+        expected: Option<Option<&Cid>>,
+    ) -> Result<(), RepoError> {
+        let Some(expected) = expected else {
+            return Ok(());
+        };
+        let current = self.get(path).await?;
+        if current.as_ref() != expected {
+            return Err(RepoError::InvalidSwap {
+                object: "Record",
+                current,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the records whose path is within the provided bounds.
+    ///
+    /// See [`Mst::range`].
+    pub async fn list(
+        &mut self,
+        lower: Bound<&str>,
+        upper: Bound<&str>,
+        reverse: bool,
+        limit: usize,
+    ) -> Result<Vec<(Box<[u8]>, Cid)>, RepoError> {
+        let lower = lower.map(str::as_bytes);
+        let upper = upper.map(str::as_bytes);
+        Ok(self
+            .mst
+            .range(self.storage, lower, upper, reverse, limit)
+            .await?)
+    }
+
+    /// Returns the records of a collection, with their record key.
+    ///
+    /// Records are sorted by descending record key, or ascending when
+    /// `reverse` is set, starting right after the record key `cursor`. As
+    /// the cursor is a key rather than an offset, paging never skips or
+    /// repeats a record that was not itself written in the meantime.
+    pub async fn list_records(
+        &mut self,
+        collection: &Nsid,
+        cursor: Option<&str>,
+        reverse: bool,
+        limit: usize,
+    ) -> Result<Vec<(RecordKey, Cid)>, RepoError> {
+        // The paths of the collection sort between `<collection>/` and
+        // `<collection>0`, as `0` comes right after `/`.
+        let prefix = format!("{collection}/");
+        let end = format!("{collection}0");
+        let cursor = cursor.map(|cursor| format!("{prefix}{cursor}"));
+        let (lower, upper) = match (&cursor, reverse) {
+            (Some(cursor), true) => (
+                Bound::Excluded(cursor.as_str()),
+                Bound::Excluded(end.as_str()),
+            ),
+            (Some(cursor), false) => (
+                Bound::Included(prefix.as_str()),
+                Bound::Excluded(cursor.as_str()),
+            ),
+            (None, _) => (
+                Bound::Included(prefix.as_str()),
+                Bound::Excluded(end.as_str()),
+            ),
+        };
+
+        self.list(lower, upper, !reverse, limit)
+            .await?
+            .into_iter()
+            .map(|(path, cid)| {
+                let rkey = std::str::from_utf8(&path[prefix.len()..])
+                    .ok()
+                    .and_then(|rkey| RecordKey::try_from(Box::<str>::from(rkey)).ok())
+                    .ok_or(MstError::InvalidKey)?;
+                Ok((rkey, cid))
+            })
+            .collect()
+    }
+
+    /// Creates or replaces the record stored at the provided path.
+    ///
+    /// Returns the CID of the new record, and the CID of the record it
+    /// replaced, if any.
+    ///
+    /// Writing a record identical to the current one is not a change.
+    pub async fn put(
+        &mut self,
+        path: &str,
+        record: &Value,
+    ) -> Result<(Cid, Option<Cid>), RepoError> {
+        let cid = self.blocks.insert_value(record);
+        let prev = self.mst.insert(self.storage, path.as_bytes(), cid).await?;
+        if prev == Some(cid) {
+            return Ok((cid, prev));
+        }
+        self.writes.push(RecordWrite {
+            path: path.into(),
+            cid: Some(cid),
+            blobs: BlobRef::find_all(record)
+                .into_iter()
+                .map(|blob| blob.cid)
+                .collect(),
+        });
+        Ok((cid, prev))
+    }
+
+    /// Deletes the record stored at the provided path.
+    ///
+    /// Returns the CID of the deleted record, if any.
+    pub async fn delete(&mut self, path: &str) -> Result<Option<Cid>, RepoError> {
+        let prev = self.mst.remove(self.storage, path.as_bytes()).await?;
+        if prev.is_some() {
+            self.writes.push(RecordWrite {
+                path: path.into(),
+                cid: None,
+                blobs: Vec::new(),
+            });
+        }
+        Ok(prev)
+    }
+
+    /// Signs a new commit for the modified repository and makes it the
+    /// current commit.
+    ///
+    /// The blobs referenced by the written records are made permanent in
+    /// `blobs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RepoError::Conflict`] if another commit was applied since
+    /// the transaction started.
+    pub async fn commit(
+        mut self,
+        key: &SigningKey,
+        blobs: &impl BlobStore,
+    ) -> Result<CommitData, RepoError> {
+        let mut blocks = self.blocks;
+        let data = self.mst.write(&mut blocks);
+
+        let rev = Tid::next();
+
+        let commit = Commit::sign(self.storage.did().clone(), data, rev, key);
+        let cid = blocks.insert_value(&commit.to_value());
+
+        let commit = CommitData {
+            cid,
+            rev,
+            since: self.since.map(|root| root.cid),
+            blocks,
+            writes: self.writes,
+        };
+
+        self.storage.apply_commit(&commit, blobs).await?;
+        Ok(commit)
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn commit_flow() {
+    use crate::{
+        blob::MemoryBlobStore,
+        global::database::{TemporaryDatabase, ACTOR_MIGRATIONS},
+    };
+
+    let db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let conn = db.connect().await.unwrap();
+
+    let did = crate::api::xrpc::model::Did::try_from(Box::<str>::from(
+        "did:plc:abcdefghijklmnopqrstuvwx",
+    ))
+    .unwrap();
+
+    let storage = RepoStorage::new(conn, did);
+    let blobs = MemoryBlobStore::new();
+    let key = SigningKey::generate();
+    storage.set_signing_key(&key).await.unwrap();
+    let key = storage.signing_key().await.unwrap();
+
+    assert!(matches!(
+        RepoTransaction::begin(&storage).await,
+        Err(RepoError::NotFound)
+    ));
+
+    let genesis = RepoTransaction::genesis(&storage)
+        .commit(&key, &blobs)
+        .await
+        .unwrap();
+    assert_eq!(storage.root().await.unwrap().unwrap().cid, genesis.cid);
+
+    let record = Value::Map([("text".into(), Value::String("hello".into()))].into());
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    let (record_cid, prev) = tx
+        .put("app.bsky.feed.post/3jzfcijpj2z2a", &record)
+        .await
+        .unwrap();
+    assert_eq!(prev, None);
+    let second = tx.commit(&key, &blobs).await.unwrap();
+    assert_eq!(second.since, Some(genesis.cid));
+    assert!(second.rev > genesis.rev);
+
+    // The commit is signed and points to the new MST.
+    let bytes = storage.get_block(&second.cid).await.unwrap().unwrap();
+    let commit = Commit::decode(&bytes).unwrap();
+    assert!(commit.verify(key.verifying_key()));
+    assert_eq!(&commit.rev, &second.rev);
+
+    let mut tx = RepoTransaction::begin(&storage).await.unwrap();
+    assert_eq!(
+        tx.get("app.bsky.feed.post/3jzfcijpj2z2a").await.unwrap(),
+        Some(record_cid)
+    );
+    assert_eq!(storage.read_record(&record_cid).await.unwrap(), record);
+    let collections = || async {
+        let collections = storage.collections().await.unwrap();
+        collections
+            .iter()
+            .map(|c| c.as_str().to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(collections().await, ["app.bsky.feed.post"]);
+
+    // A transaction started before another commit can't be applied.
+    let mut stale = RepoTransaction::begin(&storage).await.unwrap();
+    tx.delete("app.bsky.feed.post/3jzfcijpj2z2a").await.unwrap();
+    tx.commit(&key, &blobs).await.unwrap();
+    stale
+        .put("app.bsky.feed.post/3jzfcijpj2z2b", &record)
+        .await
+        .unwrap();
+    assert!(matches!(
+        stale.commit(&key, &blobs).await,
+        Err(RepoError::Conflict)
+    ));
+    assert!(collections().await.is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn list_records_pages() {
+    use crate::{
+        blob::MemoryBlobStore,
+        global::database::{TemporaryDatabase, ACTOR_MIGRATIONS},
+    };
+
+    let db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let did = crate::api::xrpc::model::Did::try_from(Box::<str>::from(
+        "did:plc:abcdefghijklmnopqrstuvwx",
+    ))
+    .unwrap();
+    let storage = RepoStorage::new(db.connect().await.unwrap(), did);
+    let blobs = MemoryBlobStore::new();
+    let key = SigningKey::generate();
+    let collection = Nsid::try_from(Box::<str>::from("com.example.thing")).unwrap();
+    let record = Value::Map([("text".into(), Value::String("hello".into()))].into());
+
+    let write = |puts: &'static [&'static str], deletes: &'static [&'static str]| {
+        let (storage, blobs, key, record) = (&storage, &blobs, &key, &record);
+        async move {
+            let mut tx = match storage.root().await.unwrap() {
+                Some(_) => RepoTransaction::begin(storage).await.unwrap(),
+                None => RepoTransaction::genesis(storage),
+            };
+            for path in puts {
+                tx.put(path, record).await.unwrap();
+            }
+            for path in deletes {
+                tx.delete(path).await.unwrap();
+            }
+            tx.commit(key, blobs).await.unwrap();
+        }
+    };
+    // Reads a page, returning its record keys and the next cursor.
+    let page = |cursor: Option<String>, reverse: bool| {
+        let (storage, collection) = (&storage, &collection);
+        async move {
+            let mut tx = RepoTransaction::begin(storage).await.unwrap();
+            let records = tx
+                .list_records(collection, cursor.as_deref(), reverse, 2)
+                .await
+                .unwrap();
+            let rkeys: Vec<String> = records
+                .iter()
+                .map(|(rkey, _)| rkey.as_str().to_owned())
+                .collect();
+            let cursor = (rkeys.len() == 2).then(|| rkeys[1].clone());
+            (rkeys, cursor)
+        }
+    };
+
+    // Records of neighbouring collections surround the collection.
+    write(
+        &[
+            "com.example.thin/a",
+            "com.example.thing.sub/a",
+            "com.example.thing/k1",
+            "com.example.thing/k2",
+            "com.example.thing/k3",
+            "com.example.thing/k4",
+            "com.example.thing/k5",
+            "com.example.thinga/a",
+        ],
+        &[],
+    )
+    .await;
+
+    // Descending, while records are written before and after the cursor.
+    let (rkeys, cursor) = page(None, false).await;
+    assert_eq!(rkeys, ["k5", "k4"]);
+    write(
+        &["com.example.thing/k0", "com.example.thing/k6"],
+        &["com.example.thing/k3"],
+    )
+    .await;
+    let (rkeys, cursor) = page(cursor, false).await;
+    assert_eq!(rkeys, ["k2", "k1"]);
+    let (rkeys, cursor) = page(cursor, false).await;
+    assert_eq!(rkeys, ["k0"]);
+    assert_eq!(cursor, None);
+
+    // Ascending: when exactly `limit` records remain, the cursor leads to an
+    // empty page.
+    let (rkeys, cursor) = page(None, true).await;
+    assert_eq!(rkeys, ["k0", "k1"]);
+    write(&["com.example.thing/k3"], &["com.example.thing/k6"]).await;
+    let (rkeys, cursor) = page(cursor, true).await;
+    assert_eq!(rkeys, ["k2", "k3"]);
+    let (rkeys, cursor) = page(cursor, true).await;
+    assert_eq!(rkeys, ["k4", "k5"]);
+    let (rkeys, cursor) = page(cursor, true).await;
+    assert!(rkeys.is_empty());
+    assert_eq!(cursor, None);
+}
@@ -0,0 +1,132 @@
+use {
+    super::{CommitData, RepoError, RepoStorage, RepoTransaction, SigningKey},
+    crate::blob::BlobStore,
+    futures::future::BoxFuture,
+};
+
+/// The number of times a write is attempted when other commits are applied
+/// to the repository concurrently.
+const ATTEMPTS: usize = 3;
+
+/// Writes records to a repository, turning each write into a signed commit.
+pub struct RepoWriter<'a, B> {
+    storage: RepoStorage,
+    key: SigningKey,
+    /// The store in which the blobs referenced by the written records are
+    /// made permanent.
+    blobs: &'a B,
+}
+
+impl<'a, B: BlobStore> RepoWriter<'a, B> {
+    /// Creates a new [`RepoWriter`] for the provided repository, signing its
+    /// commits with the key of the repository.
+    pub async fn new(storage: RepoStorage, blobs: &'a B) -> Result<Self, RepoError> {
+        let key = storage.signing_key().await?;
+        Ok(Self {
+            storage,
+            key,
+            blobs,
+        })
+    }
+
+    /// Returns the storage of the repository.
+    #[inline]
+    pub fn storage(&self) -> &RepoStorage {
+        &self.storage
+    }
+
+    /// Runs `write` on a new transaction, and commits the transaction.
+    ///
+    /// If another commit was applied to the repository in the meantime, the
+    /// transaction is discarded and `write` runs again on top of the new
+    /// commit, a few times at most. Errors returned by `write` abort the
+    /// whole operation, leaving the repository untouched.
+    ///
+    /// If `write` did not change any record, nothing is committed and `None`
+    /// is returned in place of the commit.
+    pub async fn write<'w, T, E, F>(&'w self, mut write: F) -> Result<(T, Option<CommitData>), E>
+    where
+        F: for<'t> FnMut(&'t mut RepoTransaction<'w>) -> BoxFuture<'t, Result<T, E>>,
+        E: From<RepoError>,
+    {
+        let mut attempt = 1;
+        loop {
+            let mut tx = RepoTransaction::begin(&self.storage).await?;
+            let output = write(&mut tx).await?;
+            if !tx.has_changes() {
+                return Ok((output, None));
+            }
+
+            match tx.commit(&self.key, self.blobs).await {
+                Ok(commit) => return Ok((output, Some(commit))),
+                Err(RepoError::Conflict) if attempt < ATTEMPTS => attempt += 1,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn retry_on_conflict() {
+    use crate::{
+        blob::MemoryBlobStore,
+        dag_cbor::Value,
+        global::database::{TemporaryDatabase, ACTOR_MIGRATIONS},
+    };
+
+    let db = TemporaryDatabase::new(ACTOR_MIGRATIONS).await;
+    let did = crate::api::xrpc::model::Did::try_from(Box::<str>::from(
+        "did:plc:abcdefghijklmnopqrstuvwx",
+    ))
+    .unwrap();
+    let storage = RepoStorage::new(db.connect().await.unwrap(), did.clone());
+    let key = SigningKey::generate();
+    storage.set_signing_key(&key).await.unwrap();
+
+    let blobs = MemoryBlobStore::new();
+    RepoTransaction::genesis(&storage)
+        .commit(&key, &blobs)
+        .await
+        .unwrap();
+
+    let other = RepoStorage::new(db.connect().await.unwrap(), did);
+    let writer = RepoWriter::new(storage, &blobs).await.unwrap();
+    let record = Value::Map([("text".into(), Value::String("hello".into()))].into());
+
+    // Another commit is applied while the first attempt is running.
+    let (other, record, key, blobs) = (&other, &record, &key, &blobs);
+    let mut attempts = 0;
+    let (cid, commit) = writer
+        .write(|tx| {
+            attempts += 1;
+            let concurrent = attempts == 1;
+            Box::pin(async move {
+                if concurrent {
+                    let mut concurrent = RepoTransaction::begin(other).await?;
+                    concurrent.put("app.bsky.feed.post/b", record).await?;
+                    concurrent.commit(key, blobs).await?;
+                }
+                Ok::<_, RepoError>(tx.put("app.bsky.feed.post/a", record).await?.0)
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(attempts, 2);
+    assert_eq!(
+        writer.storage().root().await.unwrap().unwrap().cid,
+        commit.unwrap().cid
+    );
+
+    // Both writes made it to the repository.
+    let mut tx = RepoTransaction::begin(writer.storage()).await.unwrap();
+    assert_eq!(tx.get("app.bsky.feed.post/a").await.unwrap(), Some(cid));
+    assert!(tx.get("app.bsky.feed.post/b").await.unwrap().is_some());
+
+    // Writing the same record again changes nothing.
+    let (_, commit) = writer
+        .write(|tx| Box::pin(async move { tx.put("app.bsky.feed.post/a", record).await }))
+        .await
+        .unwrap();
+    assert!(commit.is_none());
+}